@@ -0,0 +1,33 @@
+// Headless packing: pack_request <request.json> prints the solution as JSON. The request is
+// `{ "items": [...], "options": {...} }`, the pack_items command's arguments; a request that can't
+// be packed prints the PackError instead and exits with 1.
+use pack_core::{try_pack_with_options, Item, PackingOptions};
+use serde::Deserialize;
+use std::process::ExitCode;
+
+#[derive(Deserialize)]
+struct Request {
+    items: Vec<Item>,
+    #[serde(default)]
+    options: PackingOptions,
+}
+
+fn main() -> ExitCode {
+    let Some(path) = std::env::args().nth(1) else {
+        eprintln!("usage: pack_request <request.json>");
+        return ExitCode::FAILURE;
+    };
+    let request: Request = match std::fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|text| serde_json::from_str(&text).map_err(|e| e.to_string())) {
+        Ok(request) => request,
+        Err(error) => {
+            eprintln!("{}: {}", path, error);
+            return ExitCode::FAILURE;
+        }
+    };
+    let (output, code) = match try_pack_with_options(request.items, &request.options) {
+        Ok(solution) => (serde_json::to_string(&solution), ExitCode::SUCCESS),
+        Err(error) => (serde_json::to_string(&error), ExitCode::FAILURE),
+    };
+    println!("{}", output.unwrap_or_default());
+    code
+}
@@ -0,0 +1,117 @@
+// Soft pairing of accessories with their parent item: a box already holding the item named by
+// pair_with is preferred for the accessory, but no box is opened for it. References to ids that
+// aren't in the request and pairings that loop back to themselves are only warned about.
+use crate::{Item, PackedBox};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+// Whether a paired item ended up with its parent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pairing {
+    pub item_id: String,
+    pub pair_with: String,
+    // Zero-based index into the solution's boxes; none when the item wasn't packed
+    pub box_index: Option<usize>,
+    pub satisfied: bool,
+}
+
+// Whether the box already holds the item's parent
+pub(crate) fn holds_parent(box_data: &PackedBox, item: &Item) -> bool {
+    item.pair_with
+        .as_deref()
+        .is_some_and(|parent| box_data.items.iter().any(|other| other.id == parent))
+}
+
+// Ids whose pair_with chain comes back to themselves
+fn cyclic_ids<'a>(links: &HashMap<&'a str, &'a str>) -> HashSet<&'a str> {
+    let mut cyclic = HashSet::new();
+    for &start in links.keys() {
+        let mut seen = HashSet::new();
+        let mut current = start;
+        while let Some(&next) = links.get(current) {
+            if next == start {
+                cyclic.insert(start);
+                break;
+            }
+            if !seen.insert(next) {
+                break;
+            }
+            current = next;
+        }
+    }
+    cyclic
+}
+
+fn links(items: &[Item]) -> HashMap<&str, &str> {
+    items
+        .iter()
+        .filter_map(|item| Some((item.id.as_str(), item.pair_with.as_deref()?)))
+        .collect()
+}
+
+// Dangling and circular pair_with references of the request, one warning each
+pub(crate) fn reference_warnings(items: &[Item]) -> Vec<String> {
+    let ids: HashSet<&str> = items.iter().map(|item| item.id.as_str()).collect();
+    let links = links(items);
+    let mut warnings = Vec::new();
+
+    let mut dangling: Vec<(&str, &str)> = links.iter().filter(|(_, parent)| !ids.contains(*parent)).map(|(&id, &parent)| (id, parent)).collect();
+    dangling.sort_unstable();
+    for (id, parent) in dangling {
+        warnings.push(format!("Item {} is paired with {}, which is not in the request", id, parent));
+    }
+
+    let mut cyclic: Vec<&str> = cyclic_ids(&links).into_iter().collect();
+    cyclic.sort_unstable();
+    if !cyclic.is_empty() {
+        warnings.push(format!("Items {} are paired with each other in a loop, so none of them is packed after its parent", cyclic.join(", ")));
+    }
+    warnings
+}
+
+// Move each accessory that comes before its parent to just after it, so the parent's box is there
+// to prefer; items in a pairing loop stay where they are
+pub(crate) fn follow_parents(items: &mut Vec<Item>) {
+    // Items sharing an id but not a parent could chase each other; this bounds them
+    let mut moves_left = items.len() * items.len();
+    let cyclic: HashSet<String> = {
+        let links = links(items);
+        cyclic_ids(&links).into_iter().map(String::from).collect()
+    };
+    let mut index = 0;
+    while index < items.len() {
+        let parent = match items[index].pair_with.as_deref() {
+            Some(_) if cyclic.contains(&items[index].id) => None,
+            Some(parent) => items[index + 1..].iter().position(|other| other.id == parent),
+            None => None,
+        };
+        match parent {
+            // The parent shifts down by one once the accessory is taken out
+            Some(offset) if moves_left > 0 => {
+                let item = items.remove(index);
+                items.insert(index + offset + 1, item);
+                moves_left -= 1;
+            }
+            _ => index += 1,
+        }
+    }
+}
+
+// Every paired item of the solution, in box order, then the unpacked ones
+pub fn pairings(boxes: &[PackedBox], unpacked: &[Item]) -> Vec<Pairing> {
+    let packed = boxes.iter().enumerate().flat_map(|(index, box_data)| {
+        box_data.items.iter().map(move |item| (Some(index), item, holds_parent(box_data, item)))
+    });
+    let unpacked = unpacked.iter().map(|item| (None, item, false));
+    packed
+        .chain(unpacked)
+        .filter_map(|(box_index, item, satisfied)| {
+            Some(Pairing {
+                item_id: item.id.clone(),
+                pair_with: item.pair_with.clone()?,
+                box_index,
+                satisfied,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,193 @@
+// Strategy::Auto: the packing effort fitted to each destination, so small jobs get the thorough
+// search and big ones the fast path without the caller knowing which is which. The heuristic goes
+// by the destination's item count and a rough estimate of what the local search would take, and
+// is reported per destination in PackStats.destinations.
+use crate::{
+    box_limits, debug_log, find_best_position_with_rotation, search, CandidateQuality, DestinationConstraints, Item, PackedBox,
+    PackingOptions, Strategy,
+};
+use serde::{Deserialize, Serialize};
+
+// Rough time the local search takes per pair of items, from a native release build
+const ESTIMATED_SEARCH_MS_PER_PAIR: f64 = 0.01;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Heuristic {
+    // The greedy pass on every candidate, then a branch and bound over which box each item goes in
+    Exact,
+    // The greedy pass on the extended candidates, then emptying the boxes with fewest items into the others
+    Search,
+    // The greedy pass on the basic candidates alone
+    Greedy,
+}
+
+// Where Strategy::Auto moves from one heuristic to the next
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoThresholds {
+    // Most items of a destination given the exact search (default 15)
+    pub exact_max_items: usize,
+    // Most items given the local search, and the longest it is expected to take; past either the
+    // greedy pass is left as it is (default 300 items, 1000 ms)
+    pub search_max_items: usize,
+    pub search_max_estimated_ms: f64,
+    // Box choices the exact search tries before settling for the best found (default 20000)
+    pub exact_node_limit: usize,
+}
+
+impl Default for AutoThresholds {
+    fn default() -> Self {
+        AutoThresholds {
+            exact_max_items: 15,
+            search_max_items: 300,
+            search_max_estimated_ms: 1000.0,
+            exact_node_limit: 20_000,
+        }
+    }
+}
+
+impl AutoThresholds {
+    fn heuristic(&self, item_count: usize) -> Heuristic {
+        let estimated_ms = (item_count * item_count) as f64 * ESTIMATED_SEARCH_MS_PER_PAIR;
+        if item_count <= self.exact_max_items {
+            Heuristic::Exact
+        } else if item_count <= self.search_max_items && estimated_ms <= self.search_max_estimated_ms {
+            Heuristic::Search
+        } else {
+            Heuristic::Greedy
+        }
+    }
+}
+
+// The heuristic for a destination of this many items, None unless the strategy is Auto
+pub(crate) fn heuristic(item_count: usize, options: &PackingOptions) -> Option<Heuristic> {
+    (options.strategy == Strategy::Auto).then(|| options.auto_thresholds.unwrap_or_default().heuristic(item_count))
+}
+
+// The options the destination is packed with under the heuristic; a candidate quality the caller
+// asked for is kept
+pub(crate) fn tuned_options(heuristic: Heuristic, options: &PackingOptions) -> PackingOptions {
+    let (strategy, quality) = match heuristic {
+        Heuristic::Exact => (Strategy::FreeSpaces, CandidateQuality::EmptyMaximalSpaces),
+        Heuristic::Search => (Strategy::ExtremePoints, CandidateQuality::Extended),
+        Heuristic::Greedy => (Strategy::ExtremePoints, CandidateQuality::Basic),
+    };
+    PackingOptions {
+        strategy,
+        candidate_quality: options.candidate_quality.or(Some(quality)),
+        ..options.clone()
+    }
+}
+
+// What the heuristic adds to the greedy boxes of one destination (or order)
+pub(crate) fn improve(heuristic: Heuristic, boxes: &mut Vec<PackedBox>, constraints: &DestinationConstraints, options: &PackingOptions) {
+    match heuristic {
+        Heuristic::Exact => {
+            if let Some(better) = debug_log::muted(|| pack_exact(boxes, constraints, options)) {
+                debug_log::log(debug_log::INFO, || format!("exact search: {} boxes instead of {}", better.len(), boxes.len()));
+                *boxes = better;
+            }
+        }
+        // Every success drops a box, so this ends
+        Heuristic::Search => while search::consolidate_once(boxes, options, None) {},
+        Heuristic::Greedy => {}
+    }
+}
+
+// Fewer boxes first, then less volume
+fn measure(boxes: &[PackedBox]) -> (usize, f64) {
+    (boxes.len(), boxes.iter().map(PackedBox::volume).sum())
+}
+
+fn better(a: (usize, f64), b: (usize, f64)) -> bool {
+    a.0 < b.0 || (a.0 == b.0 && a.1 < b.1)
+}
+
+struct Exact<'a> {
+    items: Vec<Item>,
+    constraints: &'a DestinationConstraints,
+    options: &'a PackingOptions,
+    template: PackedBox,
+    best: Option<Vec<PackedBox>>,
+    best_measure: (usize, f64),
+    nodes: usize,
+    node_limit: usize,
+}
+
+impl Exact<'_> {
+    // Put item `index` and the ones after it into the boxes every way the bound leaves open:
+    // into each box that takes it, or a new one. Boxes only grow, so a partial packing no better
+    // than the best is cut off.
+    fn branch(&mut self, index: usize, boxes: &mut Vec<PackedBox>) {
+        if !better(measure(boxes), self.best_measure) {
+            return;
+        }
+        let Some(item) = self.items.get(index).cloned() else {
+            self.best_measure = measure(boxes);
+            self.best = Some(boxes.clone());
+            return;
+        };
+
+        for target in 0..boxes.len() {
+            if self.nodes >= self.node_limit {
+                return;
+            }
+            self.nodes += 1;
+            let limits = box_limits(&boxes[target], self.constraints, self.options);
+            if let Some((position, rotated)) = find_best_position_with_rotation(&boxes[target], &item, &limits, self.options) {
+                let before = boxes[target].clone();
+                boxes[target].add_item(rotated, position);
+                self.branch(index + 1, boxes);
+                boxes[target] = before;
+            }
+        }
+
+        // Every new box is the same, so only one is tried
+        if self.nodes >= self.node_limit {
+            return;
+        }
+        self.nodes += 1;
+        let new_box = self.template.clone();
+        let limits = box_limits(&new_box, self.constraints, self.options);
+        if let Some((position, rotated)) = find_best_position_with_rotation(&new_box, &item, &limits, self.options) {
+            boxes.push(new_box);
+            if let Some(last) = boxes.last_mut() {
+                last.add_item(rotated, position);
+            }
+            self.branch(index + 1, boxes);
+            boxes.pop();
+        }
+    }
+}
+
+// Re-pack the items of the greedy boxes by branch and bound, the packer's own placement choosing
+// where in a box; Some only if it found fewer boxes or less volume. Catalog cartons and boxes
+// opened under an exception are left to the greedy pass.
+fn pack_exact(boxes: &[PackedBox], constraints: &DestinationConstraints, options: &PackingOptions) -> Option<Vec<PackedBox>> {
+    let first = boxes.first()?;
+    if boxes.iter().any(|b| b.spec.is_some() || b.exceptional) {
+        return None;
+    }
+    let mut items: Vec<Item> = boxes
+        .iter()
+        .flat_map(|b| b.items.iter().cloned())
+        .map(|item| Item { position: None, ..item })
+        .collect();
+    items.sort_by(|a, b| b.volume().total_cmp(&a.volume()));
+
+    let mut template = PackedBox::cut(&first.destination, first.service.as_deref(), options);
+    template.carrier = first.carrier.clone();
+    let mut search = Exact {
+        items,
+        constraints,
+        options,
+        template,
+        best: None,
+        best_measure: measure(boxes),
+        nodes: 0,
+        node_limit: options.auto_thresholds.unwrap_or_default().exact_node_limit,
+    };
+    search.branch(0, &mut Vec::new());
+    search.best
+}
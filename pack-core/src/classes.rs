@@ -0,0 +1,121 @@
+// Rows of one physical SKU under different ids. Frontends send a product as hundreds of rows whose
+// sizes and weights differ at most in the last digits of a unit conversion; those are snapped to
+// the first row's so they are identical, and a box that found no place for one row is skipped for
+// the others of its class until it changes. Every row is still placed, and reported, on its own.
+use crate::{stats, Item, PackedBox};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+// Sizes and weights this close are read as the same, in cm and kg
+const EPSILON: f64 = 1e-6;
+
+// Everything placement looks at: not the id, nor the caller data and labels carried through
+fn placement_fields(item: &Item) -> Item {
+    Item {
+        id: String::new(),
+        description: None,
+        metadata: None,
+        category: None,
+        ..item.clone()
+    }
+}
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() <= EPSILON
+}
+
+// Whether the item is its class's first row but for the conversion noise
+fn same_class(first: &Item, item: &Item) -> bool {
+    if !(close(first.length, item.length) && close(first.width, item.width) && close(first.height, item.height) &&
+        close(first.weight, item.weight))
+    {
+        return false;
+    }
+    let mut snapped = placement_fields(item);
+    snapped.length = first.length;
+    snapped.width = first.width;
+    snapped.height = first.height;
+    snapped.weight = first.weight;
+    snapped == placement_fields(first)
+}
+
+// Snap the rows of each class to its first row and number the classes, by item id (rows sharing an
+// id are identical, see validate_items)
+pub(crate) fn canonicalize(items: &mut [Item]) -> HashMap<String, usize> {
+    // Classes by their sizes and weight rounded well past the noise, so each row is only compared
+    // with a few; rows either side of a rounding boundary just don't share their searches
+    let bucket = |item: &Item| {
+        [item.length, item.width, item.height, item.weight].map(|value| (value * 1e3).round() as i64)
+    };
+    let mut firsts: Vec<Item> = Vec::new();
+    let mut buckets: HashMap<[i64; 4], Vec<usize>> = HashMap::new();
+    let mut class_of = HashMap::new();
+    for item in items.iter_mut() {
+        let candidates = buckets.entry(bucket(item)).or_default();
+        let class = match candidates.iter().copied().find(|&class| same_class(&firsts[class], item)) {
+            Some(class) => {
+                let first = &firsts[class];
+                item.length = first.length;
+                item.width = first.width;
+                item.height = first.height;
+                item.weight = first.weight;
+                class
+            }
+            None => {
+                firsts.push(item.clone());
+                candidates.push(firsts.len() - 1);
+                firsts.len() - 1
+            }
+        };
+        class_of.insert(item.id.clone(), class);
+    }
+    class_of
+}
+
+// Searches of a box for an item: for a position among its items, or re-packing it with them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Attempt {
+    Place,
+    Repack,
+}
+
+// Classes of a destination's items and the boxes each found no place in. A box only ever gains
+// items while its destination is packed, so its index and item count stand for its state.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ItemClasses {
+    class_of: Arc<HashMap<String, usize>>,
+    no_fit: HashSet<(Attempt, usize, usize, usize)>,
+}
+
+impl ItemClasses {
+    pub(crate) fn new(class_of: HashMap<String, usize>) -> Self {
+        ItemClasses {
+            class_of: Arc::new(class_of),
+            no_fit: HashSet::new(),
+        }
+    }
+
+    // Whether another row of the item's class already came out of the attempt on the box, as it
+    // is now, without a place
+    pub(crate) fn fits_nowhere_in(&self, attempt: Attempt, item: &Item, index: usize, box_data: &PackedBox) -> bool {
+        let skip = self
+            .class_of
+            .get(&item.id)
+            .is_some_and(|&class| self.no_fit.contains(&(attempt, class, index, box_data.items.len())));
+        if skip {
+            stats::record(|stats| stats.class_skips += 1);
+        }
+        skip
+    }
+
+    pub(crate) fn record_no_fit(&mut self, attempt: Attempt, item: &Item, index: usize, box_data: &PackedBox) {
+        if let Some(&class) = self.class_of.get(&item.id) {
+            self.no_fit.insert((attempt, class, index, box_data.items.len()));
+        }
+    }
+
+    // Forget the boxes, for a pass that may change them in other ways
+    pub(crate) fn reset(&mut self) {
+        self.no_fit.clear();
+    }
+}
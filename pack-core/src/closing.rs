@@ -0,0 +1,65 @@
+// Why a box stopped taking items (PackedBox.closed_reason): the check that turned down the last
+// item tried in it, or the items of its destination running out. The position search tallies the
+// rejections of the box it is searching in a thread-local, read back when it found no place.
+use crate::Rejection;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClosedReason {
+    // The item had room but would have put the box over its weight limit
+    WeightLimit,
+    // The item had room but would have put the box over its declared value cap
+    ValueCap,
+    // The box grown around the item would break a size limit, or the catalog carton is too small
+    DimensionLimit,
+    // The box grown around the item would be over max_box_volume
+    VolumeCap,
+    // No room left between the items: every position collided or lacked support
+    NoRoom,
+    // The item's hazard class may not go in with the box's, or the box holds all the hazmat it may
+    Hazmat,
+    // No item of the destination was left to try
+    InputExhausted,
+}
+
+// Closest to fitting first: a weight or value rejection means some position had room
+const PRECEDENCE: [(Rejection, ClosedReason); 7] = [
+    (Rejection::Weight, ClosedReason::WeightLimit),
+    (Rejection::Value, ClosedReason::ValueCap),
+    (Rejection::Volume, ClosedReason::VolumeCap),
+    (Rejection::Bounds, ClosedReason::DimensionLimit),
+    (Rejection::Support, ClosedReason::NoRoom),
+    (Rejection::Collision, ClosedReason::NoRoom),
+    (Rejection::Hazmat, ClosedReason::Hazmat),
+];
+
+thread_local! {
+    // One bit per rejection class seen since the search began
+    static SEEN: Cell<u8> = const { Cell::new(0) };
+}
+
+fn bit(rejection: Rejection) -> u8 {
+    1 << rejection as u8
+}
+
+// A search of an item in a box starts
+pub(crate) fn begin() {
+    SEEN.with(|seen| seen.set(0));
+}
+
+pub(crate) fn record(outcome: Result<(), Rejection>) {
+    if let Err(rejection) = outcome {
+        SEEN.with(|seen| seen.set(seen.get() | bit(rejection)));
+    }
+}
+
+// Why the search found no place; with no position tried, no orientation was within the limits
+pub(crate) fn reason() -> ClosedReason {
+    let seen = SEEN.with(Cell::get);
+    PRECEDENCE
+        .iter()
+        .find(|(rejection, _)| seen & bit(*rejection) != 0)
+        .map_or(ClosedReason::DimensionLimit, |(_, reason)| *reason)
+}
@@ -0,0 +1,69 @@
+// Destination constraints loaded from a user-editable JSON file. The app keeps the loaded table and
+// hands it to each request as PackingOptions.configured_destinations, so the engine holds no state
+// of its own and every lookup of one request reads the same table.
+use crate::{DestinationConstraints, PackError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+pub const DESTINATION_CONFIG_FILE: &str = "destinations.json";
+
+pub type DestinationTable = HashMap<String, DestinationConstraints>;
+
+// Outcome of the last attempt to load the config file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DestinationConfigStatus {
+    pub path: Option<String>,
+    // Whether a config file was found and parsed
+    pub loaded: bool,
+    // Destinations whose constraints come from the file
+    pub destinations: Vec<String>,
+    pub error: Option<String>,
+}
+
+// A loaded config file, shared by the requests packed while it is in effect
+#[derive(Debug, Clone, Default)]
+pub struct DestinationConfig {
+    pub status: DestinationConfigStatus,
+    pub table: Arc<DestinationTable>,
+}
+
+// The entries of the config file; None without one
+pub fn read_destination_config(path: &Path) -> Result<Option<DestinationTable>, PackError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let invalid = |reason: String| PackError::InvalidConfig {
+        path: path.display().to_string(),
+        reason,
+    };
+    let text = std::fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+    serde_json::from_str(&text).map(Some).map_err(|e| invalid(e.to_string()))
+}
+
+// (Re)load the config file. A missing file or a parse error leaves only the built-in table active,
+// the error kept in the status for the UI.
+pub fn load_destination_config(path: &Path) -> DestinationConfig {
+    let mut status = DestinationConfigStatus {
+        path: Some(path.display().to_string()),
+        ..Default::default()
+    };
+    let table = match read_destination_config(path) {
+        Ok(Some(table)) => {
+            status.loaded = true;
+            table
+        }
+        Ok(None) => DestinationTable::new(),
+        Err(error) => {
+            status.error = Some(error.to_string());
+            DestinationTable::new()
+        }
+    };
+    status.destinations = table.keys().cloned().collect();
+    status.destinations.sort();
+    DestinationConfig {
+        status,
+        table: Arc::new(table),
+    }
+}
@@ -0,0 +1,86 @@
+// Load plan for a full container or truck: every box of the solution floor-loaded into one fixed
+// interior, across destinations. Built on the pallet stacking, with the container as the only
+// carton in stock. Expects the solution in cm and kg.
+use crate::pallet::{box_as_item, placed_boxes, stack_boxes, PlacedBox, UnplacedBox};
+use crate::{BoxSpec, PackingSolution};
+use serde::{Deserialize, Serialize};
+
+// Every box goes into this one load, whatever its destination
+const LOAD_KEY: &str = "container";
+
+// Interior of the container; the default is a 20-foot dry container
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContainerSpec {
+    pub length: f64,
+    pub width: f64,
+    pub height: f64,
+    pub max_payload_kg: f64,
+    // Most weight that may rest on any one box; unlimited when absent
+    pub max_stack_weight_kg: Option<f64>,
+}
+
+impl Default for ContainerSpec {
+    fn default() -> Self {
+        ContainerSpec {
+            length: 589.0,
+            width: 235.0,
+            height: 239.0,
+            max_payload_kg: 28_200.0,
+            max_stack_weight_kg: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadPlan {
+    pub boxes: Vec<PlacedBox>,
+    // Outer volume of the loaded boxes over the interior volume, in percent
+    pub fill_percent: f64,
+    pub total_weight: f64,
+    // Loaded weight over the payload, in percent
+    pub weight_percent: f64,
+    // Boxes left out: too big or heavy for the container, or not fitting next to the others
+    pub unplaced: Vec<UnplacedBox>,
+}
+
+fn percent(part: f64, whole: f64) -> f64 {
+    if whole > 0.0 {
+        part / whole * 100.0
+    } else {
+        0.0
+    }
+}
+
+pub fn load_plan(solution: &PackingSolution, container: &ContainerSpec) -> LoadPlan {
+    let interior = BoxSpec {
+        name: LOAD_KEY.to_string(),
+        length: container.length,
+        width: container.width,
+        height: container.height,
+        max_weight: Some(container.max_payload_kg),
+        available: Some(1),
+        cost: 0.0,
+    };
+    let items = (0..solution.boxes.len())
+        .map(|index| {
+            let mut item = box_as_item(solution, index);
+            item.destination = LOAD_KEY.to_string();
+            item.max_load_kg = container.max_stack_weight_kg;
+            item
+        })
+        .collect();
+    // Boxes rest fully on the floor or on other boxes
+    let (loads, unplaced, indices) = stack_boxes(items, &interior, Some(0.0));
+
+    let boxes: Vec<PlacedBox> = loads.iter().flat_map(|load| placed_boxes(load, &indices)).collect();
+    let volume: f64 = boxes.iter().map(|b| b.dimensions.0 * b.dimensions.1 * b.dimensions.2).sum();
+    let total_weight: f64 = boxes.iter().map(|b| b.weight).sum();
+    LoadPlan {
+        fill_percent: percent(volume, container.length * container.width * container.height),
+        weight_percent: percent(total_weight, container.max_payload_kg),
+        total_weight,
+        boxes,
+        unplaced,
+    }
+}
@@ -0,0 +1,125 @@
+// Debug log of a packing run, collected into PackingSolution.debug_log when
+// PackingOptions.debug_level is set. Lines go into a thread-local like the stats; a global count
+// of runs that are logging lets every call site skip even the thread-local lookup, and the message
+// is only formatted when its level is on.
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Destinations, their groups and the improvement passes
+pub(crate) const INFO: u8 = 1;
+// Where each item went and how that was decided, boxes opened
+pub(crate) const DEBUG: u8 = 2;
+// Every candidate position turned down or scored
+pub(crate) const TRACE: u8 = 3;
+
+// Lines kept per run; the rest are only counted, so a long search can't grow the solution to megabytes
+const MAX_LINES: usize = 2000;
+
+static LOGGING: AtomicUsize = AtomicUsize::new(0);
+
+struct Log {
+    level: u8,
+    // Spans entered and not left, for the indentation
+    depth: usize,
+    lines: Vec<String>,
+    dropped: usize,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Log>> = const { RefCell::new(None) };
+}
+
+// Start logging on this thread up to the level, discarding anything left from an earlier run
+pub(crate) fn begin(level: u8) {
+    let log = Log {
+        level,
+        depth: 0,
+        lines: Vec::new(),
+        dropped: 0,
+    };
+    if CURRENT.with(|current| current.borrow_mut().replace(log)).is_none() {
+        LOGGING.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+// Stop logging and return the lines, with a note of any that didn't fit
+pub(crate) fn finish() -> Vec<String> {
+    let Some(log) = CURRENT.with(|current| current.borrow_mut().take()) else {
+        return Vec::new();
+    };
+    LOGGING.fetch_sub(1, Ordering::Relaxed);
+    let mut lines = log.lines;
+    if log.dropped > 0 {
+        lines.push(format!("... {} more lines dropped", log.dropped));
+    }
+    lines
+}
+
+pub(crate) fn enabled(level: u8) -> bool {
+    LOGGING.load(Ordering::Relaxed) > 0 &&
+        CURRENT.with(|current| current.borrow().as_ref().is_some_and(|log| level <= log.level))
+}
+
+// Add a line at the level if this thread's run logs it
+pub(crate) fn log(level: u8, message: impl FnOnce() -> String) {
+    if !enabled(level) {
+        return;
+    }
+    let message = message();
+    CURRENT.with(|current| {
+        if let Some(log) = current.borrow_mut().as_mut() {
+            if log.lines.len() < MAX_LINES {
+                log.lines.push(format!("{}{}", "  ".repeat(log.depth), message));
+            } else {
+                log.dropped += 1;
+            }
+        }
+    });
+}
+
+// Run without logging anything, for the many re-packs of the search that only its outcome is
+// worth a line of
+pub(crate) fn muted<R>(run: impl FnOnce() -> R) -> R {
+    if LOGGING.load(Ordering::Relaxed) == 0 {
+        return run();
+    }
+    let set_level = |level: Option<u8>| {
+        CURRENT.with(|current| {
+            current.borrow_mut().as_mut().map(|log| std::mem::replace(&mut log.level, level.unwrap_or(0)))
+        })
+    };
+    let level = set_level(None);
+    let result = run();
+    set_level(level);
+    result
+}
+
+// Lines logged while the span is alive are indented under its own
+pub(crate) struct Span {
+    entered: bool,
+}
+
+pub(crate) fn span(level: u8, message: impl FnOnce() -> String) -> Span {
+    if !enabled(level) {
+        return Span { entered: false };
+    }
+    log(level, message);
+    CURRENT.with(|current| {
+        if let Some(log) = current.borrow_mut().as_mut() {
+            log.depth += 1;
+        }
+    });
+    Span { entered: true }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        if self.entered {
+            CURRENT.with(|current| {
+                if let Some(log) = current.borrow_mut().as_mut() {
+                    log.depth = log.depth.saturating_sub(1);
+                }
+            });
+        }
+    }
+}
@@ -0,0 +1,226 @@
+// Exact shortfalls for items rejected as too big or heavy: how far each limit would have to move
+// for the item's best orientation, and which other destinations and carriers would take it as it is.
+// Found in cm, cm³ and kg and converted to the request's units with the rest of the solution.
+use crate::{
+    active_destination_constraints, carrier_for_destination, carton_weight, clearance, destination_constraints,
+    dunnage_weight, effective_constraints, insured, known_carriers, length_plus_girth, own_box_size, reservation, same_destination,
+    DestinationConstraints, Dimensions, Item, PackingOptions, DEFAULT_DESTINATION_KEY,
+};
+use crate::units::Units;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConstraintGap {
+    // DestinationConstraints field, or "box_length" / "box_width" / "box_height" for alternative_dimensions
+    pub constraint: String,
+    // Smallest limit that would let the item through
+    pub needed: f64,
+    pub limit: f64,
+    // "needs max dimension ≥ 71.2 cm (limit 63.0)"
+    pub message: String,
+}
+
+fn gap(constraint: &str, needed: f64, limit: f64) -> Option<ConstraintGap> {
+    (needed > limit).then(|| ConstraintGap {
+        constraint: constraint.to_string(),
+        needed,
+        limit,
+        message: gap_message(constraint, needed, limit, Units::default()),
+    })
+}
+
+// The message of a gap, its needed and limit in these units
+pub(crate) fn gap_message(constraint: &str, needed: f64, limit: f64, units: Units) -> String {
+    let (length, weight) = (units.length.label(), units.weight.label());
+    match constraint {
+        "max_box_dimension" => format!("needs max dimension ≥ {:.1} {} (limit {:.1})", needed, length, limit),
+        "max_length_plus_girth" => format!("needs length plus girth ≥ {:.1} {} (limit {:.1})", needed, length, limit),
+        "max_dimension_sum" => format!("needs dimension sum ≥ {:.1} {} (limit {:.1})", needed, length, limit),
+        "max_box_volume" => format!("needs volume ≥ {:.0} {}³ (limit {:.0})", needed, length, limit),
+        "max_box_weight" => format!("weighs {:.1} {} packed (limit {:.1})", needed, weight, limit),
+        "max_box_value" => format!("declares {:.2} in value (limit {:.2})", needed, limit),
+        // box_length, box_width or box_height of alternative_dimensions
+        side => format!("needs {} ≥ {:.1} {} (limit {:.1})", side.replace('_', " "), needed, length, limit),
+    }
+}
+
+// Every limit the item in this orientation breaks, packed alone in a box of its own with the
+// reserved items
+fn rotation_gaps(item: &Item, constraints: &DestinationConstraints, clearance: [f64; 3], options: &PackingOptions) -> Vec<ConstraintGap> {
+    let (length, width, height) = own_box_size(item, clearance, options);
+    let reserved = reservation(options).unwrap_or_default();
+    let mut gaps = Vec::new();
+
+    match constraints.alternative_dimensions {
+        Some(Dimensions { length: max_length, width: max_width, height: max_height }) => {
+            for (name, needed, limit) in [
+                ("box_length", length, max_length),
+                ("box_width", width, max_width),
+                ("box_height", height, max_height),
+            ] {
+                gaps.extend(gap(name, needed, limit));
+            }
+        }
+        None => gaps.extend(gap("max_box_dimension", length.max(width).max(height), constraints.max_box_dimension)),
+    }
+    if let Some(limit) = constraints.max_length_plus_girth {
+        gaps.extend(gap("max_length_plus_girth", length_plus_girth(length, width, height), limit));
+    }
+    if let Some(limit) = constraints.max_dimension_sum {
+        gaps.extend(gap("max_dimension_sum", length + width + height, limit));
+    }
+    if let Some(limit) = constraints.max_box_volume {
+        gaps.extend(gap("max_box_volume", length * width * height, limit));
+    }
+
+    // With its own carton and dunnage, which a cut-to-fit box adds
+    let gross = item.weight +
+        reserved.weight +
+        carton_weight(&options.material, length, width, height) +
+        dunnage_weight(options.dunnage.as_ref(), (length, width, height), item.volume());
+    gaps.extend(gap("max_box_weight", gross, constraints.max_box_weight));
+    if let Some(limit) = constraints.max_box_value {
+        gaps.extend(gap("max_box_value", item.declared_value() + reserved.value, limit));
+    }
+    gaps
+}
+
+// How far over its limits the orientation is, summed relative to each limit
+fn overshoot(gaps: &[ConstraintGap]) -> f64 {
+    gaps.iter()
+        .map(|gap| if gap.limit > 0.0 { gap.needed / gap.limit - 1.0 } else { f64::INFINITY })
+        .sum()
+}
+
+// Shortfalls of the item at its destination for the permitted orientation that misses by the
+// least, with that orientation (a ROTATIONS index)
+pub(crate) fn constraint_gaps(item: &Item, options: &PackingOptions) -> (Vec<ConstraintGap>, Option<u8>) {
+    let service = item.service.as_deref();
+    let constraints = effective_constraints(&item.destination, service, options);
+    let clearance = clearance(&item.destination, service, options);
+    item.allowed_rotations()
+        .into_iter()
+        .map(|rotation| {
+            let rotated = item.with_rotation(rotation);
+            (rotation_gaps(&rotated, &constraints, clearance, options), rotated.rotation)
+        })
+        .min_by(|a, b| overshoot(&a.0).total_cmp(&overshoot(&b.0)))
+        .map_or((Vec::new(), None), |(gaps, rotation)| (gaps, Some(rotation)))
+}
+
+// Known destinations, built-in, configured and custom, by name
+fn known_destinations(options: &PackingOptions) -> Vec<String> {
+    let mut names: Vec<String> = active_destination_constraints(&options.configured_destinations)
+        .into_keys()
+        .chain(options.custom_destinations.keys().cloned())
+        .filter(|name| name != DEFAULT_DESTINATION_KEY)
+        .collect();
+    names.sort();
+    names.dedup_by(|a, b| same_destination(a, b));
+    names
+}
+
+// Whether the item fits the limits alone in a box of its own, in some permitted orientation
+fn takes_item(item: &Item, constraints: &DestinationConstraints, clearance: [f64; 3], options: &PackingOptions) -> bool {
+    item.allowed_rotations()
+        .into_iter()
+        .any(|rotation| rotation_gaps(&item.with_rotation(rotation), constraints, clearance, options).is_empty())
+}
+
+// Known destinations other than the item's own that would take it in a box of its own, by name
+pub(crate) fn accepting_destinations(item: &Item, options: &PackingOptions) -> Vec<String> {
+    known_destinations(options)
+        .into_iter()
+        .filter(|name| !same_destination(name, &item.destination))
+        .filter(|name| {
+            let service = item.service.as_deref();
+            takes_item(item, &effective_constraints(name, service, options), clearance(name, service, options), options)
+        })
+        .collect()
+}
+
+// Most lanes suggested for one item
+const MAX_LANE_SUGGESTIONS: usize = 5;
+
+// A destination and carrier whose limits take an item its own lane refused
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LaneSuggestion {
+    pub destination: String,
+    // None: the destination's own limits, without a carrier's on top
+    pub carrier: Option<String>,
+    // "another carrier", "another destination" or "another destination and carrier"
+    pub change: String,
+}
+
+// How different two sets of limits are, 0 for the same longest side and weight limit
+fn limits_distance(a: &DestinationConstraints, b: &DestinationConstraints) -> f64 {
+    let relative = |x: f64, y: f64| if x.max(y) > 0.0 { (x - y).abs() / x.max(y) } else { 0.0 };
+    let longest = |limits: &DestinationConstraints| {
+        let (length, width, height) = limits.axis_limits();
+        length.max(width).max(height)
+    };
+    relative(longest(a), longest(b)) + relative(a.max_box_weight, b.max_box_weight)
+}
+
+// Lanes that would take the item alone in a box, at most a handful: another carrier to the same
+// destination first, then other destinations with the carrier the request gives them, then with
+// any other carrier; within each, the limits nearest the item's own lane first. Only worth the
+// full table walk for rejected items.
+pub(crate) fn suggest_lanes(item: &Item, options: &PackingOptions) -> Vec<LaneSuggestion> {
+    let service = item.service.as_deref();
+    let own_lane = effective_constraints(&item.destination, service, options);
+    let own_carrier = carrier_for_destination(&item.destination, options);
+    let carriers = known_carriers(options);
+    let carrier_names: Vec<Option<&str>> =
+        std::iter::once(None).chain(carriers.iter().map(|carrier| Some(carrier.name.as_str()))).collect();
+    let same_carrier = |a: Option<&str>, b: Option<&str>| match (a, b) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        (a, b) => a.is_none() && b.is_none(),
+    };
+
+    let mut lanes: Vec<(u8, f64, LaneSuggestion)> = Vec::new();
+    for destination in known_destinations(options) {
+        let same_place = same_destination(&destination, &item.destination);
+        let request_carrier = carrier_for_destination(&destination, options);
+        let base = insured(destination_constraints(&destination, service, options), options);
+        let clearance = clearance(&destination, service, options);
+        for &carrier in &carrier_names {
+            let rank = match (same_place, same_carrier(carrier, request_carrier)) {
+                (true, true) => continue,
+                (true, false) => 0,
+                (false, true) => 1,
+                (false, false) => 2,
+            };
+            let limits = match carrier.and_then(|name| carriers.iter().find(|profile| profile.name == name)) {
+                Some(profile) => base.intersect_carrier(profile),
+                None => base.clone(),
+            };
+            if !takes_item(item, &limits, clearance, options) {
+                continue;
+            }
+            let change = if same_place {
+                "another carrier"
+            } else if same_carrier(carrier, own_carrier) {
+                "another destination"
+            } else {
+                "another destination and carrier"
+            };
+            lanes.push((
+                rank,
+                limits_distance(&limits, &own_lane),
+                LaneSuggestion {
+                    destination: destination.clone(),
+                    carrier: carrier.map(str::to_string),
+                    change: change.to_string(),
+                },
+            ));
+        }
+    }
+
+    lanes.sort_by(|a, b| {
+        a.0.cmp(&b.0)
+            .then(a.1.total_cmp(&b.1))
+            .then_with(|| a.2.destination.cmp(&b.2.destination))
+    });
+    lanes.into_iter().take(MAX_LANE_SUGGESTIONS).map(|(_, _, lane)| lane).collect()
+}
@@ -0,0 +1,76 @@
+// Errors the pack_items command hands back to the UI instead of a solution
+use serde::Serialize;
+use std::fmt;
+
+// Serialized as `{ "kind": "<tag>", ...fields }`. The frontend branches on `kind`, so the tags
+// below are part of the command's contract and must not change.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind")]
+pub enum PackError {
+    // "invalid_item": a field of the item can't be packed (non-positive size, negative weight, ...)
+    #[serde(rename = "invalid_item")]
+    InvalidItem { id: String, field: String, reason: String },
+    // "invalid_option": an option can't be used as given (a label template with an unknown placeholder, ...)
+    #[serde(rename = "invalid_option")]
+    InvalidOption { field: String, reason: String },
+    // "unknown_destination": no constraints for the destination, with strict_destinations set
+    #[serde(rename = "unknown_destination")]
+    UnknownDestination { name: String },
+    // "duplicate_id": items share an id but not their dimensions, weight or destination
+    #[serde(rename = "duplicate_id")]
+    DuplicateId { id: String },
+    // "too_many_destinations": more distinct destinations than PackingOptions.max_destinations,
+    // likely an import mistake; the likeliest typos first (unknown, then fewest items)
+    #[serde(rename = "too_many_destinations")]
+    TooManyDestinations {
+        count: usize,
+        limit: usize,
+        destinations: Vec<DestinationCount>,
+    },
+    // "reserved_item_unplaced": a box had no room left for one of PackingOptions.reserved_items,
+    // though the room was held back for them; a packer bug rather than bad input
+    #[serde(rename = "reserved_item_unplaced")]
+    ReservedItemUnplaced { id: String },
+    // "invalid_placement": place_item can't put the item there (no such box, a collision, over a
+    // limit, ...)
+    #[serde(rename = "invalid_placement")]
+    InvalidPlacement { reason: String },
+    // "invalid_config": the destination config file can't be read or parsed
+    #[serde(rename = "invalid_config")]
+    InvalidConfig { path: String, reason: String },
+    // "empty_input": the request has no items
+    #[serde(rename = "empty_input")]
+    EmptyInput,
+    // "internal_error": the packer failed on valid input; the message is meant for bug reports
+    #[serde(rename = "internal_error")]
+    InternalError { message: String },
+}
+
+// A destination of the request as it was spelled first, and its items
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DestinationCount {
+    pub destination: String,
+    pub items: usize,
+}
+
+impl fmt::Display for PackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackError::InvalidItem { id, field, reason } => write!(f, "Item \"{}\": {} {}", id, field, reason),
+            PackError::InvalidOption { field, reason } => write!(f, "Option {} {}", field, reason),
+            PackError::UnknownDestination { name } => write!(f, "Unknown destination \"{}\"", name),
+            PackError::DuplicateId { id } => write!(f, "Items with id \"{}\" differ", id),
+            PackError::TooManyDestinations { count, limit, destinations } => {
+                let listed: Vec<String> = destinations.iter().map(|d| format!("\"{}\" ({})", d.destination, d.items)).collect();
+                write!(f, "{} destinations, over the limit of {}: {}", count, limit, listed.join(", "))
+            }
+            PackError::ReservedItemUnplaced { id } => write!(f, "Reserved item \"{}\" did not fit the room held back for it", id),
+            PackError::InvalidPlacement { reason } => write!(f, "{}", reason),
+            PackError::InvalidConfig { path, reason } => write!(f, "Config file {}: {}", path, reason),
+            PackError::EmptyInput => write!(f, "No items to pack"),
+            PackError::InternalError { message } => write!(f, "Internal error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for PackError {}
@@ -0,0 +1,227 @@
+// Why each item was placed where it was, recorded when PackingOptions.explain is set. The position
+// search writes into a thread-local scratch for the item being packed, which is empty unless a run
+// asked for traces; the packer keeps the trace of the decision it actually made.
+use crate::{PackedBox, Position, Rejection};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+
+// Candidate positions turned down, by check
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Rejections {
+    pub bounds: u32,
+    pub collision: u32,
+    // Overhang, stacking limits, and liquid or squashed-item rules
+    pub support: u32,
+    pub weight: u32,
+    // Declared value cap
+    #[serde(default)]
+    pub value: u32,
+    // Hazmat rules
+    #[serde(default)]
+    pub hazmat: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoredPosition {
+    pub position: Position,
+    // Weighted score with placement weights, volume the box grows by (cube-weighted) with the cube
+    // bias, otherwise 0 as the first fit wins
+    pub growth: f64,
+    // Box surface area with the item in, the tie-break
+    pub surface_area: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RotationTrial {
+    // ROTATIONS index relative to the entered dimensions
+    pub rotation: u8,
+    // Tried at its compressed height
+    pub squashed: bool,
+    // Over the box's limits on its own, so no position was tried
+    pub exceeds_limits: bool,
+    pub candidate_positions: u32,
+    pub rejected: Rejections,
+    // Where this rotation would go, if anywhere
+    pub best: Option<ScoredPosition>,
+}
+
+// The search in one box
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoxAttempt {
+    // Index among the destination's boxes at the time
+    pub box_index: usize,
+    pub rotations: Vec<RotationTrial>,
+    // The winning rotation's position, if the item fit
+    pub placed: Option<ScoredPosition>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    // Lowest score across all existing boxes
+    BestExistingBox,
+    // First existing box it fit in
+    FirstExistingBox,
+    // An existing box was re-packed from scratch to take it
+    RepackedBox,
+    // Nothing open could take it
+    NewBox,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlacementTrace {
+    pub item_id: String,
+    pub destination: String,
+    pub decision: Decision,
+    // Where the item ended up in PackingSolution.boxes; None when a later pass moved it
+    pub box_index: Option<usize>,
+    pub slot_index: Option<usize>,
+    pub position: Position,
+    pub rotation: u8,
+    // Summed over every box tried
+    pub candidate_positions: u32,
+    pub attempts: Vec<BoxAttempt>,
+    // Surface area of the chosen box before and after the item went in
+    pub surface_before: f64,
+    pub surface_after: f64,
+}
+
+#[derive(Default)]
+struct Scratch {
+    // Box address and search of every box tried for the current item
+    attempts: Vec<(usize, BoxAttempt)>,
+    trial: Option<RotationTrial>,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Scratch>> = const { RefCell::new(None) };
+}
+
+// Start recording on this thread
+pub(crate) fn begin() {
+    CURRENT.with(|current| *current.borrow_mut() = Some(Scratch::default()));
+}
+
+pub(crate) fn finish() {
+    CURRENT.with(|current| *current.borrow_mut() = None);
+}
+
+pub(crate) fn active() -> bool {
+    CURRENT.with(|current| current.borrow().is_some())
+}
+
+fn with_scratch(update: impl FnOnce(&mut Scratch)) {
+    CURRENT.with(|current| {
+        if let Some(scratch) = current.borrow_mut().as_mut() {
+            update(scratch);
+        }
+    });
+}
+
+// A new item: forget the searches made for the previous one
+pub(crate) fn begin_item() {
+    with_scratch(|scratch| *scratch = Scratch::default());
+}
+
+pub(crate) fn open_trial(rotation: u8, squashed: bool) {
+    with_scratch(|scratch| {
+        scratch.trial = Some(RotationTrial {
+            rotation,
+            squashed,
+            exceeds_limits: false,
+            candidate_positions: 0,
+            rejected: Rejections::default(),
+            best: None,
+        })
+    });
+}
+
+pub(crate) fn record_candidate(outcome: Result<(), Rejection>) {
+    with_scratch(|scratch| {
+        if let Some(trial) = scratch.trial.as_mut() {
+            trial.candidate_positions += 1;
+            match outcome {
+                Ok(()) => {}
+                Err(Rejection::Bounds | Rejection::Volume) => trial.rejected.bounds += 1,
+                Err(Rejection::Collision) => trial.rejected.collision += 1,
+                Err(Rejection::Support) => trial.rejected.support += 1,
+                Err(Rejection::Weight) => trial.rejected.weight += 1,
+                Err(Rejection::Value) => trial.rejected.value += 1,
+                Err(Rejection::Hazmat) => trial.rejected.hazmat += 1,
+            }
+        }
+    });
+}
+
+// A search of the item in the box starts
+pub(crate) fn open_attempt(box_data: &PackedBox) {
+    let address = box_data as *const PackedBox as usize;
+    with_scratch(|scratch| {
+        scratch.attempts.push((
+            address,
+            BoxAttempt {
+                box_index: 0,
+                rotations: Vec::new(),
+                placed: None,
+            },
+        ))
+    });
+}
+
+// End the open rotation trial, filed under the current search
+pub(crate) fn close_trial(exceeds_limits: bool, best: Option<ScoredPosition>) {
+    with_scratch(|scratch| {
+        let (Some(mut trial), Some((_, attempt))) = (scratch.trial.take(), scratch.attempts.last_mut()) else {
+            return;
+        };
+        trial.exceeds_limits = exceeds_limits;
+        trial.best = best;
+        attempt.rotations.push(trial);
+    });
+}
+
+// The current search is over, with the winning position if there is one
+pub(crate) fn close_attempt(placed: Option<ScoredPosition>) {
+    with_scratch(|scratch| {
+        if let Some((_, attempt)) = scratch.attempts.last_mut() {
+            attempt.placed = placed;
+        }
+    });
+}
+
+// Searches of the current item in the given boxes, numbered by their index there; searches of
+// copies and scratch boxes are dropped
+pub(crate) fn take_attempts(boxes: &[PackedBox]) -> Vec<BoxAttempt> {
+    let mut attempts = Vec::new();
+    with_scratch(|scratch| {
+        for (address, mut attempt) in scratch.attempts.drain(..) {
+            if let Some(index) = boxes.iter().position(|b| b as *const PackedBox as usize == address) {
+                attempt.box_index = index;
+                attempts.push(attempt);
+            }
+        }
+    });
+    attempts
+}
+
+// Point every trace at the item's box and slot in the final boxes, matching on id, position and
+// rotation; items a later pass moved stay unlocated
+pub(crate) fn locate(traces: &mut [PlacementTrace], boxes: &[PackedBox]) {
+    let mut taken = vec![Vec::new(); boxes.len()];
+    for trace in traces.iter_mut() {
+        let found = boxes.iter().enumerate().find_map(|(box_index, box_data)| {
+            let slot = box_data.items.iter().enumerate().position(|(slot, item)| {
+                item.id == trace.item_id &&
+                    item.rotation == trace.rotation &&
+                    item.position == Some(trace.position) &&
+                    !taken[box_index].contains(&slot)
+            })?;
+            Some((box_index, slot))
+        });
+        if let Some((box_index, slot)) = found {
+            taken[box_index].push(slot);
+            trace.box_index = Some(box_index);
+            trace.slot_index = Some(slot);
+        }
+    }
+}
@@ -0,0 +1,115 @@
+// Customs declaration rows (CN22 / commercial invoice) per box
+use super::{box_label, csv_row, format::NumberFormat, metadata_value};
+use crate::{Item, PackedBox, PackingSolution};
+
+const HEADER: [&str; 12] = [
+    "box_label",
+    "destination",
+    "row_type",
+    "item_id",
+    "description",
+    "quantity",
+    "unit_value",
+    "total_value",
+    "net_weight_kg",
+    "tare_weight_kg",
+    "gross_weight_kg",
+    "flags",
+];
+
+// The units of one SKU within one box (see PackedBox::contents), declared as a single line
+struct CustomsLine<'a> {
+    first_item: Option<&'a Item>,
+    sku: String,
+    description: Option<String>,
+    total_value: Option<f64>,
+    quantity: u32,
+    net_weight: f64,
+}
+
+impl CustomsLine<'_> {
+    fn unit_value(&self) -> Option<f64> {
+        self.total_value.map(|value| value / self.quantity.max(1) as f64)
+    }
+
+    // Missing data is flagged rather than emitted as blank cells that look intentional
+    fn flags(&self) -> String {
+        let mut flags = Vec::new();
+        if self.description.as_deref().is_none_or(|d| d.trim().is_empty()) {
+            flags.push("MISSING_DESCRIPTION");
+        }
+        if self.total_value.is_none() {
+            flags.push("MISSING_VALUE");
+        }
+        flags.join(";")
+    }
+}
+
+fn group_lines(packed_box: &PackedBox) -> Vec<CustomsLine<'_>> {
+    packed_box
+        .contents()
+        .into_iter()
+        .map(|count| CustomsLine {
+            first_item: count.item_ids.first().and_then(|id| packed_box.items.iter().find(|item| &item.id == id)),
+            sku: count.sku,
+            description: count.description,
+            total_value: count.total_value,
+            quantity: count.count,
+            net_weight: count.total_weight,
+        })
+        .collect()
+}
+
+// Metadata columns are appended after the fixed ones, taken from the first item of each line
+pub fn customs_csv(solution: &PackingSolution, metadata_keys: &[String], format: &NumberFormat) -> String {
+    let money = |value: Option<f64>| value.map(|v| format.money(v)).unwrap_or_default();
+    let weight = |value: f64| format.number(value, 3);
+    let mut header: Vec<String> = HEADER.map(String::from).to_vec();
+    header.extend(metadata_keys.iter().cloned());
+    let mut csv = csv_row(&header);
+
+    for (index, packed_box) in solution.boxes.iter().enumerate() {
+        let label = box_label(solution, index);
+        let lines = group_lines(packed_box);
+
+        for line in &lines {
+            let mut row = vec![
+                label.clone(),
+                packed_box.destination.clone(),
+                "ITEM".to_string(),
+                line.sku.clone(),
+                line.description.clone().unwrap_or_default(),
+                line.quantity.to_string(),
+                money(line.unit_value()),
+                money(line.total_value),
+                weight(line.net_weight),
+                String::new(),
+                String::new(),
+                line.flags(),
+            ];
+            row.extend(metadata_keys.iter().map(|key| line.first_item.map(|item| metadata_value(item, key)).unwrap_or_default()));
+            csv.push_str(&csv_row(&row));
+        }
+
+        // Box totals, with the packaging tare kept separate from the net goods weight
+        let incomplete = lines.iter().any(|line| !line.flags().is_empty());
+        let mut totals = vec![
+            label,
+            packed_box.destination.clone(),
+            "TOTAL".to_string(),
+            String::new(),
+            String::new(),
+            packed_box.items.len().to_string(),
+            String::new(),
+            money(Some(packed_box.value)),
+            weight(packed_box.items_weight()),
+            weight(packed_box.packaging_weight()),
+            weight(packed_box.weight),
+            if incomplete { "INCOMPLETE".to_string() } else { String::new() },
+        ];
+        totals.resize(header.len(), String::new());
+        csv.push_str(&csv_row(&totals));
+    }
+
+    csv
+}
@@ -0,0 +1,238 @@
+// Numbers in the exported documents as the warehouse reads them: decimal separator, thousands
+// grouping and currency symbol by locale. Every exporter formats through here, so a manifest and
+// the customs rows of the same boxes never disagree; the solution itself keeps raw numbers.
+// Without a locale the text is what it always was, a point and no grouping.
+
+// Separators and currency conventions of one locale
+struct LocaleSpec {
+    tag: &'static str,
+    decimal: char,
+    group: char,
+    currency: &'static str,
+    // "$1.00" rather than "1,00 €"
+    symbol_first: bool,
+}
+
+const LOCALES: [LocaleSpec; 9] = [
+    LocaleSpec { tag: "en-US", decimal: '.', group: ',', currency: "USD", symbol_first: true },
+    LocaleSpec { tag: "en-GB", decimal: '.', group: ',', currency: "GBP", symbol_first: true },
+    LocaleSpec { tag: "de-DE", decimal: ',', group: '.', currency: "EUR", symbol_first: false },
+    LocaleSpec { tag: "de-CH", decimal: '.', group: '’', currency: "CHF", symbol_first: true },
+    LocaleSpec { tag: "fr-FR", decimal: ',', group: '\u{202f}', currency: "EUR", symbol_first: false },
+    LocaleSpec { tag: "es-ES", decimal: ',', group: '.', currency: "EUR", symbol_first: false },
+    LocaleSpec { tag: "it-IT", decimal: ',', group: '.', currency: "EUR", symbol_first: false },
+    LocaleSpec { tag: "ja-JP", decimal: '.', group: ',', currency: "JPY", symbol_first: true },
+    LocaleSpec { tag: "zh-CN", decimal: '.', group: ',', currency: "CNY", symbol_first: true },
+];
+
+// ISO 4217 code, symbol, minor digits
+const CURRENCIES: [(&str, &str, usize); 9] = [
+    ("USD", "$", 2),
+    ("EUR", "€", 2),
+    ("GBP", "£", 2),
+    ("CHF", "CHF", 2),
+    ("JPY", "¥", 0),
+    ("CNY", "¥", 2),
+    ("CAD", "CA$", 2),
+    ("AUD", "A$", 2),
+    ("HKD", "HK$", 2),
+];
+
+// Keeps the symbol on the same line as the amount on a printed page
+const SYMBOL_SPACE: char = '\u{a0}';
+
+#[derive(Debug, Clone, PartialEq)]
+struct Currency {
+    symbol: String,
+    decimals: usize,
+}
+
+impl Currency {
+    // A code not in the table is written out as its own symbol, with cents
+    fn from_code(code: &str) -> Self {
+        let code = code.trim().to_ascii_uppercase();
+        match CURRENCIES.iter().find(|(known, _, _)| *known == code) {
+            Some((_, symbol, decimals)) => Currency { symbol: symbol.to_string(), decimals: *decimals },
+            None => Currency { symbol: code, decimals: 2 },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberFormat {
+    decimal: char,
+    group: Option<char>,
+    currency: Option<Currency>,
+    symbol_first: bool,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        NumberFormat {
+            decimal: '.',
+            group: None,
+            currency: None,
+            symbol_first: true,
+        }
+    }
+}
+
+fn find_locale(tag: &str) -> Option<&'static LocaleSpec> {
+    let tag = tag.trim().replace('_', "-");
+    let language = tag.split('-').next().unwrap_or_default();
+    LOCALES
+        .iter()
+        .find(|spec| spec.tag.eq_ignore_ascii_case(&tag))
+        .or_else(|| LOCALES.iter().find(|spec| spec.tag.split('-').next().is_some_and(|own| own.eq_ignore_ascii_case(language))))
+}
+
+impl NumberFormat {
+    // The format of a locale tag ("de-DE", "en_us"), or of the first locale of its language for
+    // a region not in the table ("de-AT" reads as de-DE); an unknown language keeps the plain
+    // format. The currency defaults to the locale's own, and never to one without a locale.
+    pub fn new(locale: Option<&str>, currency: Option<&str>) -> Self {
+        let spec = locale.and_then(find_locale);
+        let currency = currency.or(spec.map(|spec| spec.currency)).map(Currency::from_code);
+        match spec {
+            Some(spec) => NumberFormat {
+                decimal: spec.decimal,
+                group: Some(spec.group),
+                currency,
+                symbol_first: spec.symbol_first,
+            },
+            None => NumberFormat { currency, ..Default::default() },
+        }
+    }
+
+    // The value with a fixed number of decimals
+    pub fn number(&self, value: f64, decimals: usize) -> String {
+        let plain = format!("{:.*}", decimals, value);
+        let (sign, digits) = match plain.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", plain.as_str()),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+
+        let mut text = sign.to_string();
+        for (index, digit) in whole.chars().enumerate() {
+            if index > 0 && (whole.len() - index) % 3 == 0 {
+                text.extend(self.group);
+            }
+            text.push(digit);
+        }
+        if !fraction.is_empty() {
+            text.push(self.decimal);
+            text.push_str(fraction);
+        }
+        text
+    }
+
+    // A measurement with at most one decimal place (37.0 -> "37", 37.25 -> "37.3")
+    pub fn measure(&self, value: f64) -> String {
+        let rounded = (value * 10.0).round() / 10.0;
+        self.number(rounded, if rounded.fract() == 0.0 { 0 } else { 1 })
+    }
+
+    // An amount in the currency, with its symbol where the locale puts it; cents without one
+    pub fn money(&self, value: f64) -> String {
+        let Some(currency) = &self.currency else {
+            return self.number(value, 2);
+        };
+        let amount = self.number(value, currency.decimals);
+        let (sign, amount) = match amount.strip_prefix('-') {
+            Some(amount) => ("-", amount),
+            None => ("", amount.as_str()),
+        };
+        if !self.symbol_first {
+            return format!("{}{}{}{}", sign, amount, SYMBOL_SPACE, currency.symbol);
+        }
+        // Letters run into the digits without a space ("CHF12.00")
+        let space = if currency.symbol.ends_with(|c: char| c.is_alphabetic()) { SYMBOL_SPACE.to_string() } else { String::new() };
+        format!("{}{}{}{}", sign, currency.symbol, space, amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn en_us() -> NumberFormat {
+        NumberFormat::new(Some("en-US"), None)
+    }
+
+    fn de_de() -> NumberFormat {
+        NumberFormat::new(Some("de-DE"), None)
+    }
+
+    #[test]
+    fn decimal_separator() {
+        assert_eq!(en_us().number(12.5, 2), "12.50");
+        assert_eq!(de_de().number(12.5, 2), "12,50");
+        assert_eq!(en_us().measure(37.25), "37.3");
+        assert_eq!(de_de().measure(37.25), "37,3");
+        assert_eq!(de_de().measure(37.0), "37");
+    }
+
+    #[test]
+    fn thousands_separator() {
+        assert_eq!(en_us().number(1234567.891, 2), "1,234,567.89");
+        assert_eq!(de_de().number(1234567.891, 2), "1.234.567,89");
+        assert_eq!(en_us().number(999.0, 0), "999");
+        assert_eq!(de_de().number(1000.0, 0), "1.000");
+        assert_eq!(en_us().number(-1234.5, 1), "-1,234.5");
+        assert_eq!(de_de().number(-1234.5, 1), "-1.234,5");
+    }
+
+    #[test]
+    fn currency_placement() {
+        assert_eq!(en_us().money(1234.5), "$1,234.50");
+        assert_eq!(de_de().money(1234.5), "1.234,50\u{a0}€");
+        assert_eq!(en_us().money(-3.0), "-$3.00");
+        assert_eq!(de_de().money(-3.0), "-3,00\u{a0}€");
+        // Another currency keeps the locale's separators and placement
+        assert_eq!(NumberFormat::new(Some("de-DE"), Some("usd")).money(1234.5), "1.234,50\u{a0}$");
+        assert_eq!(NumberFormat::new(Some("en-US"), Some("JPY")).money(1234.6), "¥1,235");
+        assert_eq!(NumberFormat::new(Some("en-US"), Some("CHF")).money(12.0), "CHF\u{a0}12.00");
+    }
+
+    // No locale is the plain format, with a currency only when one is given
+    #[test]
+    fn plain_without_a_locale() {
+        let plain = NumberFormat::new(None, None);
+        assert_eq!(plain, NumberFormat::default());
+        assert_eq!(plain.number(1234567.891, 2), "1234567.89");
+        assert_eq!(plain.money(1234.5), "1234.50");
+        assert_eq!(NumberFormat::new(None, Some("EUR")).money(1234.5), "€1234.50");
+        assert_eq!(NumberFormat::new(Some("xx-YY"), None), NumberFormat::default());
+    }
+
+    // Region and spelling variants fall back to a locale of their language
+    #[test]
+    fn locale_tags() {
+        assert_eq!(NumberFormat::new(Some("de_de"), None), de_de());
+        assert_eq!(NumberFormat::new(Some("de-AT"), None), de_de());
+        assert_eq!(NumberFormat::new(Some(" en-us "), None), en_us());
+    }
+
+    // In the customs CSV a German decimal comma is quoted so it doesn't split the field
+    #[test]
+    fn exports_format_through_the_locale() {
+        let items = vec![serde_json::from_value(serde_json::json!({
+            "id": "a",
+            "destination": "Germany",
+            "length": 10.0,
+            "width": 10.0,
+            "height": 10.0,
+            "weight": 1.25,
+            "value": 1234.5,
+        }))
+        .unwrap()];
+        let solution = crate::pack_with_options(items, &crate::PackingOptions::default()).unwrap();
+        let german = crate::export::customs::customs_csv(&solution, &[], &de_de());
+        assert!(german.contains("\"1.234,50\u{a0}€\""), "{german}");
+        assert!(german.contains("\"1,250\""), "{german}");
+        let american = crate::export::customs::customs_csv(&solution, &[], &en_us());
+        assert!(american.contains("\"$1,234.50\""), "{american}");
+        assert!(american.contains(",1.250,"), "{american}");
+    }
+}
@@ -0,0 +1,201 @@
+// Printable step-by-step packing instructions for staff without the app. Every phrase comes from
+// the language's table; positions are told relative to the box (thirds along each side) or to
+// an item placed earlier.
+use super::{box_label, format::NumberFormat};
+use crate::{footprints_overlap, Item, PackedBox, PackingSolution, Position, CONTACT_EPSILON};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    En,
+    Zh,
+}
+
+// Templates fill {placeholders}; indices of the arrays follow the ROTATIONS table and the
+// thirds of the box from back to front, left to right and bottom to top
+pub(crate) struct Phrases {
+    // {label} {length} {width} {height} {weight}
+    header: &'static str,
+    // {number} {item} {orientation} {place}
+    step: &'static str,
+    step_separator: &'static str,
+    end: &'static str,
+    // {other}
+    on_top_of: &'static str,
+    right_of: &'static str,
+    in_front_of: &'static str,
+    // {spot} {level}
+    in_box: &'static str,
+    // An item of a weight-only box, which has no position
+    anywhere: &'static str,
+    // {item}, the whole body for an item shipping loose
+    loose: &'static str,
+    spots: [[&'static str; 3]; 3],
+    levels: [&'static str; 3],
+    pub(crate) rotations: [&'static str; 6],
+    empty: &'static str,
+}
+
+pub(crate) const ENGLISH: Phrases = Phrases {
+    header: "Box {label} ({length}×{width}×{height} cm, {weight} kg): ",
+    step: "{number}) place {item} ({orientation}) {place}",
+    step_separator: "; ",
+    end: ".",
+    on_top_of: "on top of {other}",
+    right_of: "to the right of {other}",
+    in_front_of: "in front of {other}",
+    in_box: "{spot} {level}",
+    anywhere: "wherever it fits",
+    loose: "no carton, ship {item} in its own packaging with the label on it",
+    spots: [
+        ["in the back-left corner", "at the back", "in the back-right corner"],
+        ["on the left", "in the middle", "on the right"],
+        ["in the front-left corner", "at the front", "in the front-right corner"],
+    ],
+    levels: ["on the floor", "halfway up", "near the top"],
+    rotations: [
+        "upright, length along box length",
+        "lay on side, length along box length",
+        "upright, turned a quarter, width along box length",
+        "stand on end, width along box length",
+        "lay on side, height along box length",
+        "stand on end, height along box length",
+    ],
+    empty: "nothing to pack",
+};
+
+const CHINESE: Phrases = Phrases {
+    header: "箱子 {label}（{length}×{width}×{height} 厘米，{weight} 千克）：",
+    step: "{number}）将 {item}（{orientation}）放在{place}",
+    step_separator: "；",
+    end: "。",
+    on_top_of: " {other} 的上面",
+    right_of: " {other} 的右侧",
+    in_front_of: " {other} 的前面",
+    in_box: "{level}{spot}",
+    anywhere: "任意放得下的位置",
+    loose: "无需装箱，{item} 使用自带包装发货，标签直接贴在上面",
+    spots: [
+        ["后左角", "后部", "后右角"],
+        ["左侧", "中间", "右侧"],
+        ["前左角", "前部", "前右角"],
+    ],
+    levels: ["底层", "中层", "顶层"],
+    rotations: [
+        "直立，长边沿箱长",
+        "侧放，长边沿箱长",
+        "直立，转四分之一圈，宽边沿箱长",
+        "竖立，宽边沿箱长",
+        "侧放，高边沿箱长",
+        "竖立，高边沿箱长",
+    ],
+    empty: "无需装箱",
+};
+
+fn phrases(language: Language) -> &'static Phrases {
+    match language {
+        Language::En => &ENGLISH,
+        Language::Zh => &CHINESE,
+    }
+}
+
+fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    values
+        .iter()
+        .fold(template.to_string(), |text, (key, value)| text.replace(&format!("{{{}}}", key), value))
+}
+
+// Which third of the extent the middle of the span falls in
+fn third(start: f64, size: f64, extent: f64) -> usize {
+    if extent <= 0.0 {
+        return 0;
+    }
+    (((start + size / 2.0) / extent * 3.0) as usize).min(2)
+}
+
+// Where the item goes, relative to what is already in the box if it touches it
+fn place(packed_box: &PackedBox, earlier: &[&Item], item: &Item, words: &Phrases) -> String {
+    let Some(Position { x, y, z }) = item.position else {
+        return words.anywhere.to_string();
+    };
+    let placed = |other: &&&Item| other.position.is_some();
+
+    // Resting on an item placed earlier, the one under most of it
+    if z > CONTACT_EPSILON {
+        let below = earlier.iter().filter(placed).filter(|other| {
+            let Position { x: ox, y: oy, z: oz } = other.position.unwrap_or_default();
+            (oz + other.height - z).abs() <= CONTACT_EPSILON && footprints_overlap(item, (x, y), other, (ox, oy))
+        });
+        let overlap = |other: &Item| {
+            let Position { x: ox, y: oy, .. } = other.position.unwrap_or_default();
+            let dx = (x + item.length).min(ox + other.length) - x.max(ox);
+            let dy = (y + item.width).min(oy + other.width) - y.max(oy);
+            dx * dy
+        };
+        if let Some(other) = below.max_by(|a, b| overlap(a).total_cmp(&overlap(b))) {
+            return fill(words.on_top_of, &[("other", &other.id)]);
+        }
+    }
+
+    // Right beside the previous item on the same level
+    if let Some(previous) = earlier.last().filter(placed) {
+        let Position { x: px, y: py, z: pz } = previous.position.unwrap_or_default();
+        if (pz - z).abs() <= CONTACT_EPSILON {
+            if (px + previous.length - x).abs() <= CONTACT_EPSILON && (py - y).abs() <= CONTACT_EPSILON {
+                return fill(words.right_of, &[("other", &previous.id)]);
+            }
+            if (py + previous.width - y).abs() <= CONTACT_EPSILON && (px - x).abs() <= CONTACT_EPSILON {
+                return fill(words.in_front_of, &[("other", &previous.id)]);
+            }
+        }
+    }
+
+    let spot = words.spots[third(y, item.width, packed_box.width)][third(x, item.length, packed_box.length)];
+    let level = words.levels[third(z, item.height, packed_box.height)];
+    fill(words.in_box, &[("spot", spot), ("level", level)])
+}
+
+// The box's packing sequence as one text block; empty for an index past the last box
+pub fn instructions(solution: &PackingSolution, box_index: usize, language: Language, format: &NumberFormat) -> String {
+    let Some(packed_box) = solution.boxes.get(box_index) else {
+        return String::new();
+    };
+    let words = phrases(language);
+
+    let header = fill(
+        words.header,
+        &[
+            ("label", &box_label(solution, box_index)),
+            ("length", &format.measure(packed_box.length)),
+            ("width", &format.measure(packed_box.width)),
+            ("height", &format.measure(packed_box.height)),
+            ("weight", &format.number(packed_box.weight, 1)),
+        ],
+    );
+    let items = packed_box.loaded_items();
+    let steps: Vec<String> = items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let orientation = words.rotations[item.rotation as usize % words.rotations.len()];
+            fill(
+                words.step,
+                &[
+                    ("number", &(index + 1).to_string()),
+                    ("item", &item.id),
+                    ("orientation", orientation),
+                    ("place", &place(packed_box, &items[..index], item, words)),
+                ],
+            )
+        })
+        .collect();
+
+    let body = match items.first() {
+        Some(item) if packed_box.ship_loose => fill(words.loose, &[("item", &item.id)]),
+        _ if steps.is_empty() => words.empty.to_string(),
+        _ => steps.join(words.step_separator),
+    };
+    format!("{}{}{}", header, body, words.end)
+}
@@ -0,0 +1,108 @@
+// Packing manifest: one CSV row per packed item
+use super::{box_label, csv_row, format::NumberFormat, metadata_value, rotation_phrase};
+use crate::{hazmat, PackedBox, PackingSolution};
+
+const HEADER: [&str; 22] = [
+    "box_label",
+    "destination",
+    "box_length_cm",
+    "box_width_cm",
+    "box_height_cm",
+    "box_weight_kg",
+    "box_contents",
+    "box_skus",
+    "box_hazmat",
+    "item_id",
+    "description",
+    "category",
+    "length_cm",
+    "width_cm",
+    "height_cm",
+    "weight_kg",
+    "x_cm",
+    "y_cm",
+    "z_cm",
+    "rotation",
+    "orientation",
+    "value",
+];
+
+// Category breakdown of the box, "12 × books, 4 × stationery"
+pub fn box_contents(packed_box: &PackedBox) -> String {
+    packed_box
+        .category_counts()
+        .iter()
+        .map(|count| format!("{} × {}", count.count, count.category))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Units per SKU of the box, "SKU-123 × 14, SKU-9 × 2"
+pub fn box_skus(packed_box: &PackedBox) -> String {
+    packed_box
+        .contents()
+        .iter()
+        .map(|count| format!("{} × {}", count.sku, count.count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Hazmat of the box for its labels, "UN3481: 2 items, 40"
+pub fn box_hazmat(packed_box: &PackedBox) -> String {
+    hazmat::contents(&packed_box.items)
+        .iter()
+        .map(|content| format!("{}: {} items, {}", content.class, content.items, content.quantity))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+// Metadata columns are appended after the fixed ones, in the order requested
+pub fn manifest_csv(solution: &PackingSolution, metadata_keys: &[String], format: &NumberFormat) -> String {
+    let dimension = |value: f64| format.number(value, 2);
+    let weight = |value: f64| format.number(value, 3);
+    let mut header: Vec<String> = HEADER.map(String::from).to_vec();
+    header.extend(metadata_keys.iter().cloned());
+    let mut csv = csv_row(&header);
+
+    for (index, packed_box) in solution.boxes.iter().enumerate() {
+        let label = box_label(solution, index);
+        let contents = box_contents(packed_box);
+        let skus = box_skus(packed_box);
+        let hazmat = box_hazmat(packed_box);
+        for item in packed_box.loaded_items() {
+            // Blank for the items of a weight-only box, which have no position
+            let [x, y, z] = match item.position {
+                Some(position) => position.to_array().map(dimension),
+                None => Default::default(),
+            };
+            let mut row = vec![
+                label.clone(),
+                packed_box.destination.clone(),
+                dimension(packed_box.length),
+                dimension(packed_box.width),
+                dimension(packed_box.height),
+                weight(packed_box.weight),
+                contents.clone(),
+                skus.clone(),
+                hazmat.clone(),
+                item.id.clone(),
+                item.description.clone().unwrap_or_default(),
+                item.category.clone().unwrap_or_default(),
+                dimension(item.length),
+                dimension(item.width),
+                dimension(item.height),
+                weight(item.weight),
+                x,
+                y,
+                z,
+                item.rotation.to_string(),
+                rotation_phrase(item.rotation).to_string(),
+                item.value.map(|v| format.money(v)).unwrap_or_default(),
+            ];
+            row.extend(metadata_keys.iter().map(|key| metadata_value(item, key)));
+            csv.push_str(&csv_row(&row));
+        }
+    }
+
+    csv
+}
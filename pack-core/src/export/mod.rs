@@ -0,0 +1,81 @@
+// Exporters that turn a packing solution into documents for the warehouse
+use crate::{Item, PackingSolution};
+
+pub mod customs;
+pub mod format;
+pub mod instructions;
+pub mod manifest;
+pub mod packing_slip;
+pub mod pick_list;
+pub mod svg;
+
+// Human-readable box label, the one packing gave it or, for solutions saved before labels were,
+// numbered within its destination ("USA-002")
+pub(crate) fn box_label(solution: &PackingSolution, box_index: usize) -> String {
+    if !solution.boxes[box_index].label.is_empty() {
+        return solution.boxes[box_index].label.clone();
+    }
+    let destination = &solution.boxes[box_index].destination;
+    let sequence = solution.boxes[..=box_index]
+        .iter()
+        .filter(|b| &b.destination == destination)
+        .count();
+    format!("{}-{:03}", destination, sequence)
+}
+
+// Value of one metadata key rendered for a CSV cell; strings are unquoted, other JSON as-is
+pub(crate) fn metadata_value(item: &Item, key: &str) -> String {
+    match item.metadata.as_ref().and_then(|metadata| metadata.get(key)) {
+        Some(serde_json::Value::String(text)) => text.clone(),
+        Some(serde_json::Value::Null) | None => String::new(),
+        Some(value) => value.to_string(),
+    }
+}
+
+// Quote a CSV field when it contains a separator, quote, or line break
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub(crate) fn csv_row(fields: &[String]) -> String {
+    let mut row = fields
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<_>>()
+        .join(",");
+    row.push('\n');
+    row
+}
+
+// How to put the item into the box, by ROTATIONS index: which of its entered sides points up,
+// and which runs along the box length
+pub(crate) fn rotation_phrase(rotation: u8) -> &'static str {
+    let rotations = &instructions::ENGLISH.rotations;
+    rotations[rotation as usize % rotations.len()]
+}
+
+// Escape text for inclusion in XML/HTML output
+pub(crate) fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Format a measurement with at most one decimal place (37.0 -> "37", 37.25 -> "37.3"), in the plain
+// format of the exports that take no locale
+pub(crate) fn format_measure(value: f64) -> String {
+    format::NumberFormat::default().measure(value)
+}
@@ -0,0 +1,115 @@
+// Printable packing slip for one box: a self-contained HTML page (inline CSS, no assets) with the
+// label, the box, what it holds, the packing sequence and totals. Long tables break across pages
+// with their header repeated; every piece of item text is escaped.
+use super::{box_label, escape_xml, format::NumberFormat, manifest::box_hazmat};
+use crate::{Item, PackingSolution};
+
+const STYLE: &str = "\
+body { font-family: sans-serif; font-size: 11pt; margin: 1.5cm; color: #000; }
+h1 { font-size: 20pt; margin: 0 0 0.2cm; }
+h2 { font-size: 13pt; margin: 0.6cm 0 0.2cm; }
+.box { margin: 0 0 0.4cm; }
+.box td { padding: 0 0.6cm 0 0; }
+table.items { width: 100%; border-collapse: collapse; }
+table.items th, table.items td { border-bottom: 1px solid #999; padding: 0.1cm 0.2cm; text-align: left; }
+table.items td.number, table.items th.number { text-align: right; }
+table.items thead { display: table-header-group; }
+table.items tfoot { display: table-footer-group; }
+table.items tr { page-break-inside: avoid; break-inside: avoid; }
+ol.sequence { columns: 3; margin: 0; }
+ol.sequence li { break-inside: avoid; }
+footer { margin-top: 0.6cm; border-top: 2px solid #000; padding-top: 0.2cm; }
+@page { margin: 1.5cm; }
+@media print { body { margin: 0; } h2 { page-break-after: avoid; break-after: avoid; } }
+";
+
+// Items sharing an id, in loading order of their first one
+fn lines<'a>(items: &[&'a Item]) -> Vec<(&'a Item, usize, f64)> {
+    let mut lines: Vec<(&Item, usize, f64)> = Vec::new();
+    for &item in items {
+        match lines.iter_mut().find(|(first, _, _)| first.id == item.id) {
+            Some((_, quantity, total)) => {
+                *quantity += 1;
+                *total += item.weight;
+            }
+            None => lines.push((item, 1, item.weight)),
+        }
+    }
+    lines
+}
+
+// The slip for the box as one HTML document; empty when there is no such box
+pub fn packing_slip_html(solution: &PackingSolution, box_index: usize, format: &NumberFormat) -> String {
+    let Some(packed_box) = solution.boxes.get(box_index) else {
+        return String::new();
+    };
+    let weight = |value: f64| format.number(value, 3);
+    let label = escape_xml(&box_label(solution, box_index));
+    let items = packed_box.loaded_items();
+    let items_weight: f64 = items.iter().map(|item| item.weight).sum();
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>Packing slip {}</title>\n<style>\n{}</style>\n</head>\n<body>\n", label, STYLE));
+    html.push_str(&format!("<h1>{}</h1>\n", label));
+
+    html.push_str("<table class=\"box\">\n");
+    let mut detail = |name: &str, value: String| {
+        html.push_str(&format!("<tr><td>{}</td><td>{}</td></tr>\n", name, value));
+    };
+    detail("Destination", escape_xml(&packed_box.destination));
+    if let Some(carrier) = &packed_box.carrier {
+        detail("Carrier", escape_xml(carrier));
+    }
+    detail(
+        "Outer dimensions",
+        format!(
+            "{} × {} × {} cm",
+            format.measure(packed_box.length),
+            format.measure(packed_box.width),
+            format.measure(packed_box.height)
+        ),
+    );
+    detail("Gross weight", format!("{} kg", weight(packed_box.weight)));
+    let hazmat = box_hazmat(packed_box);
+    if !hazmat.is_empty() {
+        detail("Hazmat", escape_xml(&hazmat));
+    }
+    html.push_str("</table>\n");
+
+    let lines = lines(&items);
+    html.push_str("<h2>Contents</h2>\n<table class=\"items\">\n");
+    html.push_str("<thead><tr><th>Item</th><th>Description</th><th class=\"number\">Quantity</th><th class=\"number\">Weight (kg)</th></tr></thead>\n");
+    html.push_str(&format!(
+        "<tfoot><tr><td colspan=\"2\">{} lines</td><td class=\"number\">{}</td><td class=\"number\">{}</td></tr></tfoot>\n",
+        lines.len(),
+        items.len(),
+        weight(items_weight)
+    ));
+    html.push_str("<tbody>\n");
+    for (item, quantity, total) in &lines {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td class=\"number\">{}</td><td class=\"number\">{}</td></tr>\n",
+            escape_xml(&item.id),
+            escape_xml(item.description.as_deref().unwrap_or_default()),
+            quantity,
+            weight(*total)
+        ));
+    }
+    html.push_str("</tbody>\n</table>\n");
+
+    html.push_str("<h2>Packing sequence</h2>\n<ol class=\"sequence\">\n");
+    for item in &items {
+        html.push_str(&format!("<li>{}</li>\n", escape_xml(&item.id)));
+    }
+    html.push_str("</ol>\n");
+
+    html.push_str(&format!(
+        "<footer>{} items, {} kg of items, {} kg packed</footer>\n",
+        items.len(),
+        weight(items_weight),
+        weight(packed_box.weight)
+    ));
+    html.push_str("</body>\n</html>\n");
+    html
+}
@@ -0,0 +1,98 @@
+// Pick list for the packing bench: what goes into each box, and how when it is a grid
+use super::{box_label, csv_row, format_measure, rotation_phrase};
+use crate::{Item, PackingSolution};
+
+const HEADER: [&str; 10] = [
+    "box_label",
+    "destination",
+    "row_type",
+    "item_id",
+    "description",
+    "quantity",
+    "placed_dimensions",
+    "orientation",
+    "layout",
+    "handling",
+];
+
+// Fragile and unload-first items go in last, by the face the box opens on; the column lets the
+// bench check
+fn handling(item: &Item) -> String {
+    let kind = match (item.fragile, item.unload_first) {
+        (true, true) => "fragile, unload first",
+        (true, false) => "fragile",
+        (false, true) => "unload first",
+        (false, false) => return String::new(),
+    };
+    format!("{}: place near the opening", kind)
+}
+
+fn dimensions((length, width, height): (f64, f64, f64)) -> String {
+    format!("{}×{}×{}", format_measure(length), format_measure(width), format_measure(height))
+}
+
+// Items sharing an id and orientation within the box, in loading order; squashed ones get their own line
+fn group_by_id<'a>(items: &[&'a Item]) -> Vec<(&'a Item, usize)> {
+    let mut lines: Vec<(&Item, usize)> = Vec::new();
+    for &item in items {
+        let same_line = |first: &Item| first.id == item.id && first.rotation == item.rotation && first.nominal_height == item.nominal_height && first.height == item.height;
+        match lines.iter_mut().find(|(first, _)| same_line(first)) {
+            Some((_, quantity)) => *quantity += 1,
+            None => lines.push((item, 1)),
+        }
+    }
+    lines
+}
+
+pub fn pick_list_csv(solution: &PackingSolution) -> String {
+    let mut csv = csv_row(&HEADER.map(String::from));
+
+    for (index, packed_box) in solution.boxes.iter().enumerate() {
+        let label = box_label(solution, index);
+        let items = packed_box.loaded_items();
+        let grid_items = packed_box.grid.as_ref().map_or(0, |grid| grid.item_count.min(items.len()));
+
+        if let Some(grid) = &packed_box.grid {
+            let (nx, ny, nz) = grid.counts;
+            csv.push_str(&csv_row(&[
+                label.clone(),
+                packed_box.destination.clone(),
+                "GRID".to_string(),
+                String::new(),
+                String::new(),
+                grid.item_count.to_string(),
+                dimensions(grid.cell),
+                String::new(),
+                format!("{} × {} × {} = {}", nx, ny, nz, nx * ny * nz),
+                String::new(),
+            ]));
+        }
+
+        let sections = [
+            (&items[..grid_items], "grid"),
+            (&items[grid_items..], ""),
+        ];
+        for (items, layout) in sections {
+            for (item, quantity) in group_by_id(items) {
+                let layout = match item.nominal_height {
+                    Some(_) => format!("squash to height {}", format_measure(item.height)),
+                    None => layout.to_string(),
+                };
+                csv.push_str(&csv_row(&[
+                    label.clone(),
+                    packed_box.destination.clone(),
+                    "ITEM".to_string(),
+                    item.id.clone(),
+                    item.description.clone().unwrap_or_default(),
+                    quantity.to_string(),
+                    dimensions((item.length, item.width, item.height)),
+                    rotation_phrase(item.rotation).to_string(),
+                    layout,
+                    handling(item),
+                ]));
+            }
+        }
+    }
+
+    csv
+}
@@ -0,0 +1,214 @@
+// Top-down layer diagrams for packers working box by box
+use super::{escape_xml, format_measure};
+use crate::{Item, PackedBox, Position};
+
+// Longest side of the box outline in pixels
+const DIAGRAM_SIZE_PX: f64 = 480.0;
+const MARGIN_LEFT_PX: f64 = 56.0;
+const MARGIN_TOP_PX: f64 = 44.0;
+const MARGIN_RIGHT_PX: f64 = 24.0;
+const MARGIN_BOTTOM_PX: f64 = 40.0;
+const FONT_SIZE_PX: f64 = 11.0;
+// Rough advance of one character at FONT_SIZE_PX, used to decide what fits
+const CHAR_WIDTH_PX: f64 = 6.6;
+const LEGEND_ROW_PX: f64 = 16.0;
+const LEGEND_WIDTH_PX: f64 = 420.0;
+// Truncated labels shorter than this are useless, so the item goes to the legend instead
+const MIN_LABEL_CHARS: usize = 5;
+const HEIGHT_EPSILON: f64 = 1e-6;
+
+// A horizontal slice of the box between two consecutive item base heights
+struct Layer {
+    base: f64,
+    top: f64,
+}
+
+// Slice the box at every distinct base height among its items
+fn layers_of(packed_box: &PackedBox) -> Vec<Layer> {
+    let mut bases: Vec<f64> = packed_box
+        .items
+        .iter()
+        .filter_map(|item| item.position.map(|Position { z, .. }| z))
+        .collect();
+    bases.sort_by(f64::total_cmp);
+    bases.dedup_by(|a, b| (*a - *b).abs() < HEIGHT_EPSILON);
+
+    bases
+        .iter()
+        .enumerate()
+        .map(|(i, &base)| Layer {
+            base,
+            top: bases.get(i + 1).copied().unwrap_or(packed_box.height),
+        })
+        .filter(|layer| layer.top - layer.base > HEIGHT_EPSILON)
+        .collect()
+}
+
+// Shorten an id to at most `max_chars` characters, marking the cut with an ellipsis
+fn truncate_label(id: &str, max_chars: usize) -> String {
+    if id.chars().count() <= max_chars {
+        id.to_string()
+    } else {
+        let mut label: String = id.chars().take(max_chars.saturating_sub(1)).collect();
+        label.push('…');
+        label
+    }
+}
+
+// Render one SVG document per layer of the box
+pub fn render_layers(packed_box: &PackedBox, box_number: usize) -> Vec<String> {
+    let layers = layers_of(packed_box);
+    layers
+        .iter()
+        .enumerate()
+        .map(|(i, layer)| render_layer(packed_box, box_number, i + 1, layers.len(), layer))
+        .collect()
+}
+
+fn render_layer(
+    packed_box: &PackedBox,
+    box_number: usize,
+    layer_number: usize,
+    layer_count: usize,
+    layer: &Layer,
+) -> String {
+    let scale = DIAGRAM_SIZE_PX / packed_box.length.max(packed_box.width).max(HEIGHT_EPSILON);
+    let outline_w = packed_box.length * scale;
+    let outline_h = packed_box.width * scale;
+
+    // Items whose vertical extent intersects this layer
+    let layer_items: Vec<(&Item, Position)> = packed_box
+        .items
+        .iter()
+        .filter_map(|item| item.position.map(|pos| (item, pos)))
+        .filter(|(item, Position { z, .. })| {
+            *z < layer.top - HEIGHT_EPSILON && z + item.height > layer.base + HEIGHT_EPSILON
+        })
+        .collect();
+
+    let mut shapes = String::new();
+    let mut legend: Vec<(usize, &Item, f64)> = Vec::new();
+
+    for (item, Position { x, y, z }) in &layer_items {
+        let rx = MARGIN_LEFT_PX + x * scale;
+        let ry = MARGIN_TOP_PX + y * scale;
+        let rw = item.length * scale;
+        let rh = item.width * scale;
+
+        // Items resting on this layer's base are drawn solid, ones reaching up from below are dashed
+        let starts_here = (z - layer.base).abs() < HEIGHT_EPSILON;
+        let (fill, dash) = if starts_here {
+            ("#93c5fd", "")
+        } else {
+            ("#e5e7eb", " stroke-dasharray=\"4 3\"")
+        };
+        shapes.push_str(&format!(
+            "  <rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\" stroke=\"#1e3a8a\" stroke-width=\"1\"{}/>\n",
+            rx, ry, rw, rh, fill, dash
+        ));
+
+        let max_chars = ((rw - 6.0) / CHAR_WIDTH_PX).floor().max(0.0) as usize;
+        let fits_whole = item.id.chars().count() <= max_chars;
+        let label = if rh >= FONT_SIZE_PX + 3.0 && (fits_whole || max_chars >= MIN_LABEL_CHARS) {
+            Some(truncate_label(&item.id, max_chars))
+        } else {
+            // Too small for a readable id: list it in the legend and mark it with its key if possible
+            let key = legend.len() + 1;
+            legend.push((key, item, *z));
+            let key_text = format!("{}", key);
+            if rh >= FONT_SIZE_PX && rw >= key_text.len() as f64 * CHAR_WIDTH_PX + 2.0 {
+                Some(key_text)
+            } else {
+                None
+            }
+        };
+
+        if let Some(label) = label {
+            shapes.push_str(&format!(
+                "  <text x=\"{:.2}\" y=\"{:.2}\" font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>\n",
+                rx + rw / 2.0,
+                ry + rh / 2.0,
+                FONT_SIZE_PX,
+                escape_xml(&label)
+            ));
+        }
+    }
+
+    let legend_top = MARGIN_TOP_PX + outline_h + MARGIN_BOTTOM_PX;
+    let legend_height = if legend.is_empty() {
+        0.0
+    } else {
+        (legend.len() + 1) as f64 * LEGEND_ROW_PX + 8.0
+    };
+    let mut width = MARGIN_LEFT_PX + outline_w.max(DIAGRAM_SIZE_PX / 2.0) + MARGIN_RIGHT_PX;
+    if !legend.is_empty() {
+        width = width.max(MARGIN_LEFT_PX + LEGEND_WIDTH_PX + MARGIN_RIGHT_PX);
+    }
+    let height = legend_top + legend_height;
+
+    let mut svg = String::new();
+    svg.push_str(&format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" viewBox=\"0 0 {:.0} {:.0}\" font-family=\"sans-serif\">\n",
+        width, height, width, height
+    ));
+    svg.push_str(&format!(
+        "  <text x=\"{:.2}\" y=\"18\" font-size=\"14\" font-weight=\"bold\">Box {} ({}) — layer {}/{}: {}–{} cm</text>\n",
+        MARGIN_LEFT_PX,
+        box_number,
+        escape_xml(&packed_box.destination),
+        layer_number,
+        layer_count,
+        format_measure(layer.base),
+        format_measure(layer.top)
+    ));
+
+    // Box outline with its length along the bottom and width along the left side
+    svg.push_str(&format!(
+        "  <rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"none\" stroke=\"#111827\" stroke-width=\"2\"/>\n",
+        MARGIN_LEFT_PX, MARGIN_TOP_PX, outline_w, outline_h
+    ));
+    svg.push_str(&shapes);
+    svg.push_str(&format!(
+        "  <text x=\"{:.2}\" y=\"{:.2}\" font-size=\"12\" text-anchor=\"middle\">{} cm</text>\n",
+        MARGIN_LEFT_PX + outline_w / 2.0,
+        MARGIN_TOP_PX + outline_h + 20.0,
+        format_measure(packed_box.length)
+    ));
+    let width_label_x = MARGIN_LEFT_PX - 14.0;
+    let width_label_y = MARGIN_TOP_PX + outline_h / 2.0;
+    svg.push_str(&format!(
+        "  <text x=\"{:.2}\" y=\"{:.2}\" font-size=\"12\" text-anchor=\"middle\" transform=\"rotate(-90 {:.2} {:.2})\">{} cm</text>\n",
+        width_label_x,
+        width_label_y,
+        width_label_x,
+        width_label_y,
+        format_measure(packed_box.width)
+    ));
+
+    // Legend table for items whose label did not fit in their rectangle
+    if !legend.is_empty() {
+        let mut row_y = legend_top + LEGEND_ROW_PX;
+        svg.push_str(&format!(
+            "  <text x=\"{:.2}\" y=\"{:.2}\" font-size=\"{}\" font-weight=\"bold\">Key</text>\n  <text x=\"{:.2}\" y=\"{:.2}\" font-size=\"{}\" font-weight=\"bold\">Item</text>\n  <text x=\"{:.2}\" y=\"{:.2}\" font-size=\"{}\" font-weight=\"bold\">Size (cm)</text>\n",
+            MARGIN_LEFT_PX, row_y, FONT_SIZE_PX,
+            MARGIN_LEFT_PX + 40.0, row_y, FONT_SIZE_PX,
+            MARGIN_LEFT_PX + 260.0, row_y, FONT_SIZE_PX
+        ));
+        for (key, item, z) in &legend {
+            row_y += LEGEND_ROW_PX;
+            svg.push_str(&format!(
+                "  <text x=\"{:.2}\" y=\"{:.2}\" font-size=\"{}\">{}</text>\n  <text x=\"{:.2}\" y=\"{:.2}\" font-size=\"{}\">{}</text>\n  <text x=\"{:.2}\" y=\"{:.2}\" font-size=\"{}\">{} × {} × {} @ z {}</text>\n",
+                MARGIN_LEFT_PX, row_y, FONT_SIZE_PX, key,
+                MARGIN_LEFT_PX + 40.0, row_y, FONT_SIZE_PX, escape_xml(&item.id),
+                MARGIN_LEFT_PX + 260.0, row_y, FONT_SIZE_PX,
+                format_measure(item.length),
+                format_measure(item.width),
+                format_measure(item.height),
+                format_measure(*z)
+            ));
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
@@ -0,0 +1,94 @@
+// The tradeoff between box count, box volume and cost across strategies and objectives: every
+// combination packs the same items, and only the results no other one beats on all counts are
+// kept, for the operator to weigh one box fewer against bigger cartons. Runs pack on their own
+// threads, where there are threads; with a seed in the options the frontier is the same every time.
+use crate::{pack_unchecked, platform, validate_request, Item, Objective, PackError, PackingOptions, PackingSolution, Strategy};
+use serde::{Deserialize, Serialize};
+
+// One result on the frontier, in the request's units
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrontierPoint {
+    // The run: the request's options with this strategy and objective
+    pub strategy: Strategy,
+    pub objective: Objective,
+    pub box_count: usize,
+    pub total_volume: f64,
+    // Carton and postage cost with a box catalog, else the cardboard cost when a price was given
+    pub total_cost: Option<f64>,
+    pub unpacked_count: usize,
+    pub solution: PackingSolution,
+}
+
+impl FrontierPoint {
+    fn new(strategy: Strategy, objective: Objective, solution: PackingSolution) -> Self {
+        FrontierPoint {
+            strategy,
+            objective,
+            box_count: solution.boxes.len(),
+            total_volume: solution.total_volume,
+            total_cost: solution.total_cost.or(solution.total_packaging_cost),
+            unpacked_count: solution.unpacked_items.len(),
+            solution,
+        }
+    }
+
+    // No worse on any count; costs only count when both runs have one
+    fn no_worse_than(&self, other: &FrontierPoint) -> bool {
+        self.unpacked_count <= other.unpacked_count &&
+        self.box_count <= other.box_count &&
+        self.total_volume <= other.total_volume &&
+        match (self.total_cost, other.total_cost) {
+            (Some(cost), Some(other_cost)) => cost <= other_cost,
+            _ => true,
+        }
+    }
+
+    fn same_figures(&self, other: &FrontierPoint) -> bool {
+        self.unpacked_count == other.unpacked_count &&
+        self.box_count == other.box_count &&
+        self.total_volume == other.total_volume &&
+        self.total_cost == other.total_cost
+    }
+}
+
+// The points no other point dominates. Of points with the same figures only the first run is
+// kept; the rest come back by box count, then volume, then cost, ties in run order.
+fn non_dominated(points: Vec<FrontierPoint>) -> Vec<FrontierPoint> {
+    let keep: Vec<bool> = points
+        .iter()
+        .enumerate()
+        .map(|(index, point)| {
+            !points.iter().enumerate().any(|(other_index, other)| {
+                other_index != index &&
+                other.no_worse_than(point) &&
+                (!other.same_figures(point) || other_index < index)
+            })
+        })
+        .collect();
+    let mut frontier: Vec<FrontierPoint> = points.into_iter().zip(keep).filter(|(_, keep)| *keep).map(|(point, _)| point).collect();
+    // Stable, so equal figures keep the run order
+    frontier.sort_by(|a, b| {
+        a.box_count
+            .cmp(&b.box_count)
+            .then(a.total_volume.total_cmp(&b.total_volume))
+            .then(a.total_cost.unwrap_or(0.0).total_cmp(&b.total_cost.unwrap_or(0.0)))
+    });
+    frontier
+}
+
+// Pack the items with every strategy under every objective, all other options unchanged
+pub fn utilization_frontier(items: Vec<Item>, options: &PackingOptions) -> Result<Vec<FrontierPoint>, PackError> {
+    validate_request(&items, options)?;
+    let runs: Vec<(Strategy, Objective)> = Strategy::ALL
+        .iter()
+        .flat_map(|&strategy| Objective::ALL.iter().map(move |&objective| (strategy, objective)))
+        .collect();
+    let jobs: Vec<platform::Job<'_, FrontierPoint>> = runs
+        .into_iter()
+        .map(|(strategy, objective)| -> platform::Job<'_, FrontierPoint> {
+            let (items, options) = (items.clone(), PackingOptions { strategy, objective, ..options.clone() });
+            Box::new(move || FrontierPoint::new(strategy, objective, pack_unchecked(items, &options)))
+        })
+        .collect();
+    Ok(non_dominated(platform::run_all(jobs)))
+}
@@ -0,0 +1,134 @@
+// Random but reproducible items for demos and stress tests
+use crate::search::XorShift;
+use crate::Item;
+use serde::{Deserialize, Serialize};
+
+// Sizes are rounded to millimetres and weights to 10 g, so the generated items read like real ones
+const SIZE_DECIMALS: i32 = 1;
+const WEIGHT_DECIMALS: i32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Range {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Range {
+    fn sample(&self, random: &mut XorShift) -> f64 {
+        let (low, high) = if self.min <= self.max { (self.min, self.max) } else { (self.max, self.min) };
+        low + random.unit() * (high - low)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DestinationShare {
+    pub destination: String,
+    // Relative weight among the destinations
+    pub share: f64,
+}
+
+// Ranges for the few large items of "mostly small plus a few oversized"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OversizedSpec {
+    // Fraction of the items, 0..1
+    pub share: f64,
+    pub length: Range,
+    pub width: Range,
+    pub height: Range,
+    pub weight: Range,
+}
+
+impl Default for OversizedSpec {
+    fn default() -> Self {
+        OversizedSpec {
+            share: 0.1,
+            length: Range { min: 50.0, max: 100.0 },
+            width: Range { min: 40.0, max: 60.0 },
+            height: Range { min: 30.0, max: 50.0 },
+            weight: Range { min: 10.0, max: 25.0 },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeneratorSpec {
+    pub count: usize,
+    pub destinations: Vec<DestinationShare>,
+    pub length: Range,
+    pub width: Range,
+    pub height: Range,
+    pub weight: Range,
+    // Bimodal mode: this share of the items is drawn from larger ranges
+    pub oversized: Option<OversizedSpec>,
+    // The same seed and spec always give the same items
+    pub seed: u64,
+}
+
+impl Default for GeneratorSpec {
+    fn default() -> Self {
+        GeneratorSpec {
+            count: 20,
+            destinations: ["USA", "UK", "Germany", "Japan", "Australia"]
+                .into_iter()
+                .map(|destination| DestinationShare {
+                    destination: destination.to_string(),
+                    share: 1.0,
+                })
+                .collect(),
+            length: Range { min: 10.0, max: 40.0 },
+            width: Range { min: 5.0, max: 30.0 },
+            height: Range { min: 2.0, max: 20.0 },
+            weight: Range { min: 0.1, max: 5.0 },
+            oversized: None,
+            seed: 1,
+        }
+    }
+}
+
+// Rounded to the given decimals, and never below the smallest step
+fn round_to(value: f64, decimals: i32) -> f64 {
+    let factor = 10f64.powi(decimals);
+    (value * factor).round().max(1.0) / factor
+}
+
+fn pick_destination(destinations: &[DestinationShare], random: &mut XorShift) -> String {
+    let total: f64 = destinations.iter().map(|d| d.share.max(0.0)).sum();
+    if total <= 0.0 {
+        return destinations.first().map_or_else(|| "USA".to_string(), |d| d.destination.clone());
+    }
+    let mut target = random.unit() * total;
+    for share in destinations {
+        target -= share.share.max(0.0);
+        if target < 0.0 {
+            return share.destination.clone();
+        }
+    }
+    destinations[destinations.len() - 1].destination.clone()
+}
+
+// Items GEN-0001, GEN-0002, ... drawn from the spec; sizes and weights are always positive,
+// so the result can go straight to pack_items
+pub fn generate_items(spec: &GeneratorSpec) -> Vec<Item> {
+    let mut random = XorShift::new(spec.seed);
+    (0..spec.count)
+        .map(|index| {
+            let destination = pick_destination(&spec.destinations, &mut random);
+            let oversized = spec.oversized.as_ref().filter(|oversized| random.unit() < oversized.share);
+            let (length, width, height, weight) = match oversized {
+                Some(o) => (o.length, o.width, o.height, o.weight),
+                None => (spec.length, spec.width, spec.height, spec.weight),
+            };
+            Item {
+                id: format!("GEN-{:04}", index + 1),
+                destination,
+                length: round_to(length.sample(&mut random), SIZE_DECIMALS),
+                width: round_to(width.sample(&mut random), SIZE_DECIMALS),
+                height: round_to(height.sample(&mut random), SIZE_DECIMALS),
+                weight: round_to(weight.sample(&mut random), WEIGHT_DECIMALS),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
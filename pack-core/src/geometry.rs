@@ -0,0 +1,224 @@
+// Positions and sizes as named fields rather than bare triples, which are easy to read back in the
+// wrong order. Both serialize as objects and also accept the `[x, y, z]` and
+// `[length, width, height]` arrays solutions and configs were written with before.
+use serde::{Deserialize, Serialize};
+
+// Offset of an item's corner nearest the box origin, in cm along length (x), width (y) and height (z)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "PositionForm")]
+pub struct Position {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+// Outer size of a box or an item, in cm
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "DimensionsForm")]
+pub struct Dimensions {
+    pub length: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PositionForm {
+    Fields { x: f64, y: f64, z: f64 },
+    Array((f64, f64, f64)),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DimensionsForm {
+    Fields { length: f64, width: f64, height: f64 },
+    Array((f64, f64, f64)),
+}
+
+impl From<PositionForm> for Position {
+    fn from(form: PositionForm) -> Self {
+        match form {
+            PositionForm::Fields { x, y, z } => Position { x, y, z },
+            PositionForm::Array(triple) => triple.into(),
+        }
+    }
+}
+
+impl From<DimensionsForm> for Dimensions {
+    fn from(form: DimensionsForm) -> Self {
+        match form {
+            DimensionsForm::Fields { length, width, height } => Dimensions { length, width, height },
+            DimensionsForm::Array(triple) => triple.into(),
+        }
+    }
+}
+
+impl Position {
+    pub const ORIGIN: Position = Position { x: 0.0, y: 0.0, z: 0.0 };
+
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Position { x, y, z }
+    }
+
+    // Coordinates indexed by axis, x first
+    pub fn to_array(self) -> [f64; 3] {
+        [self.x, self.y, self.z]
+    }
+
+    pub fn from_array([x, y, z]: [f64; 3]) -> Self {
+        Position { x, y, z }
+    }
+
+    // Every coordinate put through `f`, to scale or round
+    pub fn map(self, f: impl Fn(f64) -> f64) -> Self {
+        Position::new(f(self.x), f(self.y), f(self.z))
+    }
+}
+
+impl Dimensions {
+    pub fn new(length: f64, width: f64, height: f64) -> Self {
+        Dimensions { length, width, height }
+    }
+
+    // Sides indexed by axis, length first
+    pub fn to_array(self) -> [f64; 3] {
+        [self.length, self.width, self.height]
+    }
+
+    pub fn volume(self) -> f64 {
+        self.length * self.width * self.height
+    }
+
+    pub fn map(self, f: impl Fn(f64) -> f64) -> Self {
+        Dimensions::new(f(self.length), f(self.width), f(self.height))
+    }
+}
+
+impl From<(f64, f64, f64)> for Position {
+    fn from((x, y, z): (f64, f64, f64)) -> Self {
+        Position { x, y, z }
+    }
+}
+
+impl From<Position> for (f64, f64, f64) {
+    fn from(position: Position) -> Self {
+        (position.x, position.y, position.z)
+    }
+}
+
+impl From<(f64, f64, f64)> for Dimensions {
+    fn from((length, width, height): (f64, f64, f64)) -> Self {
+        Dimensions { length, width, height }
+    }
+}
+
+impl From<Dimensions> for (f64, f64, f64) {
+    fn from(dimensions: Dimensions) -> Self {
+        (dimensions.length, dimensions.width, dimensions.height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn position_round_trips_as_named_fields() {
+        let position = Position::new(1.5, 0.0, 12.25);
+        let serialized = serde_json::to_value(position).unwrap();
+        assert_eq!(serialized, json!({"x": 1.5, "y": 0.0, "z": 12.25}));
+        assert_eq!(serde_json::from_value::<Position>(serialized).unwrap(), position);
+    }
+
+    #[test]
+    fn position_accepts_the_legacy_array() {
+        let position: Position = serde_json::from_value(json!([1.5, 0.0, 12.25])).unwrap();
+        assert_eq!(position, Position::new(1.5, 0.0, 12.25));
+        // Written back in the named form
+        assert_eq!(serde_json::to_value(position).unwrap(), json!({"x": 1.5, "y": 0.0, "z": 12.25}));
+    }
+
+    #[test]
+    fn dimensions_round_trip_as_named_fields() {
+        let dimensions = Dimensions::new(40.0, 30.5, 2.0);
+        let serialized = serde_json::to_value(dimensions).unwrap();
+        assert_eq!(serialized, json!({"length": 40.0, "width": 30.5, "height": 2.0}));
+        assert_eq!(serde_json::from_value::<Dimensions>(serialized).unwrap(), dimensions);
+    }
+
+    #[test]
+    fn dimensions_accept_the_legacy_array() {
+        let dimensions: Dimensions = serde_json::from_value(json!([40.0, 30.5, 2.0])).unwrap();
+        assert_eq!(dimensions, Dimensions::new(40.0, 30.5, 2.0));
+        assert_eq!(serde_json::to_value(dimensions).unwrap(), json!({"length": 40.0, "width": 30.5, "height": 2.0}));
+    }
+
+    // Anything but three numbers, named or in order, is turned down
+    #[test]
+    fn malformed_forms_are_rejected() {
+        for value in [json!([1.0, 2.0]), json!([1.0, 2.0, 3.0, 4.0]), json!({"x": 1.0, "y": 2.0}), json!("1,2,3"), json!(null)] {
+            assert!(serde_json::from_value::<Position>(value.clone()).is_err(), "{value}");
+        }
+        for value in [json!([1.0, 2.0]), json!({"length": 1.0, "width": 2.0}), json!({"x": 1.0, "y": 2.0, "z": 3.0})] {
+            assert!(serde_json::from_value::<Dimensions>(value.clone()).is_err(), "{value}");
+        }
+    }
+
+    #[test]
+    fn tuples_convert_both_ways() {
+        let position = Position::from((1.0, 2.0, 3.0));
+        assert_eq!(<(f64, f64, f64)>::from(position), (1.0, 2.0, 3.0));
+        assert_eq!(Position::from_array(position.to_array()), position);
+        let dimensions = Dimensions::from((4.0, 5.0, 6.0));
+        assert_eq!(<(f64, f64, f64)>::from(dimensions), (4.0, 5.0, 6.0));
+        assert_eq!(dimensions.to_array(), [4.0, 5.0, 6.0]);
+    }
+
+    // The fields holding them in items, boxes and limits take either form
+    #[test]
+    fn items_and_limits_take_either_form() {
+        for (position, dimensions) in [
+            (json!({"x": 0.0, "y": 10.0, "z": 5.0}), json!({"length": 10.0, "width": 8.0, "height": 6.0})),
+            (json!([0.0, 10.0, 5.0]), json!([10.0, 8.0, 6.0])),
+        ] {
+            let item: crate::Item = serde_json::from_value(json!({
+                "id": "a",
+                "destination": "USA",
+                "length": 8.0,
+                "width": 10.0,
+                "height": 6.0,
+                "weight": 1.0,
+                "position": position,
+                "original_dimensions": dimensions,
+            }))
+            .unwrap();
+            assert_eq!(item.position, Some(Position::new(0.0, 10.0, 5.0)));
+            assert_eq!(item.original_dimensions, Some(Dimensions::new(10.0, 8.0, 6.0)));
+            let serialized = serde_json::to_value(&item).unwrap();
+            assert_eq!(serialized["position"], json!({"x": 0.0, "y": 10.0, "z": 5.0}));
+            assert_eq!(serialized["original_dimensions"], json!({"length": 10.0, "width": 8.0, "height": 6.0}));
+            assert_eq!(serde_json::from_value::<crate::Item>(serialized).unwrap(), item);
+
+            let limits: crate::DestinationConstraints = serde_json::from_value(json!({
+                "max_box_dimension": 60.0,
+                "max_box_weight": 20.0,
+                "alternative_dimensions": dimensions,
+            }))
+            .unwrap();
+            assert_eq!(limits.alternative_dimensions, Some(Dimensions::new(10.0, 8.0, 6.0)));
+        }
+    }
+
+    // A saved solution, positions and original dimensions included, reads back as it was written
+    #[test]
+    fn solutions_round_trip() {
+        let items = (0..6).map(|i| crate::tests::item(&format!("I{i}"), "USA", (30.0 - i as f64, 5.0 + i as f64, 8.0), 0.5)).collect();
+        let solution = crate::pack_with_options(items, &crate::PackingOptions::default()).unwrap();
+        let serialized = serde_json::to_value(&solution).unwrap();
+        let read_back: crate::PackingSolution = serde_json::from_value(serialized.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&read_back).unwrap(), serialized);
+        assert!(serialized["boxes"][0]["items"][0]["position"].is_object());
+        assert!(serialized["boxes"][0]["items"][0]["original_dimensions"].is_object());
+    }
+}
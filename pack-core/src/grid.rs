@@ -0,0 +1,178 @@
+// Regular row/column/layer packing for runs of identical items
+use crate::{
+    carton_weight, clearance, dunnage_weight, min_option, reservation, reserved_floor, stats, within_dimension_limits, within_value_cap,
+    DestinationConstraints, Item, PackedBox, PackingOptions, Position,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub const DEFAULT_GRID_MIN_ITEMS: usize = 8;
+
+// Slack for cells that tile a limit exactly (3 × 21 cm in a 63 cm box)
+const EPSILON: f64 = 1e-9;
+
+// "4 × 3 × 2 = 24": item counts along length, width and height, and the size of one cell
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GridLayout {
+    pub counts: (usize, usize, usize),
+    // Placed dimensions of one item
+    pub cell: (f64, f64, f64),
+    // Number of grid items; they are the first items of the box
+    pub item_count: usize,
+}
+
+struct GridPlan {
+    rotation: usize,
+    counts: (usize, usize, usize),
+    cell: (f64, f64, f64),
+}
+
+impl GridPlan {
+    fn count(&self) -> usize {
+        self.counts.0 * self.counts.1 * self.counts.2
+    }
+}
+
+fn fits_per_axis(limit: f64, cell: f64) -> usize {
+    ((limit + EPSILON) / cell).floor() as usize
+}
+
+// Largest grid of at most `available` items the destination accepts, preferring compact boxes on ties.
+// `unit` carries the heaviest weight, highest value and tightest stacking limit in the run.
+fn plan_grid(unit: &Item, available: usize, constraints: &DestinationConstraints, options: &PackingOptions) -> Option<GridPlan> {
+    let (max_length, max_width, max_height) = constraints.axis_limits();
+    let clearance = clearance(&unit.destination, unit.service.as_deref(), options);
+    let (max_length, max_width, max_height) = (max_length - clearance[0], max_width - clearance[1], max_height - clearance[2]);
+    let reserved = reservation(options).unwrap_or_default();
+    let floor = reserved_floor(Some(&reserved), clearance);
+    let mut best: Option<(GridPlan, f64)> = None;
+
+    for rotation in unit.allowed_rotations() {
+        let rotated = unit.with_rotation(rotation);
+        let cell = (rotated.length, rotated.width, rotated.height);
+        if cell.0 <= 0.0 || cell.1 <= 0.0 || cell.2 <= 0.0 {
+            return None;
+        }
+
+        for nx in 1..=fits_per_axis(max_length, cell.0).min(available) {
+            for ny in 1..=fits_per_axis(max_width, cell.1).min(available / nx) {
+                // Tallest stack that still satisfies every limit; liquids only cover the floor, and the
+                // bottom layer carries all the layers above it
+                let mut stack_limit = match unit.max_height_above_cm {
+                    _ if unit.is_liquid => 1,
+                    Some(limit) => fits_per_axis(max_height, cell.2).min(fits_per_axis(limit, cell.2) + 1),
+                    None => fits_per_axis(max_height, cell.2),
+                };
+                if let Some(limit) = unit.max_load_kg.filter(|_| unit.weight > 0.0) {
+                    stack_limit = stack_limit.min(fits_per_axis(limit, unit.weight) + 1);
+                }
+                let top = stack_limit.min(available / (nx * ny));
+                let accepted = (1..=top).rev().find(|&nz| {
+                    let count = (nx * ny * nz) as f64;
+                    let (length, width, height) = (
+                        (nx as f64 * cell.0 + clearance[0]).max(floor.0),
+                        (ny as f64 * cell.1 + clearance[1]).max(floor.1),
+                        nz as f64 * cell.2 + clearance[2],
+                    );
+                    within_dimension_limits(length, width, height, constraints) &&
+                    count * unit.weight +
+                        reserved.weight +
+                        carton_weight(&options.material, length, width, height) +
+                        dunnage_weight(options.dunnage.as_ref(), (length, width, height), count * unit.volume()) <=
+                        constraints.max_box_weight &&
+                    within_value_cap(count * unit.declared_value() + reserved.value, constraints)
+                });
+
+                if let Some(nz) = accepted {
+                    let plan = GridPlan { rotation, counts: (nx, ny, nz), cell };
+                    let (length, width, height) = (nx as f64 * cell.0, ny as f64 * cell.1, nz as f64 * cell.2);
+                    let area = 2.0 * (length * width + length * height + width * height);
+                    let better = best.as_ref().is_none_or(|(current, current_area)| {
+                        plan.count() > current.count() || (plan.count() == current.count() && area < *current_area)
+                    });
+                    if better {
+                        best = Some((plan, area));
+                    }
+                }
+            }
+        }
+    }
+
+    best.map(|(plan, _)| plan)
+}
+
+fn fill_grid_box(items: Vec<Item>, plan: &GridPlan, unit: &Item, options: &PackingOptions) -> PackedBox {
+    let mut box_data = PackedBox::cut(&unit.destination, unit.service.as_deref(), options);
+    let (nx, ny, _) = plan.counts;
+    let item_count = items.len();
+
+    // Rows along the length, then columns, then layers from the bottom up
+    for (slot, item) in items.into_iter().enumerate() {
+        let (i, j, k) = (slot % nx, (slot / nx) % ny, slot / (nx * ny));
+        let position = Position::new(i as f64 * plan.cell.0, j as f64 * plan.cell.1, k as f64 * plan.cell.2);
+        box_data.add_item(item.with_rotation(plan.rotation), position);
+    }
+
+    box_data.grid = Some(GridLayout {
+        counts: plan.counts,
+        cell: plan.cell,
+        item_count,
+    });
+    box_data
+}
+
+// Pack full grid boxes for every large enough run of identical items, pushing them onto `boxes`,
+// and return the items left for the general heuristic in their original order
+pub fn pack_grids(
+    items: Vec<Item>,
+    constraints: &DestinationConstraints,
+    options: &PackingOptions,
+    boxes: &mut Vec<PackedBox>,
+) -> Vec<Item> {
+    // Catalog cartons have fixed sizes, so there is no grid box to cut, and a grid spreads past the
+    // footprint of its first item
+    let min_items = options.grid_min_items.unwrap_or(DEFAULT_GRID_MIN_ITEMS);
+    if min_items == 0 || items.len() < min_items || !options.box_catalog.is_empty() || options.lock_footprint_after_first_item {
+        return items;
+    }
+
+    // Runs keyed by exact dimensions, in order of first appearance
+    let mut runs: Vec<Vec<usize>> = Vec::new();
+    let mut run_of: HashMap<(u64, u64, u64), usize> = HashMap::new();
+    for (index, item) in items.iter().enumerate() {
+        let key = (item.length.to_bits(), item.width.to_bits(), item.height.to_bits());
+        let run = *run_of.entry(key).or_insert_with(|| {
+            runs.push(Vec::new());
+            runs.len() - 1
+        });
+        runs[run].push(index);
+    }
+
+    let mut slots: Vec<Option<Item>> = items.into_iter().map(Some).collect();
+    for run in runs.into_iter().filter(|run| run.len() >= min_items) {
+        let Some(mut unit) = slots[run[0]].clone() else {
+            continue;
+        };
+        for item in run.iter().filter_map(|&index| slots[index].as_ref()) {
+            unit.weight = unit.weight.max(item.weight);
+            unit.value = Some(unit.declared_value().max(item.declared_value()));
+            unit.max_height_above_cm = min_option(unit.max_height_above_cm, item.max_height_above_cm);
+            unit.max_load_kg = min_option(unit.max_load_kg, item.max_load_kg);
+        }
+
+        let Some(plan) = plan_grid(&unit, run.len(), constraints, options) else {
+            continue;
+        };
+        if plan.count() < 2 {
+            continue;
+        }
+
+        for chunk in run.chunks_exact(plan.count()) {
+            let grid_items = chunk.iter().filter_map(|&index| slots[index].take()).collect();
+            boxes.push(fill_grid_box(grid_items, &plan, &unit, options));
+            stats::record(|stats| stats.boxes_opened += 1);
+        }
+    }
+
+    slots.into_iter().flatten().collect()
+}
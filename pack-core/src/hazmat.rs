@@ -0,0 +1,118 @@
+// Limited-quantity dangerous goods: classes that may not share a box, a cap on the total quantity
+// one box holds, and classes a destination refuses outright. Items without a hazmat_class are never
+// restricted; quantities are in whatever unit the rules are written in (Wh, ml), summed as given.
+use crate::{find_destination_entry, Item, PackedBox};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HazmatRules {
+    // Classes that may not be packed together, either way round
+    pub forbidden_pairs: Vec<(String, String)>,
+    // Total hazmat_quantity of one box, all classes together
+    pub max_quantity_per_box: Option<f64>,
+    // Classes each destination refuses, by destination name
+    pub banned_classes: HashMap<String, Vec<String>>,
+}
+
+// Hazmat of one class in a box, for its labels
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HazmatContent {
+    pub class: String,
+    pub items: usize,
+    pub quantity: f64,
+}
+
+fn same_class(a: &str, b: &str) -> bool {
+    a.trim().eq_ignore_ascii_case(b.trim())
+}
+
+fn quantity(item: &Item) -> f64 {
+    match item.hazmat_class {
+        Some(_) => item.hazmat_quantity.unwrap_or(0.0),
+        None => 0.0,
+    }
+}
+
+impl HazmatRules {
+    fn forbids(&self, a: &str, b: &str) -> bool {
+        self.forbidden_pairs
+            .iter()
+            .any(|(x, y)| (same_class(x, a) && same_class(y, b)) || (same_class(x, b) && same_class(y, a)))
+    }
+
+    fn banned(&self, class: &str, destination: &str) -> bool {
+        find_destination_entry(&self.banned_classes, destination)
+            .is_some_and(|classes| classes.iter().any(|banned| same_class(banned, class)))
+    }
+
+    // Whether the item can't be packed for the destination at all: its class is banned there, or
+    // it alone is over the per-box quantity
+    pub fn refuses(&self, item: &Item, destination: &str) -> bool {
+        item.hazmat_class.as_deref().is_some_and(|class| {
+            self.banned(class, destination) || self.max_quantity_per_box.is_some_and(|limit| quantity(item) > limit)
+        })
+    }
+
+    // Whether the item may join the box's items
+    pub fn allows(&self, box_data: &PackedBox, item: &Item) -> bool {
+        let Some(class) = item.hazmat_class.as_deref() else {
+            return true;
+        };
+        let mut classes = box_data.items.iter().filter_map(|other| other.hazmat_class.as_deref());
+        if classes.any(|other| self.forbids(class, other)) {
+            return false;
+        }
+        self.max_quantity_per_box
+            .is_none_or(|limit| box_data.items.iter().map(quantity).sum::<f64>() + quantity(item) <= limit)
+    }
+
+    // Every rule the box breaks, for the audit
+    pub fn violations(&self, box_data: &PackedBox) -> Vec<String> {
+        let mut messages = Vec::new();
+        let contents = contents(&box_data.items);
+        for (index, a) in contents.iter().enumerate() {
+            for (offset, b) in contents[index..].iter().enumerate() {
+                // A class forbidden with itself only counts with two items of it
+                let together = offset > 0 || a.items > 1;
+                if together && self.forbids(&a.class, &b.class) {
+                    messages.push(format!("hazard classes {} and {} share the box", a.class, b.class));
+                }
+            }
+        }
+        let total: f64 = contents.iter().map(|content| content.quantity).sum();
+        if let Some(limit) = self.max_quantity_per_box.filter(|&limit| total > limit) {
+            messages.push(format!("hazmat quantity {} is over the {} a box may hold", total, limit));
+        }
+        for content in &contents {
+            if self.banned(&content.class, &box_data.destination) {
+                messages.push(format!("hazard class {} is not accepted for {}", content.class, box_data.destination));
+            }
+        }
+        messages
+    }
+}
+
+// Hazmat of the items by class, in class order
+pub fn contents(items: &[Item]) -> Vec<HazmatContent> {
+    let mut contents: Vec<HazmatContent> = Vec::new();
+    for item in items {
+        let Some(class) = item.hazmat_class.as_deref() else {
+            continue;
+        };
+        match contents.iter_mut().find(|content| same_class(&content.class, class)) {
+            Some(content) => {
+                content.items += 1;
+                content.quantity += quantity(item);
+            }
+            None => contents.push(HazmatContent {
+                class: class.trim().to_string(),
+                items: 1,
+                quantity: quantity(item),
+            }),
+        }
+    }
+    contents.sort_by(|a, b| a.class.cmp(&b.class));
+    contents
+}
@@ -0,0 +1,256 @@
+// Background packing for very large imports: the items are packed chunk by chunk on a worker
+// thread with progress events, and the boxes are fetched page by page
+use crate::{
+    box_usage, destination_summaries, index_boxes, labels, normalize_destination, order_spans, order_splits, pack_catching_panics,
+    packing_efficiency, reserved_items_placed, resolve_destination, sort_by_volume, validate_request, BoxSpecUsage, DestinationSummary, Item,
+    OrderSpan, OrderSplit, PackError, PackedBox, PackingOptions, UnpackedDetail,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+// Event emitted after every chunk, with a JobProgress payload
+pub const PROGRESS_EVENT: &str = "packing-job-progress";
+
+// Large destinations are split into chunks of at most this many items
+const CHUNK_ITEMS: usize = 1000;
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct JobProgress {
+    pub job_id: String,
+    pub chunks_done: usize,
+    pub chunks_total: usize,
+    // Destination of the chunk just finished
+    pub destination: String,
+    pub packed_count: usize,
+    pub finished: bool,
+}
+
+// Aggregate figures of a job, complete once `finished` is set
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct JobSummary {
+    pub progress: JobProgress,
+    pub box_count: usize,
+    pub packed_count: usize,
+    pub total_volume: f64,
+    pub total_cost: Option<f64>,
+    pub total_packaging_cost: Option<f64>,
+    pub warnings: Vec<String>,
+    pub unpacked_items: Vec<Item>,
+    pub unpacked_details: Vec<UnpackedDetail>,
+    pub box_usage: Vec<BoxSpecUsage>,
+    pub order_spans: Vec<OrderSpan>,
+    pub order_splits: Vec<OrderSplit>,
+    pub destination_summaries: Vec<DestinationSummary>,
+    pub packing_efficiency: f64,
+    pub wasted_volume: f64,
+    // Indices of each destination's boxes across all pages
+    #[serde(serialize_with = "crate::sorted_map")]
+    pub boxes_by_destination: HashMap<String, Vec<usize>>,
+    // Why the job stopped early, if it did
+    pub error: Option<PackError>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobPage {
+    pub summary: JobSummary,
+    // Zero-based
+    pub page: usize,
+    pub page_size: usize,
+    pub page_count: usize,
+    pub boxes: Vec<PackedBox>,
+}
+
+struct Job {
+    summary: JobSummary,
+    boxes: Vec<PackedBox>,
+}
+
+// Jobs of this session by id; results stay until discarded
+#[derive(Default)]
+pub struct JobsState {
+    jobs: Arc<Mutex<HashMap<String, Job>>>,
+    next_id: Mutex<u64>,
+}
+
+// Items grouped by destination, in the fixed destination order the packer uses, and split into chunks
+fn chunks(items: Vec<Item>, options: &PackingOptions) -> Vec<(String, Vec<Item>)> {
+    let mut by_destination: HashMap<String, Vec<Item>> = HashMap::new();
+    for item in items {
+        let key = resolve_destination(&item.destination, options).unwrap_or_else(|| normalize_destination(&item.destination));
+        by_destination.entry(key).or_default().push(item);
+    }
+    let mut destinations: Vec<(String, Vec<Item>)> = by_destination.into_iter().collect();
+    destinations.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut chunks = Vec::new();
+    for (destination, mut items) in destinations {
+        // Largest first across the whole destination, so every chunk isn't left with its own small items
+        sort_by_volume(&mut items);
+        while !items.is_empty() {
+            let rest = items.split_off(items.len().min(CHUNK_ITEMS));
+            chunks.push((destination.clone(), std::mem::replace(&mut items, rest)));
+        }
+    }
+    chunks
+}
+
+// Pack the chunks one after the other into the job, catalog stock carrying over between them.
+// Stops early when the job is discarded.
+fn run_job(
+    job_id: &str,
+    chunks: Vec<(String, Vec<Item>)>,
+    options: &PackingOptions,
+    jobs: &Mutex<HashMap<String, Job>>,
+    on_progress: impl Fn(&JobProgress),
+) {
+    let mut chunk_options = options.clone();
+    let chunks_total = chunks.len();
+    for (index, (destination, items)) in chunks.into_iter().enumerate() {
+        let result = pack_catching_panics(items, &chunk_options).and_then(reserved_items_placed);
+
+        let Ok(mut jobs) = jobs.lock() else {
+            return;
+        };
+        let Some(job) = jobs.get_mut(job_id) else {
+            return;
+        };
+        let solution = match result {
+            Ok(solution) => solution,
+            Err(error) => {
+                job.summary.error = Some(error);
+                job.summary.progress.finished = true;
+                return;
+            }
+        };
+
+        for (spec, usage) in chunk_options.box_catalog.iter_mut().zip(&solution.box_usage) {
+            spec.available = spec.available.map(|available| available.saturating_sub(usage.used));
+        }
+        let summary = &mut job.summary;
+        summary.packed_count += solution.packed_count;
+        summary.total_volume += solution.total_volume;
+        summary.total_cost = solution.total_cost.map(|cost| cost + summary.total_cost.unwrap_or(0.0));
+        summary.total_packaging_cost = solution
+            .total_packaging_cost
+            .map(|cost| cost + summary.total_packaging_cost.unwrap_or(0.0));
+        summary.warnings.extend(solution.warnings);
+        summary.unpacked_items.extend(solution.unpacked_items);
+        summary.unpacked_details.extend(solution.unpacked_details);
+        job.boxes.extend(solution.boxes);
+        summary.box_count = job.boxes.len();
+        // Chunk-local box indices become indices into all of the job's boxes, and the labels number
+        // the boxes of every chunk so far, as pages are served while the job runs
+        summary.boxes_by_destination = index_boxes(&mut job.boxes);
+        labels::assign_labels(&mut job.boxes, options);
+        if index + 1 == chunks_total {
+            summary.box_usage = box_usage(&job.boxes, options);
+            summary.order_spans = order_spans(&job.boxes);
+            summary.destination_summaries = destination_summaries(&job.boxes);
+            (summary.packing_efficiency, summary.wasted_volume) = packing_efficiency(&job.boxes);
+            if options.separate_orders {
+                summary.order_splits = order_splits(&job.boxes, &summary.unpacked_items);
+            }
+        }
+        summary.progress = JobProgress {
+            job_id: job_id.to_string(),
+            chunks_done: index + 1,
+            chunks_total,
+            destination,
+            packed_count: summary.packed_count,
+            finished: index + 1 == chunks_total,
+        };
+
+        let progress = summary.progress.clone();
+        drop(jobs);
+        on_progress(&progress);
+    }
+}
+
+impl JobsState {
+    // Validate the request and start packing it on a worker thread
+    pub fn start(&self, items: Vec<Item>, options: PackingOptions, on_progress: impl Fn(&JobProgress) + Send + 'static) -> Result<String, PackError> {
+        validate_request(&items, &options)?;
+
+        let job_id = {
+            let mut next_id = self.next_id.lock().map_err(|_| PackError::InternalError {
+                message: "job counter unavailable".to_string(),
+            })?;
+            *next_id += 1;
+            format!("job-{}", *next_id)
+        };
+        let chunks = chunks(items, &options);
+        let summary = JobSummary {
+            progress: JobProgress {
+                job_id: job_id.clone(),
+                chunks_total: chunks.len(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        self.jobs
+            .lock()
+            .map_err(|_| PackError::InternalError {
+                message: "job table unavailable".to_string(),
+            })?
+            .insert(job_id.clone(), Job { summary, boxes: Vec::new() });
+
+        let jobs = Arc::clone(&self.jobs);
+        let id = job_id.clone();
+        std::thread::spawn(move || run_job(&id, chunks, &options, &jobs, on_progress));
+        Ok(job_id)
+    }
+
+    // One page of boxes along with the summary; boxes appear as their chunks finish
+    pub fn page(&self, job_id: &str, page: usize, page_size: Option<usize>) -> Option<JobPage> {
+        let page_size = page_size.unwrap_or(DEFAULT_PAGE_SIZE).max(1);
+        let jobs = self.jobs.lock().ok()?;
+        let job = jobs.get(job_id)?;
+        Some(JobPage {
+            summary: job.summary.clone(),
+            page,
+            page_size,
+            page_count: job.boxes.len().div_ceil(page_size),
+            boxes: job.boxes.iter().skip(page * page_size).take(page_size).cloned().collect(),
+        })
+    }
+
+    pub fn discard(&self, job_id: &str) -> bool {
+        self.jobs.lock().is_ok_and(|mut jobs| jobs.remove(job_id).is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::item;
+
+    // 1,500 items for one destination pack in two chunks, labelled as one run: every label once,
+    // numbered through both chunks and counting all of the job's boxes
+    #[test]
+    fn labels_number_the_boxes_of_every_chunk() {
+        let items = (0..1500).map(|i| item(&format!("I{i}"), "USA", (20.0, 20.0, 20.0), 15.0 + (i % 4) as f64)).collect();
+        let options = PackingOptions {
+            label_template: Some("{destination_code}-{seq:04}/{total}".to_string()),
+            ..Default::default()
+        };
+        let state = JobsState::default();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let job_id = state
+            .start(items, options, move |progress| {
+                if progress.finished {
+                    let _ = sender.send(());
+                }
+            })
+            .unwrap();
+        receiver.recv().unwrap();
+        let page = state.page(&job_id, 0, Some(usize::MAX)).unwrap();
+        assert_eq!(page.summary.progress.chunks_total, 2);
+        let total = page.boxes.len();
+        let labels: Vec<&str> = page.boxes.iter().map(|b| b.label.as_str()).collect();
+        let expected: Vec<String> = (1..=total).map(|seq| format!("US-{seq:04}/{total}")).collect();
+        assert_eq!(labels, expected);
+    }
+}
@@ -0,0 +1,206 @@
+// Box labels from PackingOptions.label_template, e.g. "{destination_code}-{seq:03}/{total}".
+// Placeholders are destination, destination_code, seq (the box's number within its destination),
+// total (boxes of the destination), order_id (with separate_orders) and date (YYYY-MM-DD); a
+// number after a colon pads to that width, with zeros when it starts with 0. "{{" and "}}" are
+// literal braces. Templates are checked with the other options, before anything is packed.
+use crate::{canonical_destination, platform, PackedBox, PackingOptions, DESTINATION_ALIASES};
+
+// The labels the export had before templates: "USA-002"
+pub const DEFAULT_LABEL_TEMPLATE: &str = "{destination}-{seq:03}";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Destination,
+    DestinationCode,
+    Sequence,
+    Total,
+    OrderId,
+    Date,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Text(String),
+    Placeholder { field: Field, width: usize, zero_pad: bool },
+}
+
+fn field(name: &str) -> Option<Field> {
+    match name {
+        "destination" => Some(Field::Destination),
+        "destination_code" => Some(Field::DestinationCode),
+        "seq" => Some(Field::Sequence),
+        "total" => Some(Field::Total),
+        "order_id" => Some(Field::OrderId),
+        "date" => Some(Field::Date),
+        _ => None,
+    }
+}
+
+fn parse(template: &str) -> Result<Vec<Segment>, String> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '}' => return Err("has a \"}\" without a matching \"{\"; write \"}}\" for a literal brace".to_string()),
+            '{' => {
+                let mut inner = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => inner.push(c),
+                        None => return Err(format!("has an unclosed placeholder \"{{{}\"", inner)),
+                    }
+                }
+                let (name, spec) = inner.split_once(':').unwrap_or((&inner, ""));
+                let field = field(name.trim()).ok_or_else(|| {
+                    format!(
+                        "has an unknown placeholder \"{{{}}}\"; use destination, destination_code, seq, total, order_id or date",
+                        inner
+                    )
+                })?;
+                let width = match spec {
+                    "" => 0,
+                    spec => spec
+                        .parse::<usize>()
+                        .map_err(|_| format!("has \"{{{}}}\", whose width is not a number", inner))?,
+                };
+                if !text.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut text)));
+                }
+                segments.push(Segment::Placeholder {
+                    field,
+                    width,
+                    zero_pad: spec.starts_with('0'),
+                });
+            }
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    Ok(segments)
+}
+
+// Why the template can't be used with these options, if it can't
+pub fn check_template(template: &str, options: &PackingOptions) -> Result<(), String> {
+    let segments = parse(template)?;
+    let uses_order = segments
+        .iter()
+        .any(|segment| matches!(segment, Segment::Placeholder { field: Field::OrderId, .. }));
+    if uses_order && !options.separate_orders {
+        return Err("uses {order_id}, which needs separate_orders so every box holds one order".to_string());
+    }
+    Ok(())
+}
+
+// ISO two-letter code of a built-in destination, the name itself for others ("UK" -> "GB")
+fn destination_code(destination: &str) -> String {
+    canonical_destination(destination)
+        .and_then(|canonical| DESTINATION_ALIASES.iter().find(|(name, _, _)| *name == canonical))
+        .map_or_else(|| destination.to_string(), |(_, code, _)| code.to_string())
+}
+
+// Today's date in UTC, YYYY-MM-DD
+fn today() -> String {
+    let days = (platform::since_epoch().as_secs() / 86_400) as i64;
+    // Days since 1970-01-01 to a civil date, counting in 400-year eras from 0000-03-01
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn pad(value: String, width: usize, zero_pad: bool) -> String {
+    if zero_pad {
+        format!("{:0>width$}", value, width = width)
+    } else {
+        format!("{:>width$}", value, width = width)
+    }
+}
+
+// Label every box from the template, numbering the boxes of each destination in their order;
+// an unusable template, which validation turns down first, falls back to the default
+pub(crate) fn assign_labels(boxes: &mut [PackedBox], options: &PackingOptions) {
+    let template = options.label_template.as_deref().unwrap_or(DEFAULT_LABEL_TEMPLATE);
+    let segments = match parse(template) {
+        Ok(segments) => segments,
+        Err(_) => parse(DEFAULT_LABEL_TEMPLATE).unwrap_or_default(),
+    };
+    let date = options.label_date.clone().unwrap_or_else(today);
+
+    let destinations: Vec<String> = boxes.iter().map(|b| b.destination.clone()).collect();
+    for (index, box_data) in boxes.iter_mut().enumerate() {
+        let destination = &destinations[index];
+        let sequence = destinations[..=index].iter().filter(|other| *other == destination).count();
+        let total = destinations.iter().filter(|other| *other == destination).count();
+        box_data.label = segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text.clone(),
+                Segment::Placeholder { field, width, zero_pad } => {
+                    let value = match field {
+                        Field::Destination => destination.clone(),
+                        Field::DestinationCode => destination_code(destination),
+                        Field::Sequence => sequence.to_string(),
+                        Field::Total => total.to_string(),
+                        Field::OrderId => box_data.order_id().unwrap_or_default().to_string(),
+                        Field::Date => date.clone(),
+                    };
+                    pad(value, *width, *zero_pad)
+                }
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every built-in destination, by its name and by each of its spellings, has its ISO code;
+    // names not built in stay as they are
+    #[test]
+    fn built_in_destinations_have_their_iso_codes() {
+        let expected = [
+            ("Australia", "AU"),
+            ("USA", "US"),
+            ("UK", "GB"),
+            ("Germany", "DE"),
+            ("Japan", "JP"),
+            ("Canada", "CA"),
+            ("France", "FR"),
+            ("Italy", "IT"),
+            ("Spain", "ES"),
+            ("Netherlands", "NL"),
+            ("Belgium", "BE"),
+            ("Sweden", "SE"),
+            ("Poland", "PL"),
+        ];
+        assert_eq!(DESTINATION_ALIASES.len(), expected.len());
+        for ((name, code, aliases), (expected_name, expected_code)) in DESTINATION_ALIASES.iter().zip(expected) {
+            assert_eq!((*name, *code), (expected_name, expected_code));
+            assert_eq!(destination_code(name), expected_code);
+            for alias in *aliases {
+                assert_eq!(destination_code(alias), expected_code, "{alias}");
+            }
+        }
+        assert_eq!(destination_code("Sverige"), "SE");
+        assert_eq!(destination_code("Atlantis"), "Atlantis");
+    }
+}
@@ -0,0 +1,226 @@
+// Layer builder for flat goods: fill a horizontal layer, then start the next on top of it
+use crate::{box_fits_constraints, fits_constraints, overhang_ok, stack_limits_ok, stats, DestinationConstraints, Item, PackedBox, PackingOptions, Position};
+use std::cmp::Ordering;
+
+pub const DEFAULT_LAYER_TOLERANCE_CM: f64 = 0.5;
+
+// A layer covering less of its box footprint than this is left to the general algorithm
+const MIN_LAYER_FILL: f64 = 0.75;
+
+// An item lying flat, with both in-plane orientations at hand
+struct FlatItem {
+    flat: Item,
+    turned: Item,
+}
+
+impl FlatItem {
+    // Lowest orientation that the destination accepts, if any; liquids are left to the general
+    // algorithm since they can't go in an upper layer
+    fn new(item: &Item, constraints: &DestinationConstraints) -> Option<FlatItem> {
+        if item.is_liquid {
+            return None;
+        }
+        let orientations: Vec<Item> = item.allowed_rotations().into_iter()
+            .map(|rot| item.with_rotation(rot))
+            .filter(|rotated| fits_constraints(rotated, constraints))
+            .collect();
+        let flat = orientations
+            .iter()
+            .min_by(|a, b| a.height.partial_cmp(&b.height).unwrap_or(Ordering::Equal))
+            .cloned()?;
+        let turned = orientations
+            .iter()
+            .find(|o| o.height == flat.height && o.length == flat.width && o.width == flat.length)
+            .cloned()
+            .unwrap_or_else(|| flat.clone());
+        Some(FlatItem { flat, turned })
+    }
+
+    fn height(&self) -> f64 {
+        self.flat.height
+    }
+
+    fn into_item(self) -> Item {
+        self.flat
+    }
+}
+
+struct Layer {
+    // Items with their in-layer (x, y) position, already oriented
+    placements: Vec<(Item, f64, f64)>,
+    length: f64,
+    width: f64,
+    height: f64,
+    area: f64,
+}
+
+// Shelf packing of rectangles into a 2D extent: rows along the first axis, stacked along the second
+#[derive(Clone)]
+pub(crate) struct Shelves {
+    extent: (f64, f64),
+    // (offset along the second axis, depth, cursor along the first axis) per shelf
+    rows: Vec<(f64, f64, f64)>,
+}
+
+impl Shelves {
+    pub(crate) fn new(extent: (f64, f64)) -> Self {
+        Shelves { extent, rows: Vec::new() }
+    }
+
+    // Position for an a × b rectangle: on an existing shelf deep enough, else on a new shelf
+    pub(crate) fn place(&mut self, a: f64, b: f64) -> Option<(f64, f64)> {
+        let extent = self.extent;
+        if let Some(row) = self.rows.iter_mut().find(|(_, depth, cursor)| b <= *depth && cursor + a <= extent.0) {
+            let position = (row.2, row.0);
+            row.2 += a;
+            return Some(position);
+        }
+        let offset = self.rows.last().map_or(0.0, |(offset, depth, _)| offset + depth);
+        if a <= extent.0 && offset + b <= extent.1 {
+            self.rows.push((offset, b, a));
+            return Some((0.0, offset));
+        }
+        None
+    }
+}
+
+// One layer within the footprint; items that don't fit are handed back
+fn build_layer(items: Vec<FlatItem>, footprint: (f64, f64), height: f64) -> (Layer, Vec<FlatItem>) {
+    let mut layer = Layer {
+        placements: Vec::new(),
+        length: 0.0,
+        width: 0.0,
+        height,
+        area: 0.0,
+    };
+    let mut rest = Vec::new();
+    let mut shelves = Shelves::new(footprint);
+
+    for item in items {
+        let placed = [&item.flat, &item.turned].into_iter().find_map(|orientation| {
+            shelves
+                .place(orientation.length, orientation.width)
+                .map(|(x, y)| (orientation.clone(), x, y))
+        });
+
+        match placed {
+            Some((oriented, x, y)) => {
+                layer.length = layer.length.max(x + oriented.length);
+                layer.width = layer.width.max(y + oriented.width);
+                layer.area += oriented.length * oriented.width;
+                layer.placements.push((oriented, x, y));
+            }
+            None => rest.push(item),
+        }
+    }
+
+    (layer, rest)
+}
+
+// The box with the layer stacked on top, if the result stays within the limits
+fn stack_layer(
+    box_data: &PackedBox,
+    layer: &Layer,
+    constraints: &DestinationConstraints,
+    options: &PackingOptions,
+) -> Option<PackedBox> {
+    let base = box_data.height;
+    // Items of the layer rest on the box as it was, not on each other
+    let rests_well = |(item, x, y): &(Item, f64, f64)| {
+        overhang_ok(box_data, item, Position::new(*x, *y, base), options) && stack_limits_ok(box_data, item, Position::new(*x, *y, base))
+    };
+    if !layer.placements.iter().all(rests_well) {
+        return None;
+    }
+    let mut stacked = box_data.clone();
+    for (item, x, y) in &layer.placements {
+        stacked.add_item(item.clone(), Position::new(*x, *y, base));
+    }
+    // Shorter items within the tolerance still take the full layer height
+    stacked.height = stacked.height.max(base + layer.height);
+    stacked.length = stacked.length.max(layer.length);
+    stacked.width = stacked.width.max(layer.width);
+    stacked.update_box_weight();
+    box_fits_constraints(&stacked, constraints).then_some(stacked)
+}
+
+// Pack items of similar height in layers, pushing the boxes onto `boxes`, and return
+// the items that don't make a clean layer for the general algorithm
+pub fn pack_layers(
+    items: Vec<Item>,
+    constraints: &DestinationConstraints,
+    options: &PackingOptions,
+    boxes: &mut Vec<PackedBox>,
+) -> Vec<Item> {
+    // Catalog cartons have fixed sizes, the footprint isn't ours to choose; a locked one is the
+    // first item's, which a layer spreads past
+    if !options.box_catalog.is_empty() || options.lock_footprint_after_first_item {
+        return items;
+    }
+    let tolerance = options.layer_tolerance_cm.unwrap_or(DEFAULT_LAYER_TOLERANCE_CM).max(0.0);
+    let (max_length, max_width, _) = constraints.axis_limits();
+
+    let mut leftovers = Vec::new();
+    let mut flat_items = Vec::new();
+    for item in items {
+        match FlatItem::new(&item, constraints) {
+            Some(flat) => flat_items.push(flat),
+            None => leftovers.push(item),
+        }
+    }
+    flat_items.sort_by(|a, b| b.height().partial_cmp(&a.height()).unwrap_or(Ordering::Equal));
+
+    // Height groups: each spans at most the tolerance, measured from its tallest item
+    let mut groups: Vec<Vec<FlatItem>> = Vec::new();
+    for item in flat_items {
+        match groups.last_mut() {
+            Some(group) if group[0].height() - item.height() <= tolerance => group.push(item),
+            _ => groups.push(vec![item]),
+        }
+    }
+
+    let first_new_box = boxes.len();
+    for group in groups {
+        let height = group[0].height();
+        let mut pending = group;
+
+        while !pending.is_empty() {
+            // Only boxes built here take more layers
+            let open_box = boxes.len().checked_sub(1).filter(|&index| index >= first_new_box);
+            let (layer, rest) = build_layer(pending, (max_length, max_width), height);
+            pending = rest;
+            if layer.placements.is_empty() {
+                break;
+            }
+
+            // Coverage is judged against the footprint the layer would sit on
+            let base_area = open_box
+                .map(|index| boxes[index].length * boxes[index].width)
+                .filter(|&area| area > 0.0)
+                .unwrap_or(layer.length * layer.width);
+            if layer.area < MIN_LAYER_FILL * base_area {
+                leftovers.extend(layer.placements.into_iter().map(|(item, _, _)| item));
+                continue;
+            }
+
+            let stacked = open_box.and_then(|index| Some((index, stack_layer(&boxes[index], &layer, constraints, options)?)));
+            if let Some((index, stacked)) = stacked {
+                boxes[index] = stacked;
+                continue;
+            }
+
+            let new_box = PackedBox::cut(&layer.placements[0].0.destination, layer.placements[0].0.service.as_deref(), options);
+            match stack_layer(&new_box, &layer, constraints, options) {
+                Some(stacked) => {
+                    boxes.push(stacked);
+                    stats::record(|stats| stats.boxes_opened += 1);
+                }
+                None => leftovers.extend(layer.placements.into_iter().map(|(item, _, _)| item)),
+            }
+        }
+
+        leftovers.extend(pending.into_iter().map(FlatItem::into_item));
+    }
+
+    leftovers
+}
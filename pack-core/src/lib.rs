@@ -0,0 +1,5288 @@
+//! Packing engine of pack-assistant, free of any Tauri dependency so a headless tool can use it.
+//!
+//! Describe the shipment as [`Item`]s and the run as [`PackingOptions`] (everything defaults;
+//! the same JSON the desktop app sends deserializes into both), then call
+//! [`try_pack_with_options`], which checks the request and returns a [`PackingSolution`] or a
+//! [`PackError`]. [`pack_with_options`] runs the same checks, but leaves a panic or a solution
+//! failing the audit to the caller. Items and solutions are in the units of
+//! `PackingOptions::units`.
+//!
+//! A solution can be audited with [`validate_solution`], edited with [`place_item`] and turned
+//! into documents with the [`export`] modules (manifest, pick list, customs, packing slip,
+//! instructions, layer SVGs). [`jobs`] and [`queue`] run packings in the background on
+//! native targets; destination limits come from the built-in table, the app's `destinations.json` ([`config`],
+//! handed in as `PackingOptions::configured_destinations`) and `PackingOptions::custom_destinations`.
+//!
+//! With the `wasm` feature the crate builds for wasm32-unknown-unknown and exports a JSON
+//! `pack_items(items_json, options_json)` to JavaScript (the `wasm` module). The same request
+//! gives the same solution JSON on every build; `fixtures/` holds the one CI checks that with.
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+pub use error::PackError;
+pub use export::instructions::Language;
+pub use geometry::{Dimensions, Position};
+
+pub mod affinity;
+pub mod auto;
+mod classes;
+pub mod closing;
+pub mod config;
+pub mod container;
+mod debug_log;
+pub mod diagnostics;
+pub mod error;
+pub mod explain;
+pub mod export;
+pub mod frontier;
+pub mod generator;
+mod geometry;
+mod grid;
+pub mod hazmat;
+// Background jobs and the queue run on threads, which wasm32 doesn't have
+#[cfg(not(target_arch = "wasm32"))]
+pub mod jobs;
+pub mod labels;
+mod layers;
+mod loose;
+pub mod materials;
+pub mod pallet;
+pub mod placement;
+mod platform;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod queue;
+// Without the queue nothing listens for the packer's progress
+#[cfg(target_arch = "wasm32")]
+mod queue {
+    pub(crate) fn report_progress(_done: usize, _total: usize) {}
+}
+pub mod scenarios;
+pub mod score;
+mod search;
+pub mod settings;
+mod spaces;
+mod spatial;
+pub mod stability;
+pub mod stats;
+pub mod units;
+pub mod verify;
+pub mod visualization;
+mod walls;
+mod weights;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+// Constants for outer box parameters
+const CARDBOARD_THICKNESS_CM: f64 = 0.6;
+const CARDBOARD_WEIGHT_KG_PER_SQM: f64 = 0.54;
+
+// Cardboard used for the outer box
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaterialSpec {
+    pub thickness_cm: f64,
+    pub weight_kg_per_sqm: f64,
+    // Price of this cardboard, overriding PackingOptions.cardboard_price_per_sqm
+    pub price_per_sqm: Option<f64>,
+}
+
+impl Default for MaterialSpec {
+    fn default() -> Self {
+        MaterialSpec {
+            thickness_cm: CARDBOARD_THICKNESS_CM,
+            weight_kg_per_sqm: CARDBOARD_WEIGHT_KG_PER_SQM,
+            price_per_sqm: None,
+        }
+    }
+}
+
+// Kraft paper and air pillows put in before sealing, counted in a box's weight
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Dunnage {
+    // The same allowance for every box
+    PerBox { grams: f64 },
+    // Filling the box volume the items leave empty
+    Density { grams_per_litre: f64 },
+}
+
+impl Dunnage {
+    // Weight in kg of the dunnage in a box of this volume holding this much item volume, in cm³
+    fn weight_kg(&self, box_volume: f64, items_volume: f64) -> f64 {
+        match *self {
+            Dunnage::PerBox { grams } => grams.max(0.0) / 1000.0,
+            Dunnage::Density { grams_per_litre } => (box_volume - items_volume).max(0.0) / 1000.0 * grams_per_litre.max(0.0) / 1000.0,
+        }
+    }
+}
+
+// Dunnage weight of a box, none without a dunnage model
+fn dunnage_weight(dunnage: Option<&Dunnage>, (length, width, height): (f64, f64, f64), items_volume: f64) -> f64 {
+    dunnage.map_or(0.0, |dunnage| dunnage.weight_kg(length * width * height, items_volume))
+}
+
+// Destination constraints
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DestinationConstraints {
+    pub max_box_dimension: f64,     // Maximum dimension for any side of the box
+    pub max_box_weight: f64,        // Maximum weight of a filled box
+    pub alternative_dimensions: Option<Dimensions>, // For special cases like Japan
+    #[serde(default)]
+    pub max_box_value: Option<f64>, // Maximum total declared value of a box, e.g. a customs relief threshold
+    #[serde(default)]
+    pub max_length_plus_girth: Option<f64>, // Longest side plus twice the sum of the other two
+    #[serde(default)]
+    pub max_dimension_sum: Option<f64>, // Maximum length + width + height
+    #[serde(default)]
+    pub max_box_volume: Option<f64>, // Maximum length × width × height in cm³ (0.12 m³ = 120000)
+    #[serde(default)]
+    pub max_weight_spread_kg: Option<f64>, // Heaviest minus lightest box of the destination, a handling policy
+    #[serde(default)]
+    pub headspace_cm: Option<f64>, // Empty space kept above the items, overriding PackingOptions.headspace_cm
+}
+
+impl Default for DestinationConstraints {
+    // The common 63 cm / 22 kg parcel limits
+    fn default() -> Self {
+        DestinationConstraints {
+            max_box_dimension: 63.0,
+            max_box_weight: 22.0,
+            alternative_dimensions: None,
+            max_box_value: None,
+            max_length_plus_girth: None,
+            max_dimension_sum: None,
+            max_box_volume: None,
+            max_weight_spread_kg: None,
+            headspace_cm: None,
+        }
+    }
+}
+
+// Carrier-specific parcel limits, applied on top of the destination's own limits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CarrierProfile {
+    pub name: String,
+    pub max_box_dimension: f64,
+    pub max_box_weight: f64,
+    #[serde(default)]
+    pub max_length_plus_girth: Option<f64>,
+    #[serde(default)]
+    pub alternative_dimensions: Option<Dimensions>,
+}
+
+// A carton size from the fixed box catalog, inner dimensions in cm
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoxSpec {
+    pub name: String,
+    pub length: f64,
+    pub width: f64,
+    pub height: f64,
+    // Rated load of the carton, on top of the destination's weight limit
+    #[serde(default)]
+    pub max_weight: Option<f64>,
+    // Cartons of this size in stock; unlimited when absent
+    #[serde(default)]
+    pub available: Option<u32>,
+    // Price of the carton plus the postage tier it ships in
+    #[serde(default)]
+    pub cost: f64,
+}
+
+impl BoxSpec {
+    fn volume(&self) -> f64 {
+        self.length * self.width * self.height
+    }
+}
+
+// Axis permutation for each of the six orientations: rotated dims are
+// (original[p[0]], original[p[1]], original[p[2]])
+const ROTATIONS: [[usize; 3]; 6] = [
+    [0, 1, 2], // Original
+    [0, 2, 1], // Rotate around x-axis
+    [1, 0, 2], // Rotate around y-axis
+    [1, 2, 0], // Rotate around x and y
+    [2, 0, 1], // Rotate around x and z
+    [2, 1, 0], // Rotate around z-axis
+];
+
+// Item dimensions
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Item {
+    pub id: String,
+    pub destination: String,
+    pub length: f64,
+    pub width: f64,
+    pub height: f64,
+    pub weight: f64,
+    // For tracking placement in solution
+    pub position: Option<Position>,
+    // Index of its box in PackingSolution.boxes and its own index in that box's items, set once
+    // the boxes are in their final order (before schema version 1, box_index was the slot)
+    pub box_index: Option<usize>,
+    #[serde(default)]
+    pub slot_index: Option<usize>,
+    // Applied orientation, indexing ROTATIONS relative to the original dimensions; set on every placed item
+    #[serde(default)]
+    pub rotation: u8,
+    // Dimensions as entered, set on every placed item and once an item has been rotated
+    #[serde(default)]
+    pub original_dimensions: Option<Dimensions>,
+    // Declared value, counted as zero when absent
+    #[serde(default)]
+    pub value: Option<f64>,
+    // Goods description for customs paperwork
+    #[serde(default)]
+    pub description: Option<String>,
+    // Caller data (order numbers, SKUs, bin locations) carried through untouched
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
+    // Bottled liquid: stays upright and stands on the box floor
+    #[serde(default)]
+    pub is_liquid: bool,
+    // This side up: only turned about the vertical axis, but free to go anywhere in the box
+    #[serde(default)]
+    pub keep_upright: bool,
+    // Most height of goods that may sit on top of the item (pressed cakes deform under a tall stack)
+    #[serde(default)]
+    pub max_height_above_cm: Option<f64>,
+    // Most weight of goods that may rest on the item, in kg (crushable cartons)
+    #[serde(default)]
+    pub max_load_kg: Option<f64>,
+    // Height a soft item (poly-bagged apparel) can be squashed to under light load
+    #[serde(default)]
+    pub compressible_to_height: Option<f64>,
+    // Set on an item placed squashed: its full height, `height` being the effective height it was placed at
+    #[serde(default)]
+    pub nominal_height: Option<f64>,
+    // Entered dimensions are a guess (plush toys, coiled cables): packed with a safety margin
+    #[serde(default)]
+    pub irregular: bool,
+    // Customer order the item belongs to; items of one order are kept together where possible
+    #[serde(default)]
+    pub order_id: Option<String>,
+    // Long item (curtain rails) that must lie with its longest side horizontal
+    #[serde(default)]
+    pub flat_only: bool,
+    // Grouping for the 3D view's colors and the per-box breakdown ("books", "stationery")
+    #[serde(default)]
+    pub category: Option<String>,
+    // Breaks easily, or wanted first by the recipient: packed after the other items of its
+    // destination and pulled toward the opening and the front of its box, where it comes out first
+    #[serde(default)]
+    pub fragile: bool,
+    #[serde(default)]
+    pub unload_first: bool,
+    // Limited-quantity dangerous goods ("UN3481", "2.1"), held to PackingOptions.hazmat_rules, and
+    // the amount the rules count (Wh, ml)
+    #[serde(default)]
+    pub hazmat_class: Option<String>,
+    #[serde(default)]
+    pub hazmat_quantity: Option<f64>,
+    // Id of the item this one is an accessory of; a box holding that item is preferred for it
+    #[serde(default)]
+    pub pair_with: Option<String>,
+    // Postal product the item ships under instead of the standard parcel ("printed papers"), held
+    // to that product's limits; items of different services never share a box
+    #[serde(default)]
+    pub service: Option<String>,
+    // Only the weight is known to be right (legacy catalogue data): packed by weight alone into
+    // boxes of its own, see weights.rs, and left without a position
+    #[serde(default)]
+    pub dimensions_unreliable: bool,
+    // Already in shipping-ready packaging of its own: not cartonized but sent as a parcel of its
+    // own, see loose.rs
+    #[serde(default)]
+    pub ship_loose: bool,
+}
+
+impl Item {
+    pub fn volume(&self) -> f64 {
+        self.length * self.width * self.height
+    }
+
+    // Fragile or unload first, to be reached first when the box is opened
+    pub fn wants_access(&self) -> bool {
+        self.fragile || self.unload_first
+    }
+
+    pub fn declared_value(&self) -> f64 {
+        self.value.unwrap_or(0.0)
+    }
+
+    // Dimensions before any rotation was applied
+    pub fn unrotated_dimensions(&self) -> Dimensions {
+        self.original_dimensions
+            .unwrap_or(Dimensions::new(self.length, self.width, self.height))
+    }
+
+    // Rotations (ROTATIONS indices, relative to the current orientation) the item may be turned by;
+    // liquids and keep-upright items keep their original vertical axis, and flat-only items their
+    // longest side horizontal
+    pub fn allowed_rotations(&self) -> Vec<usize> {
+        (0..ROTATIONS.len())
+            .filter(|&rotation| self.keeps_upright(rotation) && self.keeps_flat(rotation))
+            .collect()
+    }
+
+    // Whether the rotation leaves a liquid or keep-upright item in orientation 0 or 2
+    fn keeps_upright(&self, rotation: usize) -> bool {
+        !(self.is_liquid || self.keep_upright) || matches!(self.with_rotation(rotation).rotation, 0 | 2)
+    }
+
+    // Whether the rotation leaves a flat-only item's longest side in the horizontal plane
+    fn keeps_flat(&self, rotation: usize) -> bool {
+        if !self.flat_only {
+            return true;
+        }
+        let Dimensions { length, width, height } = self.unrotated_dimensions();
+        let longest = length.max(width).max(self.nominal_height.unwrap_or(height));
+        let rotated = self.with_rotation(rotation);
+        rotated.length.max(rotated.width) >= longest
+    }
+
+    // Create a rotated copy of the item (swapping dimensions); a squashed item springs back to full height
+    pub fn with_rotation(&self, rotation: usize) -> Self {
+        // Six possible orientations: (l,w,h), (l,h,w), (w,l,h), (w,h,l), (h,l,w), (h,w,l)
+        let permutation = ROTATIONS.get(rotation).copied().unwrap_or(ROTATIONS[0]);
+        let dims = [self.length, self.width, self.nominal_height.unwrap_or(self.height)];
+
+        // Compose with the rotation already applied so the index stays relative to the original
+        let applied = ROTATIONS[self.rotation as usize % ROTATIONS.len()];
+        let composed = permutation.map(|axis| applied[axis]);
+        let total_rotation = ROTATIONS.iter().position(|p| *p == composed).unwrap_or(0);
+
+        Item {
+            length: dims[permutation[0]],
+            width: dims[permutation[1]],
+            height: dims[permutation[2]],
+            position: None,
+            box_index: None,
+            slot_index: None,
+            rotation: total_rotation as u8,
+            original_dimensions: Some(self.unrotated_dimensions()),
+            nominal_height: None,
+            ..self.clone()
+        }
+    }
+
+    // Room the item takes at the least, squashed if it may be and in any orientation
+    fn least_volume(&self) -> f64 {
+        let Dimensions { length, width, height } = self.unrotated_dimensions();
+        length * width * self.compressible_to_height.map_or(height, |compressed| compressed.min(height))
+    }
+
+    // The item squashed to its compressible height, if it is compressible and stands on its own height axis
+    pub fn squashed(&self) -> Option<Item> {
+        let compressed = self.compressible_to_height?;
+        if self.nominal_height.is_some() || !matches!(self.rotation, 0 | 2) || compressed <= 0.0 || compressed >= self.height {
+            return None;
+        }
+        Some(Item {
+            height: compressed,
+            nominal_height: Some(self.height),
+            original_dimensions: Some(self.unrotated_dimensions()),
+            ..self.clone()
+        })
+    }
+}
+
+// Outer surface area in m² of a cardboard carton with the given inner dimensions
+fn carton_surface_sqm(material: &MaterialSpec, length: f64, width: f64, height: f64) -> f64 {
+    let thickness = material.thickness_cm;
+    let length_m = (length + 2.0 * thickness) / 100.0;
+    let width_m = (width + 2.0 * thickness) / 100.0;
+    let height_m = (height + 2.0 * thickness) / 100.0;
+
+    // Calculate box surface area (2 * (lw + lh + wh))
+    2.0 * (length_m * width_m + length_m * height_m + width_m * height_m)
+}
+
+// Weight of a cardboard carton with the given inner dimensions
+fn carton_weight(material: &MaterialSpec, length: f64, width: f64, height: f64) -> f64 {
+    carton_surface_sqm(material, length, width, height) * material.weight_kg_per_sqm
+}
+
+// Packed box with items
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackedBox {
+    pub items: Vec<Item>,
+    pub length: f64,
+    pub width: f64,
+    pub height: f64,
+    // Gross weight, which every limit is checked against. Deprecated for gross_weight, which
+    // serializes the same value, and kept for one more release.
+    pub weight: f64,
+    // The weight split as serialized, in the request's units: the goods, the cardboard and dunnage,
+    // and both together, with net_weight + tare_weight = gross_weight
+    #[serde(default)]
+    pub net_weight: f64,
+    #[serde(default)]
+    pub tare_weight: f64,
+    #[serde(default)]
+    pub gross_weight: f64,
+    pub destination: String,
+    // Total declared value of the items in the box
+    #[serde(default)]
+    pub value: f64,
+    // The lower of the insurance and customs caps on that value, if it has any
+    #[serde(default)]
+    pub value_cap: Option<ValueCap>,
+    // Carrier whose limits the box was packed against
+    #[serde(default)]
+    pub carrier: Option<String>,
+    // Postal product of its items, see Item.service
+    #[serde(default)]
+    pub service: Option<String>,
+    // Cardboard the box is made of, used for its tare weight
+    #[serde(default)]
+    pub material: MaterialSpec,
+    // Dunnage model the box was packed with, and the dunnage weight it assumes, included in weight
+    #[serde(default)]
+    pub dunnage: Option<Dunnage>,
+    #[serde(default)]
+    pub dunnage_kg: f64,
+    // Regular arrangement of the box's first items, when it was packed as a grid
+    #[serde(default)]
+    pub grid: Option<grid::GridLayout>,
+    // Catalog carton the box is; its dimensions stay fixed at the spec's
+    #[serde(default)]
+    pub spec: Option<String>,
+    // Carton and postage cost of the spec
+    #[serde(default)]
+    pub cost: Option<f64>,
+    // Item counts per category, most numerous first; items without a category are left out
+    #[serde(default)]
+    pub categories: Vec<CategoryCount>,
+    // Units per SKU under PackingOptions.sku_key, in the order the SKUs first appear in the box;
+    // an item without a key is a SKU of its own id
+    #[serde(default)]
+    pub contents_summary: Vec<SkuCount>,
+    // Cardboard cost of the carton, when a price per m² was given
+    #[serde(default)]
+    pub packaging_cost: Option<f64>,
+    #[serde(default)]
+    pub stability: Option<stability::Stability>,
+    // Empty space between the top of the items and the lid, included in the height
+    #[serde(default)]
+    pub headspace_cm: f64,
+    // Face the carton opens on, and the space kept between the items and that face so the flaps
+    // close without pressing on them, included in the box's size along the axis
+    #[serde(default)]
+    pub opening_axis: Axis,
+    #[serde(default)]
+    pub flap_clearance_cm: f64,
+    // Room still held back for PackingOptions.reserved_items, included in the headspace and the
+    // weight; None once they are in
+    #[serde(default)]
+    pub reservation: Option<Reservation>,
+    // Packed by weight alone, holding only items with dimensions_unreliable: they have no
+    // positions and the box has no size
+    #[serde(default)]
+    pub weight_only: bool,
+    // An item with ship_loose in its own packaging, the box its size with no cardboard around it
+    #[serde(default)]
+    pub ship_loose: bool,
+    // Below PackingOptions.min_fill_rate with no way to move its items elsewhere
+    #[serde(default)]
+    pub low_fill: bool,
+    // Opened under a ConstraintException for an item the standard limits can't take, and the
+    // limits it is held to instead
+    #[serde(default)]
+    pub exceptional: bool,
+    #[serde(default)]
+    pub override_limits: Option<DestinationConstraints>,
+    // Printed on the box, from PackingOptions.label_template ("USA-002")
+    #[serde(default)]
+    pub label: String,
+    // Hazmat the box holds by class, for its labels; empty when it holds none
+    #[serde(default)]
+    pub hazmat: Vec<hazmat::HazmatContent>,
+    // What stopped the box taking more items: the check that turned down the last item tried in it,
+    // or the destination running out of items
+    #[serde(default)]
+    pub closed_reason: Option<closing::ClosedReason>,
+    // Where the items are, for collision tests
+    #[serde(skip)]
+    pub(crate) index: spatial::LazyIndex,
+    // The room left between and around them, for Strategy::FreeSpaces
+    #[serde(skip)]
+    pub(crate) spaces: spaces::FreeSpaces,
+    // Too full for any item still to be packed, so the box search passes it over; only for the
+    // pass that closed it
+    #[serde(skip)]
+    pub(crate) closed: bool,
+}
+
+// Which cap holds a box's declared value down
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueCapSource {
+    // PackingOptions.insurance_cap_per_box
+    Insurance,
+    // The destination's max_box_value
+    Customs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ValueCap {
+    pub limit: f64,
+    pub source: ValueCapSource,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CategoryCount {
+    pub category: String,
+    pub count: usize,
+}
+
+// How items are grouped into SKUs for PackedBox.contents_summary
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SkuKey {
+    // The item id itself
+    #[default]
+    Id,
+    // The id up to its last separator, so "SKU-123-07" is "SKU-123" with "-"
+    IdPrefix { separator: String },
+    // A metadata field ("sku"), strings as they are and other values as JSON
+    Metadata { field: String },
+}
+
+impl SkuKey {
+    // The item's SKU, None when it has nothing to group by
+    pub fn sku(&self, item: &Item) -> Option<String> {
+        match self {
+            SkuKey::Id => Some(item.id.clone()),
+            SkuKey::IdPrefix { separator } if !separator.is_empty() => {
+                item.id.rsplit_once(separator.as_str()).map(|(prefix, _)| prefix.to_string()).filter(|prefix| !prefix.is_empty())
+            }
+            SkuKey::IdPrefix { .. } => None,
+            SkuKey::Metadata { field } => match item.metadata.as_ref()?.get(field)? {
+                serde_json::Value::String(text) => Some(text.clone()).filter(|text| !text.trim().is_empty()),
+                serde_json::Value::Null => None,
+                value => Some(value.to_string()),
+            },
+        }
+    }
+}
+
+// Units of one SKU in a box, the lines of the manifest and the customs declaration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkuCount {
+    pub sku: String,
+    // The first one given among its items
+    pub description: Option<String>,
+    pub count: u32,
+    pub total_weight: f64,
+    // Declared value of its items together, None when any of them has none
+    #[serde(default)]
+    pub total_value: Option<f64>,
+    // Its items, in the box's order
+    #[serde(default)]
+    pub item_ids: Vec<String>,
+}
+
+// Room PackingOptions.reserved_items take in a box: lying flat one on another on top of the other
+// items, a layer as high as their thinnest sides together over a floor each of them fits on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Reservation {
+    // The floor, longer side first
+    pub length: f64,
+    pub width: f64,
+    pub height: f64,
+    pub weight: f64,
+    pub value: f64,
+}
+
+impl PackedBox {
+    pub fn new(destination: &str) -> Self {
+        PackedBox {
+            items: Vec::new(),
+            length: 0.0,
+            width: 0.0,
+            height: 0.0,
+            weight: 0.0,
+            net_weight: 0.0,
+            tare_weight: 0.0,
+            gross_weight: 0.0,
+            destination: destination.to_string(),
+            value: 0.0,
+            value_cap: None,
+            carrier: None,
+            service: None,
+            material: MaterialSpec::default(),
+            dunnage: None,
+            dunnage_kg: 0.0,
+            grid: None,
+            spec: None,
+            cost: None,
+            categories: Vec::new(),
+            contents_summary: Vec::new(),
+            packaging_cost: None,
+            stability: None,
+            headspace_cm: 0.0,
+            opening_axis: Axis::Z,
+            flap_clearance_cm: 0.0,
+            reservation: None,
+            weight_only: false,
+            ship_loose: false,
+            low_fill: false,
+            exceptional: false,
+            override_limits: None,
+            label: String::new(),
+            hazmat: Vec::new(),
+            closed_reason: None,
+            index: spatial::LazyIndex::default(),
+            spaces: spaces::FreeSpaces::default(),
+            closed: false,
+        }
+    }
+
+    // An empty box cut to fit, of the requested cardboard and with the headspace of the destination
+    // and service and the flap clearance at its opening
+    pub(crate) fn cut(destination: &str, service: Option<&str>, options: &PackingOptions) -> Self {
+        let mut box_data = PackedBox::new(destination);
+        box_data.service = service.map(str::to_string);
+        box_data.material = options.material.clone();
+        box_data.dunnage = options.dunnage;
+        box_data.headspace_cm = headspace(destination, service, options);
+        box_data.opening_axis = options.opening_axis;
+        box_data.flap_clearance_cm = flap_clearance(options);
+        box_data.reservation = reservation(options);
+        box_data
+    }
+
+    // Space kept beyond the items along x, y and z: the headspace above them, with any reserved
+    // layer, and the flap clearance at the opening
+    pub fn clearance(&self) -> [f64; 3] {
+        let mut clearance = [0.0, 0.0, self.headspace_cm + self.reservation.map_or(0.0, |reserved| reserved.height)];
+        clearance[self.opening_axis.index()] += self.flap_clearance_cm;
+        clearance
+    }
+
+    // Smallest length and width the box may have while its reserved layer needs the floor
+    fn min_floor(&self) -> (f64, f64) {
+        reserved_floor(self.reservation.as_ref(), self.clearance())
+    }
+
+    // A catalog carton: full size from the start, so its tare weight is right from the first item
+    fn from_spec(destination: &str, service: Option<&str>, spec: &BoxSpec, options: &PackingOptions) -> Self {
+        let mut box_data = PackedBox::cut(destination, service, options);
+        box_data.spec = Some(spec.name.clone());
+        box_data.cost = Some(spec.cost);
+        box_data.length = spec.length;
+        box_data.width = spec.width;
+        box_data.height = spec.height;
+        box_data.update_box_weight();
+        box_data
+    }
+
+    // An empty box of the same kind, for re-packing its items
+    fn empty_like(&self) -> Self {
+        let mut box_data = PackedBox::new(&self.destination);
+        box_data.carrier = self.carrier.clone();
+        box_data.service = self.service.clone();
+        box_data.material = self.material.clone();
+        box_data.dunnage = self.dunnage;
+        box_data.headspace_cm = self.headspace_cm;
+        box_data.opening_axis = self.opening_axis;
+        box_data.flap_clearance_cm = self.flap_clearance_cm;
+        box_data.reservation = self.reservation;
+        box_data.weight_only = self.weight_only;
+        box_data.ship_loose = self.ship_loose;
+        box_data.exceptional = self.exceptional;
+        box_data.override_limits = self.override_limits.clone();
+        if self.spec.is_some() {
+            box_data.spec = self.spec.clone();
+            box_data.cost = self.cost;
+            box_data.length = self.length;
+            box_data.width = self.width;
+            box_data.height = self.height;
+            box_data.update_box_weight();
+        }
+        box_data
+    }
+
+    pub fn volume(&self) -> f64 {
+        self.length * self.width * self.height
+    }
+
+    // Order of the box's items; with separate_orders every box holds at most one
+    pub fn order_id(&self) -> Option<&str> {
+        self.items.first().and_then(|item| item.order_id.as_deref())
+    }
+
+    // Indices of the items in an order they can go in by hand, and come out again in reverse
+    // straight up: every item after the ones under it, whether it rests on them or overhangs them.
+    // Floor items come first, then the items over them, level by level, each level by height,
+    // then back to front, then left to right. A grid stays at the front of the list as a block.
+    pub fn loading_order(&self) -> Vec<usize> {
+        let corner = |item: &Item| item.position.unwrap_or_default();
+        let by_corner = |&a: &usize, &b: &usize| {
+            let (Position { x: ax, y: ay, z: az }, Position { x: bx, y: by, z: bz }) = (corner(&self.items[a]), corner(&self.items[b]));
+            az.total_cmp(&bz).then(ay.total_cmp(&by)).then(ax.total_cmp(&bx))
+        };
+
+        // Items under another are lower down, so going up they always have their level already
+        let mut rising: Vec<usize> = (0..self.items.len()).collect();
+        rising.sort_by(by_corner);
+        let mut levels = vec![0_usize; self.items.len()];
+        for (rank, &index) in rising.iter().enumerate() {
+            let item = &self.items[index];
+            let Position { x, y, z } = corner(item);
+            levels[index] = rising[..rank]
+                .iter()
+                .filter(|&&below| {
+                    let other = &self.items[below];
+                    let Position { x: ox, y: oy, z: oz } = corner(other);
+                    oz + other.height <= z + CONTACT_EPSILON && footprints_overlap(item, (x, y), other, (ox, oy))
+                })
+                .map(|&below| levels[below] + 1)
+                .max()
+                .unwrap_or(0);
+        }
+
+        let grid_items = self.grid.as_ref().map_or(0, |grid| grid.item_count.min(self.items.len()));
+        let mut order: Vec<usize> = (0..self.items.len()).collect();
+        let (grid, rest) = order.split_at_mut(grid_items);
+        for section in [grid, rest] {
+            section.sort_by(|a, b| levels[*a].cmp(&levels[*b]).then_with(|| by_corner(a, b)));
+        }
+        order
+    }
+
+    // The items in loading order, as the exports list them
+    pub fn loaded_items(&self) -> Vec<&Item> {
+        self.loading_order().into_iter().map(|index| &self.items[index]).collect()
+    }
+
+    // Put the items in loading order; the packer adds them in whatever order its passes left
+    pub(crate) fn sort_loading_order(&mut self) {
+        let order = self.loading_order();
+        if order.iter().enumerate().all(|(slot, &index)| slot == index) {
+            return;
+        }
+        let mut items: Vec<Option<Item>> = std::mem::take(&mut self.items).into_iter().map(Some).collect();
+        self.items = order.into_iter().filter_map(|index| items[index].take()).collect();
+        self.index.reset();
+    }
+
+    // Item counts per category, most numerous first and by name among equals
+    pub fn category_counts(&self) -> Vec<CategoryCount> {
+        let mut counts: Vec<CategoryCount> = Vec::new();
+        for category in self.items.iter().filter_map(|item| item.category.as_deref()) {
+            match counts.iter_mut().find(|count| count.category == category) {
+                Some(count) => count.count += 1,
+                None => counts.push(CategoryCount {
+                    category: category.to_string(),
+                    count: 1,
+                }),
+            }
+        }
+        counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.category.cmp(&b.category)));
+        counts
+    }
+
+    // Units per SKU of the items, grouped by the key
+    pub fn contents_by(&self, key: &SkuKey) -> Vec<SkuCount> {
+        let mut counts: Vec<SkuCount> = Vec::new();
+        for item in &self.items {
+            let sku = key.sku(item).unwrap_or_else(|| item.id.clone());
+            match counts.iter_mut().find(|count| count.sku == sku) {
+                Some(count) => {
+                    count.count += 1;
+                    count.total_weight += item.weight;
+                    count.total_value = count.total_value.zip(item.value).map(|(total, value)| total + value);
+                    if count.description.is_none() {
+                        count.description = item.description.clone();
+                    }
+                    count.item_ids.push(item.id.clone());
+                }
+                None => counts.push(SkuCount {
+                    sku,
+                    description: item.description.clone(),
+                    count: 1,
+                    total_weight: item.weight,
+                    total_value: item.value,
+                    item_ids: vec![item.id.clone()],
+                }),
+            }
+        }
+        counts
+    }
+
+    // The summary packing left, or one by item id for a box saved before there was one
+    pub fn contents(&self) -> Vec<SkuCount> {
+        if self.contents_summary.is_empty() {
+            self.contents_by(&SkuKey::Id)
+        } else {
+            self.contents_summary.clone()
+        }
+    }
+
+    pub fn add_item(&mut self, mut item: Item, position: Position) -> bool {
+        // Calculate new dimensions after adding the item
+        let clearance = self.clearance();
+        let floor = self.min_floor();
+        let new_length = (position.x + item.length + clearance[0]).max(self.length).max(floor.0);
+        let new_width = (position.y + item.width + clearance[1]).max(self.width).max(floor.1);
+        let new_height = (position.z + item.height + clearance[2]).max(self.height);
+
+        // Update item with its position
+        item.position = Some(position);
+
+        // Store item weight before pushing to items vector
+        let item_weight = item.weight;
+        self.value += item.declared_value();
+        self.index.push(&item);
+        self.spaces.push(&item);
+        self.items.push(item);
+
+        // Update box dimensions and weight
+        self.length = new_length;
+        self.width = new_width;
+        self.height = new_height;
+        self.weight += item_weight;
+
+        // Calculate box weight including cardboard
+        self.update_box_weight();
+
+        true
+    }
+
+    // Add an item to a weight-only box, where it has no position
+    pub(crate) fn add_unplaced(&mut self, mut item: Item) {
+        item.position = None;
+        self.value += item.declared_value();
+        self.items.push(item);
+        self.update_box_weight();
+    }
+
+    // Whether the item at the position would overlap an item already in the box
+    fn collides(&self, item: &Item, Position { x, y, z }: Position) -> bool {
+        let overlaps = |existing: &Item| {
+            existing.position.is_some_and(|Position { x: ex, y: ey, z: ez }| {
+                !(x + item.length <= ex ||
+                  ex + existing.length <= x ||
+                  y + item.width <= ey ||
+                  ey + existing.width <= y ||
+                  z + item.height <= ez ||
+                  ez + existing.height <= z)
+            })
+        };
+        self.index
+            .any_near(&self.items, Position::new(x, y, z), (item.length, item.width, item.height), overlaps)
+            .unwrap_or_else(|| self.items.iter().any(overlaps))
+    }
+
+    // Take an item out and shrink the box to the extent of the remaining items
+    pub fn remove_item(&mut self, index: usize) -> Item {
+        let mut item = self.items.remove(index);
+        self.index.reset();
+        self.spaces.reset();
+        self.closed = false;
+        item.position = None;
+        // Taking an item out of the grid leaves a hole, so it is no longer a regular layout
+        if self.grid.as_ref().is_some_and(|grid| index < grid.item_count) {
+            self.grid = None;
+        }
+        item.box_index = None;
+        item.slot_index = None;
+        let material = self.material.clone();
+        self.recompute_from_items(&material);
+
+        item
+    }
+
+    // Size, value and weight of the box worked out afresh from its items as placed, in the
+    // material given: the extent of the items with the clearance and any reserved floor, or
+    // nothing when empty. Catalog cartons keep their size and weight-only boxes have none. The
+    // items still pointing at a box get their slot in this one, the others none.
+    pub fn recompute_from_items(&mut self, material: &MaterialSpec) {
+        self.material = material.clone();
+        if self.spec.is_none() && !self.weight_only {
+            let (mut length, mut width, mut height) = (0.0_f64, 0.0_f64, 0.0_f64);
+            for item in &self.items {
+                if let Some(Position { x, y, z }) = item.position {
+                    length = length.max(x + item.length);
+                    width = width.max(y + item.width);
+                    height = height.max(z + item.height);
+                }
+            }
+            let [clear_x, clear_y, clear_z] = if self.items.is_empty() { [0.0; 3] } else { self.clearance() };
+            let floor = if self.items.is_empty() { (0.0, 0.0) } else { self.min_floor() };
+            self.length = (length + clear_x).max(floor.0);
+            self.width = (width + clear_y).max(floor.1);
+            self.height = height + clear_z;
+        }
+        self.value = self.items.iter().map(Item::declared_value).sum();
+        for (slot, item) in self.items.iter_mut().enumerate() {
+            item.slot_index = item.box_index.map(|_| slot);
+        }
+        self.update_box_weight();
+    }
+
+    // Whether the box search skips the box as closed, counting the skip
+    fn passed_over(&self) -> bool {
+        if self.closed {
+            stats::record(|stats| stats.closed_skips += 1);
+        }
+        self.closed
+    }
+
+    // The limit no item at least as heavy as `lightest` and as big as `smallest` could still go in
+    // under, however the box were grown or re-packed: the goods alone would be over the weight, or
+    // the items would need more room than the largest box the limits allow. Tare, clearance and
+    // headspace only take more, so this never turns away an item that fits.
+    fn full_by(&self, limits: &DestinationConstraints, lightest: f64, smallest: f64) -> Option<closing::ClosedReason> {
+        let (length, width, height) = limits.axis_limits();
+        let largest = length * width * height;
+        let room = limits.max_box_volume.map_or(largest, |cap| cap.min(largest));
+        let occupied: f64 = self.items.iter().map(Item::least_volume).sum();
+        if self.items_weight() + lightest > limits.max_box_weight + CONTACT_EPSILON {
+            Some(closing::ClosedReason::WeightLimit)
+        } else if occupied + smallest > room + CONTACT_EPSILON {
+            Some(if room < largest { closing::ClosedReason::VolumeCap } else { closing::ClosedReason::DimensionLimit })
+        } else {
+            None
+        }
+    }
+
+    // Weight of the goods alone, excluding the cardboard
+    pub fn items_weight(&self) -> f64 {
+        self.items.iter().map(|item| item.weight).sum()
+    }
+
+    // Item volume over box volume, 0..1
+    pub fn fill_rate(&self) -> f64 {
+        efficiency(items_volume(self), self.volume())
+    }
+
+    // Empty space in the box in cm³, what void fill has to take up
+    pub fn void_volume(&self) -> f64 {
+        (self.volume() - self.items.iter().map(Item::volume).sum::<f64>()).max(0.0)
+    }
+
+    // Weight of the cardboard carton
+    pub fn cardboard_weight(&self) -> f64 {
+        (self.weight - self.items_weight() - self.dunnage_kg - self.reserved_weight()).max(0.0)
+    }
+
+    // Weight of the packaging, the cardboard and the dunnage
+    pub fn packaging_weight(&self) -> f64 {
+        (self.weight - self.items_weight() - self.reserved_weight()).max(0.0)
+    }
+
+    // Weight and declared value of the reserved items the box still holds room for
+    pub fn reserved_weight(&self) -> f64 {
+        self.reservation.map_or(0.0, |reserved| reserved.weight)
+    }
+
+    pub fn reserved_value(&self) -> f64 {
+        self.reservation.map_or(0.0, |reserved| reserved.value)
+    }
+
+    // Outer surface area of the carton in m², the cardboard its tare weight and cost come from;
+    // none for an item shipping loose
+    pub fn outer_surface_sqm(&self) -> f64 {
+        if self.ship_loose {
+            return 0.0;
+        }
+        carton_surface_sqm(&self.material, self.length, self.width, self.height)
+    }
+
+    pub fn packaging_cost(&self, price_per_sqm: f64) -> f64 {
+        self.outer_surface_sqm() * price_per_sqm
+    }
+
+    fn update_box_weight(&mut self) {
+        // A weight-only box has no size to weigh cardboard for, a loose item no cardboard
+        let box_weight = if self.weight_only || self.ship_loose { 0.0 } else { carton_weight(&self.material, self.length, self.width, self.height) };
+        let items_volume: f64 = self.items.iter().map(Item::volume).sum();
+        self.dunnage_kg = dunnage_weight(self.dunnage.as_ref(), (self.length, self.width, self.height), items_volume);
+
+        // Total weight = items weight + box weight + dunnage, and the reserved items still to come
+        let items_weight: f64 = self.items.iter().map(|item| item.weight).sum();
+        self.weight = items_weight + box_weight + self.dunnage_kg + self.reserved_weight();
+    }
+
+    // Calculate the smallest face area
+    pub fn smallest_face_area(&self) -> f64 {
+        let face1 = self.length * self.width;
+        let face2 = self.length * self.height;
+        let face3 = self.width * self.height;
+
+        face1.min(face2).min(face3)
+    }
+
+    // Calculate total surface area
+    pub fn surface_area(&self) -> f64 {
+        2.0 * (
+            self.length * self.width +
+            self.length * self.height +
+            self.width * self.height
+        )
+    }
+}
+
+// Why an item ended up in unpacked_items
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnpackedReason {
+    // Too big or heavy for the destination in every orientation
+    ExceedsLimits,
+    // No catalog carton can hold it
+    NoFittingBox,
+    // Cartons that could hold it exist but are all used up
+    OutOfBoxes,
+    // A liquid or keep-upright item that only fits on its side, or a liquid that only fits off the floor
+    MustStayUpright,
+    // A flat-only item that only fits standing on end
+    MustLieFlat,
+    // Its hazard class is banned for the destination, or its hazmat quantity alone is over the per-box limit
+    HazmatRestricted,
+    // Its declared value alone is over PackingOptions.insurance_cap_per_box
+    OverInsuranceCap,
+    // One of PackingOptions.reserved_items that its box had no room left for
+    NoReservedRoom,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnpackedDetail {
+    pub item_id: String,
+    pub reason: UnpackedReason,
+    // For items too big or heavy: each limit the least-over orientation breaks, and by how much
+    #[serde(default)]
+    pub gaps: Vec<diagnostics::ConstraintGap>,
+    // That orientation, a ROTATIONS index relative to the entered dimensions
+    #[serde(default)]
+    pub best_rotation: Option<u8>,
+    // Other known destinations that would take the item as it is
+    #[serde(default)]
+    pub accepted_by: Vec<String>,
+    // Destinations and carriers that would take it, the closest to its own lane first
+    #[serde(default)]
+    pub suggested_lanes: Vec<diagnostics::LaneSuggestion>,
+}
+
+// Sides of an item reordered by PackingOptions.sort_dimensions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DimensionSort {
+    pub item_id: String,
+    // ROTATIONS index taking the sides as entered to the sorted ones, like Item.rotation
+    pub rotation: u8,
+}
+
+// A finished box within the safety margin of one of its limits, in cm and kg
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LimitWarning {
+    pub box_index: usize,
+    // DestinationConstraints field, or "box_length" / "box_width" / "box_height" for alternative_dimensions
+    pub constraint: String,
+    pub value: f64,
+    pub limit: f64,
+    // Limit less value
+    pub headroom: f64,
+}
+
+// Cartons of one catalog spec used by a solution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoxSpecUsage {
+    pub name: String,
+    pub used: u32,
+    pub available: Option<u32>,
+    // Left in stock after this solution
+    pub remaining: Option<u32>,
+}
+
+// A map serialized in key order, so the same solution is the same JSON on every run and build
+pub(crate) fn sorted_map<S: serde::Serializer, V: Serialize>(map: &HashMap<String, V>, serializer: S) -> Result<S::Ok, S::Error> {
+    map.iter().collect::<std::collections::BTreeMap<_, _>>().serialize(serializer)
+}
+
+// Shape of the serialized solution; 0 for solutions saved before it was recorded
+pub const SOLUTION_SCHEMA_VERSION: u32 = 1;
+
+// Packing solution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackingSolution {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub boxes: Vec<PackedBox>,
+    pub total_volume: f64,
+    pub unpacked_items: Vec<Item>,
+    // Non-fatal notes about the solution for the operator
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    // How the time-budgeted search went, when a budget was given
+    #[serde(default)]
+    pub search: Option<search::SearchReport>,
+    // Reason for each entry of unpacked_items, in the same order
+    #[serde(default)]
+    pub unpacked_details: Vec<UnpackedDetail>,
+    // Per-spec carton consumption in fixed-box-catalog mode
+    #[serde(default)]
+    pub box_usage: Vec<BoxSpecUsage>,
+    // Carton and postage cost of all boxes in fixed-box-catalog mode
+    #[serde(default)]
+    pub total_cost: Option<f64>,
+    // Items placed in a box; 0 with a non-empty unpacked_items means every item was rejected
+    #[serde(default)]
+    pub packed_count: usize,
+    // Timing and work counters, when collect_stats was set
+    #[serde(default)]
+    pub stats: Option<stats::PackStats>,
+    // Cardboard cost of all boxes, when a price per m² was given
+    #[serde(default)]
+    pub total_packaging_cost: Option<f64>,
+    // Boxes each order ended up in, most split first
+    #[serde(default)]
+    pub order_spans: Vec<OrderSpan>,
+    // Box by box breakdown of every order, with separate_orders set
+    #[serde(default)]
+    pub order_splits: Vec<OrderSplit>,
+    // Box count and fill of every destination, by destination name
+    #[serde(default)]
+    pub destination_summaries: Vec<DestinationSummary>,
+    // Item volume over box volume of all boxes, 0..1, and the box volume the items leave empty
+    #[serde(default)]
+    pub packing_efficiency: f64,
+    #[serde(default)]
+    pub wasted_volume: f64,
+    // Indices into boxes of each destination's boxes; boxes are ordered by destination, then heaviest first
+    #[serde(default, serialize_with = "sorted_map")]
+    pub boxes_by_destination: HashMap<String, Vec<usize>>,
+    // Boxes within PackingOptions.safety_margin_pct of a limit, also pointed out in warnings
+    #[serde(default)]
+    pub limit_warnings: Vec<LimitWarning>,
+    // Every item with a pair_with, and whether it shares a box with that item
+    #[serde(default)]
+    pub pairings: Vec<affinity::Pairing>,
+    // How each item placed by the extreme-point packer was chosen, with explain set; grid, layer
+    // and wall placements have no trace
+    #[serde(default)]
+    pub placements_debug: Vec<explain::PlacementTrace>,
+    // Log lines of the run with debug_level set, for bug reports
+    #[serde(default)]
+    pub debug_log: Vec<String>,
+    // Items whose sides PackingOptions.sort_dimensions reordered; their dimensions, positions and
+    // rotations in the boxes are of the sorted sides
+    #[serde(default)]
+    pub dimension_sorts: Vec<DimensionSort>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DestinationSummary {
+    pub destination: String,
+    // Every box, and those of them packed by weight alone, which have no size and are left out of
+    // the volumes and fill rates below
+    pub box_count: usize,
+    #[serde(default)]
+    pub weight_only_count: usize,
+    // Item volume over box volume of all its boxes together, 0..1: the packing efficiency
+    pub fill_rate: f64,
+    // Boxes kept below PackingOptions.min_fill_rate
+    pub low_fill_count: usize,
+    // Volume of the items and of the boxes on their final dimensions, in cm³ or in³
+    #[serde(default)]
+    pub item_volume: f64,
+    #[serde(default)]
+    pub box_volume: f64,
+    // Box volume less item volume
+    #[serde(default)]
+    pub wasted_volume: f64,
+    // Index into the boxes of the destination's box with the lowest fill rate, the first of equals
+    #[serde(default)]
+    pub worst_box: Option<usize>,
+    #[serde(default)]
+    pub worst_fill_rate: f64,
+}
+
+fn items_volume(box_data: &PackedBox) -> f64 {
+    box_data.items.iter().map(Item::volume).sum()
+}
+
+// Item volume over box volume, 0 without any box volume
+fn efficiency(item_volume: f64, box_volume: f64) -> f64 {
+    if box_volume > 0.0 { item_volume / box_volume } else { 0.0 }
+}
+
+// Packing efficiency and wasted volume of all the boxes together, leaving out the weight-only
+// ones that have no size
+pub(crate) fn packing_efficiency(boxes: &[PackedBox]) -> (f64, f64) {
+    let placed = || boxes.iter().filter(|box_data| !box_data.weight_only);
+    let item_volume: f64 = placed().map(items_volume).sum();
+    let box_volume: f64 = placed().map(PackedBox::volume).sum();
+    (efficiency(item_volume, box_volume), box_volume - item_volume)
+}
+
+// Totals of the boxes per destination, ordered by destination
+pub fn destination_summaries(boxes: &[PackedBox]) -> Vec<DestinationSummary> {
+    let mut summaries: Vec<DestinationSummary> = Vec::new();
+    for (index, box_data) in boxes.iter().enumerate() {
+        let position = match summaries.iter().position(|summary| summary.destination == box_data.destination) {
+            Some(position) => position,
+            None => {
+                summaries.push(DestinationSummary {
+                    destination: box_data.destination.clone(),
+                    box_count: 0,
+                    weight_only_count: 0,
+                    fill_rate: 0.0,
+                    low_fill_count: 0,
+                    item_volume: 0.0,
+                    box_volume: 0.0,
+                    wasted_volume: 0.0,
+                    worst_box: None,
+                    worst_fill_rate: 0.0,
+                });
+                summaries.len() - 1
+            }
+        };
+        let summary = &mut summaries[position];
+        summary.box_count += 1;
+        if box_data.weight_only {
+            summary.weight_only_count += 1;
+            continue;
+        }
+        summary.low_fill_count += usize::from(box_data.low_fill);
+        summary.item_volume += items_volume(box_data);
+        summary.box_volume += box_data.volume();
+        let fill_rate = box_data.fill_rate();
+        if summary.worst_box.is_none() || fill_rate < summary.worst_fill_rate {
+            summary.worst_box = Some(index);
+            summary.worst_fill_rate = fill_rate;
+        }
+    }
+
+    for summary in &mut summaries {
+        summary.fill_rate = efficiency(summary.item_volume, summary.box_volume);
+        summary.wasted_volume = summary.box_volume - summary.item_volume;
+    }
+    summaries.sort_by(|a, b| a.destination.cmp(&b.destination));
+    summaries
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderSpan {
+    pub order_id: String,
+    pub box_count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderBox {
+    // Zero-based index into the solution's boxes
+    pub box_index: usize,
+    // "ORDER123 box 1/2"
+    pub label: String,
+    pub item_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderSplit {
+    pub order_id: String,
+    pub boxes: Vec<OrderBox>,
+    // Items of the order that couldn't be packed; the order is incomplete when there are any
+    pub unpacked_item_ids: Vec<String>,
+}
+
+// Every order's boxes and unpacked items, ordered by order id; items without an order are left out
+pub fn order_splits(boxes: &[PackedBox], unpacked: &[Item]) -> Vec<OrderSplit> {
+    let mut splits: Vec<OrderSplit> = Vec::new();
+    fn split_for<'a>(splits: &'a mut Vec<OrderSplit>, order_id: &str) -> &'a mut OrderSplit {
+        let index = match splits.iter().position(|split| split.order_id == order_id) {
+            Some(index) => index,
+            None => {
+                splits.push(OrderSplit {
+                    order_id: order_id.to_string(),
+                    boxes: Vec::new(),
+                    unpacked_item_ids: Vec::new(),
+                });
+                splits.len() - 1
+            }
+        };
+        &mut splits[index]
+    }
+
+    for (box_index, box_data) in boxes.iter().enumerate() {
+        let mut orders: Vec<&str> = box_data.items.iter().filter_map(|item| item.order_id.as_deref()).collect();
+        orders.sort_unstable();
+        orders.dedup();
+        for order_id in orders {
+            let item_ids = box_data
+                .items
+                .iter()
+                .filter(|item| item.order_id.as_deref() == Some(order_id))
+                .map(|item| item.id.clone())
+                .collect();
+            split_for(&mut splits, order_id).boxes.push(OrderBox {
+                box_index,
+                label: String::new(),
+                item_ids,
+            });
+        }
+    }
+    for item in unpacked {
+        if let Some(order_id) = item.order_id.as_deref() {
+            split_for(&mut splits, order_id).unpacked_item_ids.push(item.id.clone());
+        }
+    }
+
+    for split in &mut splits {
+        let count = split.boxes.len();
+        for (number, order_box) in split.boxes.iter_mut().enumerate() {
+            order_box.label = format!("{} box {}/{}", split.order_id, number + 1, count);
+        }
+    }
+    splits.sort_by(|a, b| a.order_id.cmp(&b.order_id));
+    splits
+}
+
+// Boxes spanned by each order in the boxes, most split first and by order id among equals
+pub fn order_spans(boxes: &[PackedBox]) -> Vec<OrderSpan> {
+    let mut spans: Vec<OrderSpan> = Vec::new();
+    for box_data in boxes {
+        let mut orders: Vec<&str> = box_data.items.iter().filter_map(|item| item.order_id.as_deref()).collect();
+        orders.sort_unstable();
+        orders.dedup();
+        for order_id in orders {
+            match spans.iter_mut().find(|span| span.order_id == order_id) {
+                Some(span) => span.box_count += 1,
+                None => spans.push(OrderSpan {
+                    order_id: order_id.to_string(),
+                    box_count: 1,
+                }),
+            }
+        }
+    }
+    spans.sort_by(|a, b| b.box_count.cmp(&a.box_count).then_with(|| a.order_id.cmp(&b.order_id)));
+    spans
+}
+
+// An axis of a box: x along its length, y its width, z its height
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Axis {
+    X,
+    Y,
+    #[default]
+    Z,
+}
+
+impl Axis {
+    // Index into (length, width, height) and (x, y, z)
+    pub fn index(self) -> usize {
+        match self {
+            Axis::X => 0,
+            Axis::Y => 1,
+            Axis::Z => 2,
+        }
+    }
+}
+
+// What the packer optimizes for when several placements are possible
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Objective {
+    // Per-parcel pricing: accept bigger boxes and extra search to avoid opening another one
+    FewestBoxes,
+    // Keep each box as compact as possible (smallest surface area)
+    #[default]
+    SmallestVolume,
+    // Cheapest total carton and postage cost over the box catalog (plain SmallestVolume without one)
+    LowestCost,
+}
+
+impl Objective {
+    pub const ALL: [Objective; 3] = [Objective::FewestBoxes, Objective::SmallestVolume, Objective::LowestCost];
+}
+
+// How items are arranged inside boxes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Strategy {
+    // Extreme-point placement of items one at a time
+    #[default]
+    ExtremePoints,
+    // Horizontal layers of items of similar height, for flat goods
+    Layers,
+    // Vertical walls across the box, one behind the other, for long items
+    Walls,
+    // Extreme-point placement that also tries the corners of the empty spaces left between items,
+    // reaching pockets the extreme points miss
+    FreeSpaces,
+    // One of the above with the matching candidate quality and search effort chosen for each
+    // destination by its item count, see auto.rs
+    Auto,
+}
+
+impl Strategy {
+    // Every strategy that packs as it is; Auto only picks among them
+    pub const ALL: [Strategy; 4] = [Strategy::ExtremePoints, Strategy::Layers, Strategy::Walls, Strategy::FreeSpaces];
+}
+
+// How many candidate positions are tried for each item; every level adds to the one before it,
+// trading speed for tighter boxes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CandidateQuality {
+    // The points right of, in front of and on top of every item
+    Basic,
+    // Also those points projected back onto the walls and floor, and the corners of the box extent
+    Extended,
+    // Also the corners of the maximal empty spaces left between items
+    EmptyMaximalSpaces,
+}
+
+impl CandidateQuality {
+    pub const ALL: [CandidateQuality; 3] = [CandidateQuality::Basic, CandidateQuality::Extended, CandidateQuality::EmptyMaximalSpaces];
+}
+
+// Caller-supplied settings for a packing run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PackingOptions {
+    // Units of the request items and of the returned solution
+    pub units: units::Units,
+    // Decimal places for serialized weights (default: 3 for kg/lb, 0 for g)
+    pub weight_precision: Option<u32>,
+    // Decimal places for serialized lengths, positions and volumes (default: 2)
+    pub dimension_precision: Option<u32>,
+    // Round box dimensions up to this carton cutting increment after packing
+    pub dimension_rounding_cm: Option<f64>,
+    // Carrier used for destinations without an entry in destination_carriers
+    pub default_carrier: Option<String>,
+    // Carrier name per destination
+    pub destination_carriers: HashMap<String, String>,
+    // Custom carrier profiles, checked before the built-in ones
+    pub carriers: Vec<CarrierProfile>,
+    // Destination constraints that take precedence over the config file and built-in table
+    pub custom_destinations: HashMap<String, DestinationConstraints>,
+    // Entries of the destination config file, set by the app rather than sent with the request;
+    // they take precedence over the built-in table
+    #[serde(skip)]
+    pub configured_destinations: std::sync::Arc<config::DestinationTable>,
+    pub material: MaterialSpec,
+    // Void fill added to every box before sealing, weighed with it; none unless set
+    pub dunnage: Option<Dunnage>,
+    // Move items between a destination's boxes so their weights come out even
+    pub balance_weight: bool,
+    pub objective: Objective,
+    // Spend up to this long improving on the greedy result
+    pub time_budget_ms: Option<u64>,
+    // Seed for the randomized part of the search; the same seed, items and options give the same
+    // solution as long as the budget isn't what stops the search. Without one a fresh seed is
+    // drawn and reported in PackingSolution.search.
+    pub seed: Option<u64>,
+    // What the search minimizes; by default the objective's own measure
+    pub score_weights: Option<score::ScoreWeights>,
+    // Smallest run of identical items packed as a regular grid (default 8, 0 turns it off)
+    pub grid_min_items: Option<usize>,
+    // Fixed carton sizes; when given, every box is one of these instead of cut to fit
+    pub box_catalog: Vec<BoxSpec>,
+    pub strategy: Strategy,
+    // Candidate positions tried per item; by default Extended for FewestBoxes and Basic otherwise.
+    // Strategy::FreeSpaces always tries the empty-space corners.
+    pub candidate_quality: Option<CandidateQuality>,
+    // Item counts at which Strategy::Auto switches heuristics; the defaults of AutoThresholds when absent
+    pub auto_thresholds: Option<auto::AutoThresholds>,
+    // Items whose heights differ by at most this share a layer (default 0.5 cm)
+    pub layer_tolerance_cm: Option<f64>,
+    // How far an item above the floor may reach past the items under it; unlimited when absent
+    pub allow_overhang_cm: Option<f64>,
+    // Keep a cut-to-fit box at the length and width its first (largest) item gave it, later items
+    // only stacking within that footprint and the box growing in height alone, for taping lines
+    // that can't take a box grown sideways. Grids, layers and walls lay out footprints of their
+    // own and are left out.
+    pub lock_footprint_after_first_item: bool,
+    // Margin added to each dimension of irregular items, in percent (default 10)
+    pub irregular_inflation_pct: Option<f64>,
+    // Sort each item's sides into length ≥ width ≥ height before packing, for callers whose axes
+    // mean nothing, so keep_upright and flat_only can't be read against a swapped entry. Items kept
+    // upright, liquids and compressible items keep their height. Off by default, as some callers'
+    // axes are meaningful.
+    pub sort_dimensions: bool,
+    // Weight k of the cube bias: placements score volume growth × (1 + k × (longest / shortest − 1)),
+    // steering boxes away from long flat shapes with a high dimensional weight
+    pub cube_bias: Option<f64>,
+    // Score every candidate position and orientation by these weights instead of taking the first
+    // position that fits; the cube bias is ignored when set. score_weights rate whole solutions.
+    pub placement_weights: Option<placement::PlacementWeights>,
+    // Pull (≥ 0) of fragile and unload-first items toward the opening and the front of the box, added
+    // to their placement score against the volume the box grows by; default 0.5, 0 turns it off
+    pub access_bias: Option<f64>,
+    // Fail with UnknownDestination instead of packing unknown destinations with the default constraints
+    pub strict_destinations: bool,
+    // Most distinct destinations a request may have before it is refused with TooManyDestinations,
+    // as thousands of one-item destinations are an import gone wrong (default 50);
+    // allow_many_destinations packs any number
+    pub max_destinations: Option<usize>,
+    pub allow_many_destinations: bool,
+    // Longest side and heaviest weight an item may plausibly have; anything above is refused as a
+    // typo or a unit mix-up (default 500 cm and 1000 kg, at most 1e6 of either)
+    pub max_item_dimension_cm: Option<f64>,
+    pub max_item_weight_kg: Option<f64>,
+    // Report timing and work counters in PackingSolution.stats
+    pub collect_stats: bool,
+    // Share the position search between the rows of one SKU (see classes.rs; default on). Off,
+    // every row searches every box itself: the same boxes, and what the benchmarks time it against.
+    pub share_class_search: Option<bool>,
+    // Close the boxes no item still to come could go into, so the box search skips them (default
+    // on). Off, every box is tried for every item: the same boxes, and the benchmark's baseline.
+    pub close_full_boxes: Option<bool>,
+    // Record why each item went where it did in PackingSolution.placements_debug; costs memory
+    pub explain: bool,
+    // Collect a log into PackingSolution.debug_log: 1 destinations and improvement passes, 2 also
+    // every item's placement, 3 also every candidate position turned down or scored. Kept to a
+    // couple of thousand lines.
+    pub debug_level: Option<u8>,
+    // Audit the solution before returning it and fail with InternalError on any violation;
+    // on by default in debug builds only
+    pub verify: Option<bool>,
+    // Cardboard price for packaging costs; MaterialSpec.price_per_sqm takes precedence
+    pub cardboard_price_per_sqm: Option<f64>,
+    // Share (0..1) taken off the placement score of a box already holding the item's order
+    // (default 0.2, 0 turns it off)
+    pub order_bonus: Option<f64>,
+    // Share (0..1) taken off the placement score of a box already holding the item's pair_with
+    // item (default 0.5, 0 turns it off); taken on top of the order bonus
+    pub pair_bonus: Option<f64>,
+    // Empty space kept above the items of every box, for an air pillow and the packing slip;
+    // counted in the box height and cardboard
+    pub headspace_cm: Option<f64>,
+    // Face every box opens on (default the top) and the space kept between the items and it so
+    // the flaps close (default 0); catalog cartons lose it from their interior along the axis
+    pub opening_axis: Axis,
+    pub flap_clearance_cm: Option<f64>,
+    // Items added to every box once it is packed (a gift card, a flyer), in cm and kg whatever the
+    // units. Their room is held back from the start, laid flat on top of the other items, with
+    // their weight and value counted against the limits; their destination is the box's.
+    pub reserved_items: Vec<Item>,
+    // Boxes below this fill rate (0..1), other than a destination's only box, get one more attempt
+    // at moving their items into other boxes and are flagged low_fill with a warning if that fails
+    pub min_fill_rate: Option<f64>,
+    // Warn about boxes whose stability score is below this (0..1)
+    pub stability_threshold: Option<f64>,
+    // Percentage of every limit held back for scale and tape-measure error: items are only added
+    // to a box while it stays this far under the weight limit, and boxes that end up this close to
+    // any limit are listed in PackingSolution.limit_warnings. 0 unless set, and below 100.
+    pub safety_margin_pct: Option<f64>,
+    // Check that every box can be unpacked from the top in reverse packing order, lifting each
+    // item straight up, and warn about the items that are trapped; the verify audit and
+    // validate_solution report them as violations too
+    pub check_unpacking: bool,
+    // Oversized parcels approved for this request
+    pub exceptions: Vec<ConstraintException>,
+    // Limits of the services items may name, checked before the built-in ones
+    pub services: Vec<ServiceLimits>,
+    // Most declared value the insurance covers in one box, whatever the destination; boxes are held
+    // to the lower of this and the destination's max_box_value, exceptional boxes included
+    pub insurance_cap_per_box: Option<f64>,
+    // Which hazard classes may share a box, how much hazmat a box holds and where classes are banned
+    pub hazmat_rules: hazmat::HazmatRules,
+    // What makes items one SKU in PackedBox.contents_summary, the manifest and customs lines;
+    // exact ids unless set
+    pub sku_key: SkuKey,
+    // Box labels, see labels.rs; "{destination}-{seq:03}" unless set
+    pub label_template: Option<String>,
+    // Date printed by {date}, as given; today (UTC) unless set
+    pub label_date: Option<String>,
+    // Pack every order into boxes of its own, never mixed with other orders or with items
+    // without one, and report how each order was split in PackingSolution.order_splits
+    pub separate_orders: bool,
+}
+
+// Pre-approved limits for a few boxes of one destination, used only for items the standard limits
+// can't take; each one opens its own box
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConstraintException {
+    pub destination: String,
+    pub limits: DestinationConstraints,
+    // Boxes it may be used for; never more
+    pub max_boxes: u32,
+}
+
+// Limits of a postal product other than the standard parcel, for the items shipped under it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceLimits {
+    // Matched like a destination name, so "Printed papers" and "printed_papers" are one service
+    pub service: String,
+    // Destination the limits are for, or "default" for every destination without an entry of its own
+    pub destination: String,
+    pub limits: DestinationConstraints,
+}
+
+// Config file key for the constraints used by destinations without an entry of their own
+pub const DEFAULT_DESTINATION_KEY: &str = "default";
+
+// ISO two-letter code and the spellings accepted for each built-in destination, already
+// normalized (the canonical names are also the keys of the built-in constraint table)
+const DESTINATION_ALIASES: [(&str, &str, &[&str]); 13] = [
+    ("Australia", "AU", &["australia", "au", "aus", "澳洲", "澳大利亚"]),
+    ("USA", "US", &["usa", "us", "unitedstates", "unitedstatesofamerica", "america", "美国"]),
+    ("UK", "GB", &["uk", "gb", "gbr", "unitedkingdom", "greatbritain", "britain", "england", "英国"]),
+    ("Germany", "DE", &["germany", "de", "deu", "deutschland", "德国"]),
+    ("Japan", "JP", &["japan", "jp", "jpn", "日本"]),
+    ("Canada", "CA", &["canada", "ca", "can", "加拿大"]),
+    ("France", "FR", &["france", "fr", "fra", "法国"]),
+    ("Italy", "IT", &["italy", "it", "ita", "italia", "意大利"]),
+    ("Spain", "ES", &["spain", "es", "esp", "espana", "españa", "西班牙"]),
+    ("Netherlands", "NL", &["netherlands", "nl", "nld", "thenetherlands", "holland", "nederland", "荷兰"]),
+    ("Belgium", "BE", &["belgium", "be", "bel", "belgique", "belgie", "belgië", "比利时"]),
+    ("Sweden", "SE", &["sweden", "se", "swe", "sverige", "瑞典"]),
+    ("Poland", "PL", &["poland", "pl", "pol", "polska", "波兰"]),
+];
+
+// Lowercase and drop everything but letters and digits ("U.S.A." -> "usa")
+pub fn normalize_destination(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+// Canonical built-in destination name for any known spelling, ISO code, or alias
+pub fn canonical_destination(name: &str) -> Option<&'static str> {
+    let normalized = normalize_destination(name);
+    DESTINATION_ALIASES
+        .iter()
+        .find(|(_, _, aliases)| aliases.contains(&normalized.as_str()))
+        .map(|(canonical, _, _)| *canonical)
+}
+
+// Whether two boxes may share items: the same destination and, with separate_orders, the same order.
+// Weight-only boxes share with none, as moving items is placing them, and loose items are boxes themselves.
+pub(crate) fn same_box_group(a: &PackedBox, b: &PackedBox, options: &PackingOptions) -> bool {
+    !a.weight_only &&
+        !b.weight_only &&
+        !a.ship_loose &&
+        !b.ship_loose &&
+        same_destination(&a.destination, &b.destination) &&
+        same_service(a.service.as_deref(), b.service.as_deref()) &&
+        (!options.separate_orders || a.order_id() == b.order_id())
+}
+
+// Whether two service names refer to the same service; no service is only the same as none
+pub(crate) fn same_service(a: Option<&str>, b: Option<&str>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => normalize_destination(a) == normalize_destination(b),
+        (a, b) => a.is_none() && b.is_none(),
+    }
+}
+
+// Whether two destination names refer to the same destination
+pub(crate) fn same_destination(a: &str, b: &str) -> bool {
+    match (canonical_destination(a), canonical_destination(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => normalize_destination(a) == normalize_destination(b),
+    }
+}
+
+// Look up a per-destination entry regardless of how the key was spelled
+fn find_destination_entry<'a, V>(map: &'a HashMap<String, V>, destination: &str) -> Option<&'a V> {
+    map.get(destination).or_else(|| {
+        map.iter()
+            .find(|(key, _)| same_destination(key, destination))
+            .map(|(_, value)| value)
+    })
+}
+
+// Canonical name for a destination: a built-in alias, or a custom/config entry;
+// None when no table knows the destination
+fn resolve_destination(name: &str, options: &PackingOptions) -> Option<String> {
+    if let Some(canonical) = canonical_destination(name) {
+        return Some(canonical.to_string());
+    }
+    options
+        .custom_destinations
+        .keys()
+        .chain(options.configured_destinations.keys())
+        .find(|key| key.as_str() != DEFAULT_DESTINATION_KEY && same_destination(key, name))
+        .cloned()
+}
+
+// Get destination constraints, preferring entries from the config file over the built-in table
+fn get_destination_constraints(destination: &str, options: &PackingOptions) -> DestinationConstraints {
+    let destination = canonical_destination(destination).unwrap_or(destination);
+    let configured = &options.configured_destinations;
+    find_destination_entry(configured, destination)
+        .cloned()
+        .or_else(|| builtin_destination_constraints(destination))
+        .or_else(|| configured.get(DEFAULT_DESTINATION_KEY).cloned())
+        .unwrap_or_else(default_destination_constraints)
+}
+
+// Constraints for destinations without a specific entry
+fn default_destination_constraints() -> DestinationConstraints {
+    DestinationConstraints::default()
+}
+
+// Built-in constraint table
+fn builtin_destination_constraints(destination: &str) -> Option<DestinationConstraints> {
+    let constraints = match destination {
+        "Australia" | "USA" => DestinationConstraints::default(),
+        "UK" => DestinationConstraints {
+            max_box_weight: 15.0,
+            ..Default::default()
+        },
+        "Germany" => DestinationConstraints {
+            max_box_weight: 22.5,
+            ..Default::default()
+        },
+        "Japan" => DestinationConstraints {
+            max_box_dimension: 60.0,
+            max_box_weight: 40.0,
+            alternative_dimensions: Some(Dimensions::new(60.0, 50.0, 50.0)),
+            ..Default::default()
+        },
+        // Canada Post: 30 kg, 2 m longest side, 3 m length plus girth
+        "Canada" => DestinationConstraints {
+            max_box_dimension: 200.0,
+            max_box_weight: 30.0,
+            max_length_plus_girth: Some(300.0),
+            ..Default::default()
+        },
+        // Colissimo: 30 kg, 1 m longest side, 1.5 m length + width + height
+        "France" => DestinationConstraints {
+            max_box_dimension: 100.0,
+            max_box_weight: 30.0,
+            max_dimension_sum: Some(150.0),
+            ..Default::default()
+        },
+        // Poste Italiane: 20 kg, 1.5 m longest side, 3 m length plus girth
+        "Italy" => DestinationConstraints {
+            max_box_dimension: 150.0,
+            max_box_weight: 20.0,
+            max_length_plus_girth: Some(300.0),
+            ..Default::default()
+        },
+        // Correos: 30 kg, 1.2 m longest side, 2.1 m length + width + height
+        "Spain" => DestinationConstraints {
+            max_box_dimension: 120.0,
+            max_box_weight: 30.0,
+            max_dimension_sum: Some(210.0),
+            ..Default::default()
+        },
+        // PostNL: 23 kg within 176 × 78 × 58 cm
+        "Netherlands" => DestinationConstraints {
+            max_box_dimension: 176.0,
+            max_box_weight: 23.0,
+            alternative_dimensions: Some(Dimensions::new(176.0, 78.0, 58.0)),
+            ..Default::default()
+        },
+        // bpost: 30 kg, 1.5 m longest side, 3 m length plus girth
+        "Belgium" => DestinationConstraints {
+            max_box_dimension: 150.0,
+            max_box_weight: 30.0,
+            max_length_plus_girth: Some(300.0),
+            ..Default::default()
+        },
+        // PostNord: 20 kg, 1.5 m longest side, 3 m length plus girth
+        "Sweden" => DestinationConstraints {
+            max_box_dimension: 150.0,
+            max_box_weight: 20.0,
+            max_length_plus_girth: Some(300.0),
+            ..Default::default()
+        },
+        // Poczta Polska: 20 kg, 1.5 m longest side, 3 m length + width + height
+        "Poland" => DestinationConstraints {
+            max_box_dimension: 150.0,
+            max_box_weight: 20.0,
+            max_dimension_sum: Some(300.0),
+            ..Default::default()
+        },
+        _ => return None,
+    };
+    Some(constraints)
+}
+
+// Constraints in effect for every known destination, the config file's entries included
+pub fn active_destination_constraints(configured: &config::DestinationTable) -> HashMap<String, DestinationConstraints> {
+    let mut table: HashMap<String, DestinationConstraints> = DESTINATION_ALIASES
+        .iter()
+        .filter_map(|(name, _, _)| builtin_destination_constraints(name).map(|c| (name.to_string(), c)))
+        .collect();
+    table.insert(DEFAULT_DESTINATION_KEY.to_string(), default_destination_constraints());
+    table.extend(configured.iter().map(|(name, constraints)| (name.clone(), constraints.clone())));
+    table
+}
+
+const BUILTIN_CARRIERS: [&str; 4] = ["DHL", "UPS", "FedEx", "Royal Mail"];
+
+// Built-in carrier profiles
+fn builtin_carrier_profile(name: &str) -> Option<CarrierProfile> {
+    let (name, max_box_dimension, max_box_weight, max_length_plus_girth, alternative_dimensions) =
+        match name.to_ascii_lowercase().as_str() {
+            "dhl" => ("DHL", 120.0, 31.5, None, Some(Dimensions::new(120.0, 60.0, 60.0))),
+            "ups" => ("UPS", 274.0, 70.0, Some(400.0), None),
+            "fedex" => ("FedEx", 274.0, 68.0, Some(330.0), None),
+            "royal mail" | "royalmail" => ("Royal Mail", 61.0, 20.0, None, Some(Dimensions::new(61.0, 46.0, 46.0))),
+            _ => return None,
+        };
+
+    Some(CarrierProfile {
+        name: name.to_string(),
+        max_box_dimension,
+        max_box_weight,
+        max_length_plus_girth,
+        alternative_dimensions,
+    })
+}
+
+// Look up a carrier by name, preferring profiles supplied with the request
+fn find_carrier(name: &str, options: &PackingOptions) -> Option<CarrierProfile> {
+    options
+        .carriers
+        .iter()
+        .find(|carrier| carrier.name.eq_ignore_ascii_case(name))
+        .cloned()
+        .or_else(|| builtin_carrier_profile(name))
+}
+
+// Every carrier profile the request can use: its own, then the built-in ones it doesn't replace
+pub(crate) fn known_carriers(options: &PackingOptions) -> Vec<CarrierProfile> {
+    let mut carriers = options.carriers.clone();
+    for name in BUILTIN_CARRIERS {
+        if !carriers.iter().any(|carrier| carrier.name.eq_ignore_ascii_case(name)) {
+            carriers.extend(builtin_carrier_profile(name));
+        }
+    }
+    carriers
+}
+
+// Name of the carrier requested for a destination, if any
+pub(crate) fn carrier_for_destination<'a>(destination: &str, options: &'a PackingOptions) -> Option<&'a str> {
+    find_destination_entry(&options.destination_carriers, destination)
+        .or(options.default_carrier.as_ref())
+        .map(String::as_str)
+}
+
+// Per-axis (length, width, height) limits from a maximum side and optional special dimensions
+fn axis_limits(max_box_dimension: f64, alternative_dimensions: Option<Dimensions>) -> (f64, f64, f64) {
+    alternative_dimensions.map_or((max_box_dimension, max_box_dimension, max_box_dimension), Into::into)
+}
+
+fn min_option(a: Option<f64>, b: Option<f64>) -> Option<f64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+impl DestinationConstraints {
+    fn axis_limits(&self) -> (f64, f64, f64) {
+        axis_limits(self.max_box_dimension, self.alternative_dimensions)
+    }
+
+    // Limits of a catalog carton packed for this destination
+    fn intersect_spec(&self, spec: &BoxSpec) -> DestinationConstraints {
+        let own = self.axis_limits();
+        DestinationConstraints {
+            alternative_dimensions: Some(Dimensions::new(own.0.min(spec.length), own.1.min(spec.width), own.2.min(spec.height))),
+            max_box_weight: spec.max_weight.map_or(self.max_box_weight, |w| w.min(self.max_box_weight)),
+            ..self.clone()
+        }
+    }
+
+    // Limits permitted by both the destination and the carrier
+    fn intersect_carrier(&self, carrier: &CarrierProfile) -> DestinationConstraints {
+        let own = self.axis_limits();
+        let theirs = axis_limits(carrier.max_box_dimension, carrier.alternative_dimensions);
+        let alternative_dimensions = if self.alternative_dimensions.is_some() || carrier.alternative_dimensions.is_some() {
+            Some(Dimensions::new(own.0.min(theirs.0), own.1.min(theirs.1), own.2.min(theirs.2)))
+        } else {
+            None
+        };
+
+        DestinationConstraints {
+            max_box_dimension: self.max_box_dimension.min(carrier.max_box_dimension),
+            max_box_weight: self.max_box_weight.min(carrier.max_box_weight),
+            alternative_dimensions,
+            max_box_value: self.max_box_value,
+            max_length_plus_girth: min_option(self.max_length_plus_girth, carrier.max_length_plus_girth),
+            max_dimension_sum: self.max_dimension_sum,
+            max_box_volume: self.max_box_volume,
+            max_weight_spread_kg: self.max_weight_spread_kg,
+            headspace_cm: self.headspace_cm,
+        }
+    }
+}
+
+// Destination constraints narrowed by the carrier chosen in the request
+fn box_spec<'a>(box_data: &PackedBox, options: &'a PackingOptions) -> Option<&'a BoxSpec> {
+    let name = box_data.spec.as_deref()?;
+    options.box_catalog.iter().find(|spec| spec.name == name)
+}
+
+// Limits for items going into this particular box: the destination's, narrowed to its carton if it has one
+fn box_constraints(box_data: &PackedBox, options: &PackingOptions) -> DestinationConstraints {
+    box_limits(box_data, &effective_constraints(&box_data.destination, box_data.service.as_deref(), options), options)
+}
+
+// The same, from its destination's constraints already resolved
+fn box_limits(box_data: &PackedBox, destination: &DestinationConstraints, options: &PackingOptions) -> DestinationConstraints {
+    if let Some(limits) = &box_data.override_limits {
+        return insured(limits.clone(), options);
+    }
+    match box_spec(box_data, options) {
+        Some(spec) => destination.intersect_spec(spec),
+        None => destination.clone(),
+    }
+}
+
+// The destination's own limits before any carrier's: those of the service when it is a known one,
+// else the destination's parcel limits
+pub(crate) fn destination_constraints(destination: &str, service: Option<&str>, options: &PackingOptions) -> DestinationConstraints {
+    service
+        .and_then(|service| service_constraints(destination, service, options))
+        .or_else(|| find_destination_entry(&options.custom_destinations, destination).cloned())
+        .unwrap_or_else(|| get_destination_constraints(destination, options))
+}
+
+// Built-in postal products, the same for every destination
+fn builtin_service_constraints(service: &str) -> Option<DestinationConstraints> {
+    let constraints = match normalize_destination(service).as_str() {
+        // UPU printed papers: 5 kg, 60 cm longest side, 90 cm length + width + height
+        "printedpapers" | "printedmatter" => DestinationConstraints {
+            max_box_dimension: 60.0,
+            max_box_weight: 5.0,
+            max_dimension_sum: Some(90.0),
+            ..Default::default()
+        },
+        // UPU small packet: 2 kg in the same size
+        "smallpacket" | "petitpaquet" => DestinationConstraints {
+            max_box_dimension: 60.0,
+            max_box_weight: 2.0,
+            max_dimension_sum: Some(90.0),
+            ..Default::default()
+        },
+        _ => return None,
+    };
+    Some(constraints)
+}
+
+// Limits of a service: the request's entry for the destination, then its entry for every
+// destination, then the built-in product; None for a service none of them knows
+fn service_constraints(destination: &str, service: &str, options: &PackingOptions) -> Option<DestinationConstraints> {
+    let entry = |for_destination: &dyn Fn(&str) -> bool| {
+        options
+            .services
+            .iter()
+            .find(|entry| same_service(Some(&entry.service), Some(service)) && for_destination(&entry.destination))
+            .map(|entry| entry.limits.clone())
+    };
+    entry(&|name| same_destination(name, destination))
+        .or_else(|| entry(&|name| name == DEFAULT_DESTINATION_KEY))
+        .or_else(|| builtin_service_constraints(service))
+}
+
+// Limits of a destination, under the service if there is one, with the insurance cap and the carrier's
+fn effective_constraints(destination: &str, service: Option<&str>, options: &PackingOptions) -> DestinationConstraints {
+    let constraints = insured(destination_constraints(destination, service, options), options);
+    match carrier_for_destination(destination, options).and_then(|name| find_carrier(name, options)) {
+        Some(carrier) => constraints.intersect_carrier(&carrier),
+        None => constraints,
+    }
+}
+
+// The limits with the value cap lowered to the insurance cap
+pub(crate) fn insured(constraints: DestinationConstraints, options: &PackingOptions) -> DestinationConstraints {
+    DestinationConstraints {
+        max_box_value: min_option(constraints.max_box_value, options.insurance_cap_per_box),
+        ..constraints
+    }
+}
+
+// The cap a box's value is held to, and whether the insurance or the customs one is lower
+fn value_cap(box_data: &PackedBox, options: &PackingOptions) -> Option<ValueCap> {
+    let customs = match &box_data.override_limits {
+        Some(limits) => limits.max_box_value,
+        None => destination_constraints(&box_data.destination, box_data.service.as_deref(), options).max_box_value,
+    };
+    match (options.insurance_cap_per_box, customs) {
+        (Some(insurance), Some(customs)) if customs < insurance => Some(ValueCap { limit: customs, source: ValueCapSource::Customs }),
+        (Some(insurance), _) => Some(ValueCap { limit: insurance, source: ValueCapSource::Insurance }),
+        (None, Some(customs)) => Some(ValueCap { limit: customs, source: ValueCapSource::Customs }),
+        (None, None) => None,
+    }
+}
+
+// Longest side plus twice the sum of the other two
+fn length_plus_girth(a: f64, b: f64, c: f64) -> f64 {
+    let longest = a.max(b).max(c);
+    longest + 2.0 * (a + b + c - longest)
+}
+
+// Check if a declared value is within the destination's per-box value cap
+pub(crate) fn within_value_cap(value: f64, constraints: &DestinationConstraints) -> bool {
+    constraints.max_box_value.is_none_or(|max_value| value <= max_value)
+}
+
+// Check if box dimensions respect the per-axis, length-plus-girth, dimension-sum and volume limits
+pub(crate) fn within_dimension_limits(length: f64, width: f64, height: f64, constraints: &DestinationConstraints) -> bool {
+    within_size_limits(length, width, height, constraints) &&
+    constraints
+        .max_box_volume
+        .is_none_or(|max_volume| length * width * height <= max_volume)
+}
+
+// The limits on the sides alone, without the volume cap
+fn within_size_limits(length: f64, width: f64, height: f64, constraints: &DestinationConstraints) -> bool {
+    let (max_length, max_width, max_height) = constraints.axis_limits();
+
+    length <= max_length &&
+    width <= max_width &&
+    height <= max_height &&
+    constraints
+        .max_length_plus_girth
+        .is_none_or(|max_girth| length_plus_girth(length, width, height) <= max_girth) &&
+    constraints
+        .max_dimension_sum
+        .is_none_or(|max_sum| length + width + height <= max_sum)
+}
+
+// Check if an item fits within destination constraints
+fn fits_constraints(item: &Item, constraints: &DestinationConstraints) -> bool {
+    within_dimension_limits(item.length, item.width, item.height, constraints) &&
+    item.weight <= constraints.max_box_weight &&
+    within_value_cap(item.declared_value(), constraints)
+}
+
+// Whether the item fits the limits on its own with the clearance (see PackedBox::clearance) around it
+fn fits_with_clearance(item: &Item, constraints: &DestinationConstraints, clearance: [f64; 3]) -> bool {
+    within_dimension_limits(item.length + clearance[0], item.width + clearance[1], item.height + clearance[2], constraints) &&
+    item.weight <= constraints.max_box_weight &&
+    within_value_cap(item.declared_value(), constraints)
+}
+
+// Headspace kept above the items of the destination's boxes of the service, in cm
+pub(crate) fn headspace(destination: &str, service: Option<&str>, options: &PackingOptions) -> f64 {
+    effective_constraints(destination, service, options)
+        .headspace_cm
+        .or(options.headspace_cm)
+        .unwrap_or(0.0)
+        .max(0.0)
+}
+
+pub(crate) fn flap_clearance(options: &PackingOptions) -> f64 {
+    options.flap_clearance_cm.unwrap_or(0.0).max(0.0)
+}
+
+// Space kept beyond the items of the destination's boxes of the service, as PackedBox::clearance
+pub(crate) fn clearance(destination: &str, service: Option<&str>, options: &PackingOptions) -> [f64; 3] {
+    let reserved = reservation(options).map_or(0.0, |reserved| reserved.height);
+    let mut clearance = [0.0, 0.0, headspace(destination, service, options) + reserved];
+    clearance[options.opening_axis.index()] += flap_clearance(options);
+    clearance
+}
+
+// A reserved item as it goes in, on its largest face unless it has to keep another one down
+fn lying_flat(item: &Item) -> Item {
+    item.allowed_rotations()
+        .into_iter()
+        .map(|rotation| item.with_rotation(rotation))
+        .min_by(|a, b| a.height.total_cmp(&b.height))
+        .unwrap_or_else(|| item.clone())
+}
+
+// Room held back in every box for PackingOptions.reserved_items, None without any
+pub(crate) fn reservation(options: &PackingOptions) -> Option<Reservation> {
+    if options.reserved_items.is_empty() {
+        return None;
+    }
+    let mut reservation = Reservation::default();
+    for item in &options.reserved_items {
+        let flat = lying_flat(item);
+        reservation.length = reservation.length.max(flat.length.max(flat.width));
+        reservation.width = reservation.width.max(flat.length.min(flat.width));
+        reservation.height += flat.height;
+        reservation.weight += item.weight;
+        reservation.value += item.declared_value();
+    }
+    Some(reservation)
+}
+
+// Length and width a box with the clearance needs for the reserved layer's floor
+pub(crate) fn reserved_floor(reservation: Option<&Reservation>, clearance: [f64; 3]) -> (f64, f64) {
+    reservation.map_or((0.0, 0.0), |reserved| (reserved.length + clearance[0], reserved.width + clearance[1]))
+}
+
+// Size of a cut-to-fit box holding just the item, with the clearance and any reserved floor
+pub(crate) fn own_box_size(item: &Item, clearance: [f64; 3], options: &PackingOptions) -> (f64, f64, f64) {
+    let floor = reserved_floor(reservation(options).as_ref(), clearance);
+    ((item.length + clearance[0]).max(floor.0), (item.width + clearance[1]).max(floor.1), item.height + clearance[2])
+}
+
+// Check if a finished box (dimensions and gross weight) is within destination constraints
+fn box_fits_constraints(box_data: &PackedBox, constraints: &DestinationConstraints) -> bool {
+    within_dimension_limits(box_data.length, box_data.width, box_data.height, constraints) &&
+    box_data.weight <= constraints.max_box_weight &&
+    within_value_cap(box_data.value + box_data.reserved_value(), constraints)
+}
+
+// Share of a limit held back by the safety margin, 0..1
+fn safety_margin(options: &PackingOptions) -> f64 {
+    options.safety_margin_pct.unwrap_or(0.0) / 100.0
+}
+
+// Gross weight the box may reach with another item in it: the limit less the safety margin. The
+// first item may go up to the limit itself, a heavy item is no safer on its own than in no box.
+fn weight_limit(box_data: &PackedBox, constraints: &DestinationConstraints, options: &PackingOptions) -> f64 {
+    if box_data.items.is_empty() {
+        constraints.max_box_weight
+    } else {
+        constraints.max_box_weight * (1.0 - safety_margin(options))
+    }
+}
+
+// The limits with the weight limit less the safety margin, for the strategies that fill whole
+// boxes of many items at once
+fn margined_constraints(constraints: &DestinationConstraints, options: &PackingOptions) -> DestinationConstraints {
+    DestinationConstraints {
+        max_box_weight: constraints.max_box_weight * (1.0 - safety_margin(options)),
+        ..constraints.clone()
+    }
+}
+
+// Round a dimension up to the next multiple of the increment, ignoring float noise
+// so 37.000000000001 stays 37.0 rather than jumping a whole step
+fn round_up_to_increment(value: f64, increment: f64) -> f64 {
+    const NOISE: f64 = 1e-9;
+    (value / increment - NOISE).ceil().max(0.0) * increment
+}
+
+// Round each box up to the carton increment, keeping the exact size where rounding would break a limit
+fn round_box_dimensions(solution: &mut PackingSolution, increment: f64, options: &PackingOptions) {
+    if increment <= 0.0 {
+        return;
+    }
+
+    // Catalog cartons already have their final size, and loose items their own
+    for (index, box_data) in solution.boxes.iter_mut().enumerate().filter(|(_, b)| b.spec.is_none() && !b.ship_loose) {
+        let constraints = box_constraints(box_data, options);
+        let exact = (box_data.length, box_data.width, box_data.height, box_data.weight);
+
+        box_data.length = round_up_to_increment(box_data.length, increment);
+        box_data.width = round_up_to_increment(box_data.width, increment);
+        box_data.height = round_up_to_increment(box_data.height, increment);
+        // Bigger carton, more cardboard
+        box_data.update_box_weight();
+
+        if !box_fits_constraints(box_data, &constraints) {
+            (box_data.length, box_data.width, box_data.height, box_data.weight) = exact;
+            solution.warnings.push(format!(
+                "Box {} ({}): rounding up to {} cm would exceed the destination limits, kept exact size {:.2}×{:.2}×{:.2} cm",
+                index + 1,
+                box_data.destination,
+                increment,
+                box_data.length,
+                box_data.width,
+                box_data.height
+            ));
+        }
+    }
+}
+
+// Tolerance for touching faces (an item resting exactly on another)
+const CONTACT_EPSILON: f64 = 1e-6;
+
+// Chebyshev distance from a point to a rectangle (0 inside it)
+fn distance_to_rect(point: (f64, f64), rect: (f64, f64, f64, f64)) -> f64 {
+    let dx = (rect.0 - point.0).max(point.0 - rect.2).max(0.0);
+    let dy = (rect.1 - point.1).max(point.1 - rect.3).max(0.0);
+    dx.max(dy)
+}
+
+// How far an item's base reaches past the items under it. Along each axis an unsupported run of
+// the base ending at a support counts its length (an edge overhang), and one between two supports
+// its width (a bridged gap); a part with no support in line with it counts its distance to the
+// nearest support. An item with nothing under it at all is unsupported everywhere. Irregular items
+// don't count as support, their top face isn't where their dimensions say.
+fn overhang(box_data: &PackedBox, item: &Item, position: Position) -> f64 {
+    let Position { x, y, z } = position;
+    let (x1, y1) = (x + item.length, y + item.width);
+
+    // Top faces at the base height, clipped to the base
+    let supports: Vec<(f64, f64, f64, f64)> = box_data
+        .items
+        .iter()
+        .filter_map(|other| {
+            let Position { x: ox, y: oy, z: oz } = other.position?;
+            if other.irregular || (oz + other.height - z).abs() > CONTACT_EPSILON {
+                return None;
+            }
+            let clipped = (ox.max(x), oy.max(y), (ox + other.length).min(x1), (oy + other.width).min(y1));
+            (clipped.0 < clipped.2 && clipped.1 < clipped.3).then_some(clipped)
+        })
+        .collect();
+    if supports.is_empty() {
+        return f64::INFINITY;
+    }
+
+    // Cells between every support edge; each is either fully covered or fully open
+    let mut xs = vec![x, x1];
+    let mut ys = vec![y, y1];
+    for &(sx0, sy0, sx1, sy1) in &supports {
+        xs.extend([sx0, sx1]);
+        ys.extend([sy0, sy1]);
+    }
+    xs.sort_by(f64::total_cmp);
+    xs.dedup();
+    ys.sort_by(f64::total_cmp);
+    ys.dedup();
+
+    let (columns, rows) = (xs.len() - 1, ys.len() - 1);
+    let open: Vec<Vec<bool>> = (0..columns)
+        .map(|i| {
+            (0..rows)
+                .map(|j| {
+                    let centre = ((xs[i] + xs[i + 1]) / 2.0, (ys[j] + ys[j + 1]) / 2.0);
+                    !supports.iter().any(|&rect| distance_to_rect(centre, rect) == 0.0)
+                })
+                .collect()
+        })
+        .collect();
+
+    // Length of an open run, or None when neither end of it touches a support
+    let run = |start: usize, end: usize, last: usize, edges: &[f64]| {
+        let supported = start > 0 || end < last;
+        supported.then(|| edges[end + 1] - edges[start])
+    };
+
+    let mut widest: f64 = 0.0;
+    for i in 0..columns {
+        for j in 0..rows {
+            if !open[i][j] {
+                continue;
+            }
+            let (mut left, mut right) = (i, i);
+            while left > 0 && open[left - 1][j] {
+                left -= 1;
+            }
+            while right + 1 < columns && open[right + 1][j] {
+                right += 1;
+            }
+            let (mut low, mut high) = (j, j);
+            while low > 0 && open[i][low - 1] {
+                low -= 1;
+            }
+            while high + 1 < rows && open[i][high + 1] {
+                high += 1;
+            }
+
+            let along_x = run(left, right, columns - 1, &xs);
+            let along_y = run(low, high, rows - 1, &ys);
+            let reach = match (along_x, along_y) {
+                (Some(a), Some(b)) => a.min(b),
+                (Some(a), None) | (None, Some(a)) => a,
+                // Nothing in line: measure from the cell's corners to the nearest support
+                (None, None) => [(xs[i], ys[j]), (xs[i + 1], ys[j]), (xs[i], ys[j + 1]), (xs[i + 1], ys[j + 1])]
+                    .into_iter()
+                    .map(|corner| {
+                        supports
+                            .iter()
+                            .map(|&rect| distance_to_rect(corner, rect))
+                            .fold(f64::INFINITY, f64::min)
+                    })
+                    .fold(0.0, f64::max),
+            };
+            widest = widest.max(reach);
+        }
+    }
+    widest
+}
+
+// Whether the placement respects the overhang tolerance; items on the floor always do
+fn overhang_ok(box_data: &PackedBox, item: &Item, position: Position, options: &PackingOptions) -> bool {
+    match options.allow_overhang_cm {
+        Some(tolerance) if position.z > CONTACT_EPSILON => {
+            overhang(box_data, item, position) <= tolerance.max(0.0) + CONTACT_EPSILON
+        }
+        _ => true,
+    }
+}
+
+// Whether the footprints of two placed items overlap
+fn footprints_overlap(a: &Item, (ax, ay): (f64, f64), b: &Item, (bx, by): (f64, f64)) -> bool {
+    ax < bx + b.length - CONTACT_EPSILON &&
+    bx < ax + a.length - CONTACT_EPSILON &&
+    ay < by + b.width - CONTACT_EPSILON &&
+    by < ay + a.width - CONTACT_EPSILON
+}
+
+// Weight resting on a placed item: everything above it whose footprint overlaps its own
+fn load_on(box_data: &PackedBox, below: &Item) -> f64 {
+    let Some(Position { x: bx, y: by, z: bz }) = below.position else {
+        return 0.0;
+    };
+    let below_top = bz + below.height;
+    box_data
+        .items
+        .iter()
+        .filter_map(|other| {
+            let Position { x: ox, y: oy, z: oz } = other.position?;
+            (oz >= below_top - CONTACT_EPSILON && footprints_overlap(below, (bx, by), other, (ox, oy))).then_some(other.weight)
+        })
+        .sum()
+}
+
+// Whether the item at the position keeps every stack within the max_height_above_cm and max_load_kg
+// of the items in it: the items below its footprint get its top and weight on them, and it gets the
+// items over it on itself
+fn stack_limits_ok(box_data: &PackedBox, item: &Item, position: Position) -> bool {
+    let Position { x, y, z } = position;
+    let top = z + item.height;
+    if let Some(limit) = item.max_load_kg {
+        let above: f64 = box_data
+            .items
+            .iter()
+            .filter(|other| {
+                other.position.is_some_and(|Position { x: ox, y: oy, z: oz }| oz >= top - CONTACT_EPSILON && footprints_overlap(item, (x, y), other, (ox, oy)))
+            })
+            .map(|other| other.weight)
+            .sum();
+        if above > limit + CONTACT_EPSILON {
+            return false;
+        }
+    }
+    box_data.items.iter().all(|other| {
+        let Some(Position { x: ox, y: oy, z: oz }) = other.position else {
+            return true;
+        };
+        if !footprints_overlap(item, (x, y), other, (ox, oy)) {
+            return true;
+        }
+        let other_top = oz + other.height;
+        if other_top <= z + CONTACT_EPSILON {
+            other.max_height_above_cm.is_none_or(|limit| top - other_top <= limit + CONTACT_EPSILON) &&
+            other.max_load_kg.is_none_or(|limit| load_on(box_data, other) + item.weight <= limit + CONTACT_EPSILON)
+        } else if oz >= top - CONTACT_EPSILON {
+            item.max_height_above_cm.is_none_or(|limit| other_top - top <= limit + CONTACT_EPSILON)
+        } else {
+            true
+        }
+    })
+}
+
+// Whether something holds a squashed item down: an item resting on its top, or the box top
+fn compression_held(box_data: &PackedBox, item: &Item, position: Position, bounds: BoundsMode) -> bool {
+    let Position { x, y, z } = position;
+    let top = z + item.height;
+    top + box_data.clearance()[2] >= bounds.ceiling() - CONTACT_EPSILON ||
+    box_data.items.iter().any(|other| {
+        other.position.is_some_and(|Position { x: ox, y: oy, z: oz }| {
+            (oz - top).abs() <= CONTACT_EPSILON && footprints_overlap(item, (x, y), other, (ox, oy))
+        })
+    })
+}
+
+// Which check turned a placement down
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Rejection {
+    // The grown box would break the destination's size limits, or the item sticks out of a built one
+    Bounds,
+    // The grown box would be within the size limits but over max_box_volume
+    Volume,
+    Collision,
+    // Overhang, stacking height, or a liquid or squashed item without the footing it needs
+    Support,
+    // Weight limit, tare and dunnage included
+    Weight,
+    // Declared value cap
+    Value,
+    // A hazard class the box's items may not be packed with, or too much hazmat
+    Hazmat,
+}
+
+// How far a box may extend to take an item
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum BoundsMode<'a> {
+    // Grows with its items up to the limits
+    Growable(&'a DestinationConstraints),
+    // A built carton's interior, length × width × height
+    Fixed(f64, f64, f64),
+}
+
+impl<'a> BoundsMode<'a> {
+    // Catalog cartons keep their size, cut-to-fit boxes grow
+    fn for_box(box_data: &PackedBox, constraints: &'a DestinationConstraints) -> Self {
+        if box_data.spec.is_some() {
+            BoundsMode::fixed(box_data)
+        } else {
+            BoundsMode::Growable(constraints)
+        }
+    }
+
+    // The box as it is now, built
+    pub(crate) fn fixed(box_data: &PackedBox) -> Self {
+        BoundsMode::Fixed(box_data.length, box_data.width, box_data.height)
+    }
+
+    // Highest the box's contents may reach, the clearance above them included
+    fn ceiling(&self) -> f64 {
+        match *self {
+            BoundsMode::Growable(constraints) => constraints.axis_limits().2,
+            BoundsMode::Fixed(_, _, height) => height,
+        }
+    }
+
+    // Size of the box with the item in at the position, None when that breaks the bounds
+    pub(crate) fn extent(&self, box_data: &PackedBox, item: &Item, Position { x, y, z }: Position) -> Option<(f64, f64, f64)> {
+        let clearance = box_data.clearance();
+        let (end_x, end_y, top) = (x + item.length + clearance[0], y + item.width + clearance[1], z + item.height + clearance[2]);
+        match *self {
+            BoundsMode::Growable(constraints) => {
+                let grown = grown_extent(box_data, (end_x, end_y, top));
+                within_dimension_limits(grown.0, grown.1, grown.2, constraints).then_some(grown)
+            }
+            BoundsMode::Fixed(length, width, height) => {
+                let inside = x >= -CONTACT_EPSILON &&
+                    y >= -CONTACT_EPSILON &&
+                    z >= -CONTACT_EPSILON &&
+                    end_x <= length + CONTACT_EPSILON &&
+                    end_y <= width + CONTACT_EPSILON &&
+                    top <= height + CONTACT_EPSILON;
+                inside.then_some((length, width, height))
+            }
+        }
+    }
+
+    // Which limit `extent` found broken: the volume cap alone, or the size of the box
+    fn rejection(&self, box_data: &PackedBox, item: &Item, Position { x, y, z }: Position) -> Rejection {
+        let BoundsMode::Growable(constraints) = *self else {
+            return Rejection::Bounds;
+        };
+        let clearance = box_data.clearance();
+        let (length, width, height) = grown_extent(box_data, (x + item.length + clearance[0], y + item.width + clearance[1], z + item.height + clearance[2]));
+        if constraints.max_box_volume.is_some() && within_size_limits(length, width, height, constraints) {
+            Rejection::Volume
+        } else {
+            Rejection::Bounds
+        }
+    }
+}
+
+// Size of a grown box reaching to the far corner of its new item
+fn grown_extent(box_data: &PackedBox, (end_x, end_y, top): (f64, f64, f64)) -> (f64, f64, f64) {
+    let floor = box_data.min_floor();
+    (end_x.max(box_data.length).max(floor.0), end_y.max(box_data.width).max(floor.1), top.max(box_data.height))
+}
+
+// Check if item can be placed at the position without collision; `constraints` supply the weight
+// and value caps, `bounds` the space the item may take up
+fn check_placement(
+    box_data: &PackedBox,
+    item: &Item,
+    position: Position,
+    bounds: BoundsMode,
+    constraints: &DestinationConstraints,
+    options: &PackingOptions,
+) -> Result<(), Rejection> {
+    // Liquids stand on the floor
+    if item.is_liquid && position.z > CONTACT_EPSILON {
+        return Err(Rejection::Support);
+    }
+
+    if !options.hazmat_rules.allows(box_data, item) {
+        return Err(Rejection::Hazmat);
+    }
+
+    if item.nominal_height.is_some() && !compression_held(box_data, item, position, bounds) {
+        return Err(Rejection::Support);
+    }
+
+    // Check if the box, grown or as built, has room for the item there
+    let Some((new_length, new_width, new_height)) = bounds.extent(box_data, item, position) else {
+        return Err(bounds.rejection(box_data, item, position));
+    };
+    let widens = new_length > box_data.length + CONTACT_EPSILON || new_width > box_data.width + CONTACT_EPSILON;
+    if options.lock_footprint_after_first_item && !box_data.items.is_empty() && widens {
+        return Err(Rejection::Bounds);
+    }
+
+    // Check for collisions with existing items
+    if box_data.collides(item, position) {
+        return Err(Rejection::Collision);
+    }
+
+    if !overhang_ok(box_data, item, position, options) || !stack_limits_ok(box_data, item, position) {
+        return Err(Rejection::Support);
+    }
+
+    // Check if total weight would exceed maximum; a grown box is made of more cardboard and, filled
+    // by density, holds a different amount of dunnage
+    let items_weight: f64 = box_data.items.iter().map(|existing| existing.weight).sum();
+    let items_volume: f64 = box_data.items.iter().map(Item::volume).sum::<f64>() + item.volume();
+    let grown_weight = items_weight +
+        item.weight +
+        box_data.reserved_weight() +
+        carton_weight(&box_data.material, new_length, new_width, new_height) +
+        dunnage_weight(box_data.dunnage.as_ref(), (new_length, new_width, new_height), items_volume);
+    if grown_weight > weight_limit(box_data, constraints, options) {
+        return Err(Rejection::Weight);
+    }
+    if !within_value_cap(box_data.value + box_data.reserved_value() + item.declared_value(), constraints) {
+        return Err(Rejection::Value);
+    }
+    Ok(())
+}
+
+fn can_place_item(
+    box_data: &PackedBox,
+    item: &Item,
+    position: Position,
+    bounds: BoundsMode,
+    constraints: &DestinationConstraints,
+    options: &PackingOptions,
+) -> bool {
+    let outcome = check_placement(box_data, item, position, bounds, constraints, options);
+    explain::record_candidate(outcome);
+    closing::record(outcome);
+    if let Err(rejection) = outcome {
+        debug_log::log(debug_log::TRACE, || format!("{:?} turned down: {:?}", position, rejection));
+    }
+    stats::record(|stats| {
+        stats.candidate_positions += 1;
+        match outcome {
+            Ok(()) => {}
+            Err(Rejection::Bounds | Rejection::Volume) => stats.rejected_bounds += 1,
+            Err(Rejection::Collision) => stats.rejected_collision += 1,
+            Err(Rejection::Support) => stats.rejected_support += 1,
+            Err(Rejection::Weight) => stats.rejected_weight += 1,
+            Err(Rejection::Value) => stats.rejected_value += 1,
+            Err(Rejection::Hazmat) => stats.rejected_hazmat += 1,
+        }
+    });
+    outcome.is_ok()
+}
+
+// Level of candidate_points asked for, else the objective's default
+fn candidate_quality(options: &PackingOptions) -> CandidateQuality {
+    options.candidate_quality.unwrap_or(match options.objective {
+        Objective::FewestBoxes => CandidateQuality::Extended,
+        _ => CandidateQuality::Basic,
+    })
+}
+
+// Extreme points (candidates for placement) of a box, after the free-space corners at that quality
+// or with that strategy
+fn candidate_points(box_data: &PackedBox, options: &PackingOptions) -> Vec<Position> {
+    let quality = candidate_quality(options);
+    let extended = quality >= CandidateQuality::Extended;
+    let mut candidates = Vec::with_capacity(box_data.items.len() * 9 + 4);
+    if quality == CandidateQuality::EmptyMaximalSpaces || options.strategy == Strategy::FreeSpaces {
+        candidates.extend(box_data.spaces.corners(&box_data.items));
+    }
+
+    // Add (0,0,0) as a candidate
+    candidates.push(Position::ORIGIN);
+
+    // Add extreme points based on existing items
+    for existing_item in &box_data.items {
+        if let Some(Position { x: ex, y: ey, z: ez }) = existing_item.position {
+            // Point to the right of the item
+            candidates.push(Position::new(ex + existing_item.length, ey, ez));
+
+            // Point to the front of the item
+            candidates.push(Position::new(ex, ey + existing_item.width, ez));
+
+            // Point on top of the item
+            candidates.push(Position::new(ex, ey, ez + existing_item.height));
+
+            if extended {
+                // The same points projected back onto the box walls and floor,
+                // which reach gaps the plain extreme points miss
+                candidates.push(Position::new(ex + existing_item.length, 0.0, ez));
+                candidates.push(Position::new(ex + existing_item.length, ey, 0.0));
+                candidates.push(Position::new(0.0, ey + existing_item.width, ez));
+                candidates.push(Position::new(ex, ey + existing_item.width, 0.0));
+                candidates.push(Position::new(0.0, ey, ez + existing_item.height));
+                candidates.push(Position::new(ex, 0.0, ez + existing_item.height));
+            }
+        }
+    }
+
+    if extended {
+        // Corners of the current box extent
+        candidates.push(Position::new(box_data.length, 0.0, 0.0));
+        candidates.push(Position::new(0.0, box_data.width, 0.0));
+        candidates.push(Position::new(0.0, 0.0, box_data.height));
+    }
+
+    candidates
+}
+
+// Find the best position to place an item in a box with the given limits
+fn find_best_position(
+    box_data: &PackedBox,
+    item: &Item,
+    constraints: &DestinationConstraints,
+    options: &PackingOptions,
+) -> Option<Position> {
+    // For an empty box, place at origin (catalog cartons and squashed items still check the placement)
+    if box_data.items.is_empty() && box_data.spec.is_none() && item.nominal_height.is_none() {
+        return Some(Position::ORIGIN);
+    }
+
+    let mut candidates = candidate_points(box_data, options);
+
+    // Sort candidates by the sum of coordinates (prefer closer to origin)
+    candidates.sort_by(|a, b| {
+        let sum_a = a.x + a.y + a.z;
+        let sum_b = b.x + b.y + b.z;
+        sum_a.partial_cmp(&sum_b).unwrap_or(Ordering::Equal)
+    });
+
+    // Try each candidate position
+    let bounds = BoundsMode::for_box(box_data, constraints);
+    candidates.into_iter().find(|&pos| can_place_item(box_data, item, pos, bounds, constraints, options))
+}
+
+// Volume the box grows by when the item goes in at the position, weighted by how far the grown box
+// is from a cube when the cube bias is on
+fn growth_score(box_data: &PackedBox, item: &Item, Position { x, y, z }: Position, options: &PackingOptions) -> f64 {
+    let clearance = box_data.clearance();
+    let grown = [
+        (x + item.length + clearance[0]).max(box_data.length),
+        (y + item.width + clearance[1]).max(box_data.width),
+        (z + item.height + clearance[2]).max(box_data.height),
+    ];
+    let growth = grown.iter().product::<f64>() - box_data.volume();
+    match options.cube_bias {
+        Some(bias) => {
+            let longest = grown.iter().copied().fold(f64::MIN, f64::max);
+            let shortest = grown.iter().copied().fold(f64::MAX, f64::min);
+            growth * (1.0 + bias.max(0.0) * (longest / shortest - 1.0))
+        }
+        None => growth,
+    }
+}
+
+// Find the best position and rotation to place an item in a box; `constraints` are the box's own
+// limits, resolved by the caller once rather than for every candidate position
+fn find_best_position_with_rotation(
+    box_data: &PackedBox,
+    item: &Item,
+    constraints: &DestinationConstraints,
+    options: &PackingOptions,
+) -> Option<(Position, Item)> {
+    let mut best_placement: Option<(Position, Item)> = None;
+    // (weighted score with placement weights, growth score with the cube bias, resulting surface area)
+    let mut best_score = (f64::MAX, f64::MAX);
+
+    explain::open_attempt(box_data);
+    closing::begin();
+
+    // Try all permitted rotations of the item; compressible items are squashed only when
+    // no rotation fits at full height
+    let rotated: Vec<Item> = item.allowed_rotations().into_iter().map(|rotation| item.with_rotation(rotation)).collect();
+    let squashed: Vec<Item> = rotated.iter().filter_map(Item::squashed).collect();
+    for orientations in [rotated, squashed] {
+        for rotated_item in orientations {
+            stats::record(|stats| stats.rotation_trials += 1);
+            explain::open_trial(rotated_item.rotation, rotated_item.nominal_height.is_some());
+            let _trial = debug_log::span(debug_log::TRACE, || {
+                format!(
+                    "rotation {} ({}×{}×{}) in a {}×{}×{} box of {} items",
+                    rotated_item.rotation,
+                    rotated_item.length,
+                    rotated_item.width,
+                    rotated_item.height,
+                    box_data.length,
+                    box_data.width,
+                    box_data.height,
+                    box_data.items.len()
+                )
+            });
+
+            // Skip if this rotation violates constraints
+            if !fits_constraints(&rotated_item, constraints) {
+                explain::close_trial(true, None);
+                continue;
+            }
+
+            // Find the best position for this rotation; placement weights and the cube bias weigh
+            // every candidate point instead of taking the first one that fits
+            let scan = |score: &dyn Fn(Position) -> f64| {
+                candidate_points(box_data, options)
+                    .into_iter()
+                    .filter(|&pos| can_place_item(box_data, &rotated_item, pos, BoundsMode::for_box(box_data, constraints), constraints, options))
+                    .map(|pos| (pos, score(pos)))
+                    .inspect(|(pos, score)| debug_log::log(debug_log::TRACE, || format!("{:?} scores {:.4}", pos, score)))
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+            };
+            let access_bias = if item.wants_access() { options.access_bias.unwrap_or(DEFAULT_ACCESS_BIAS).max(0.0) } else { 0.0 };
+            let placement = match (&options.placement_weights, options.cube_bias) {
+                (Some(weights), _) => scan(&|pos| {
+                    let components = placement::components(box_data, &rotated_item, pos);
+                    weights.score(&components) + access_bias * components.access
+                }),
+                // Against the growth relative to the item, so the bias means the same for every size
+                _ if access_bias > 0.0 => scan(&|pos| {
+                    let growth = growth_score(box_data, &rotated_item, pos, options) / rotated_item.volume().max(f64::MIN_POSITIVE);
+                    growth + access_bias * placement::components(box_data, &rotated_item, pos).access
+                }),
+                (None, Some(_)) => scan(&|pos| growth_score(box_data, &rotated_item, pos, options)),
+                (None, None) => find_best_position(box_data, &rotated_item, constraints, options).map(|pos| (pos, 0.0)),
+            };
+
+            let scored = placement.map(|(position, growth)| {
+                // Create a temporary box copy to test this placement
+                let mut test_box = box_data.clone();
+                test_box.add_item(rotated_item.clone(), position);
+
+                // Calculate the resulting surface area
+                (position, (growth, test_box.surface_area()))
+            });
+            explain::close_trial(false, scored.map(|(position, score)| scored_position(position, score)));
+            if let Some((position, (growth, surface))) = scored {
+                debug_log::log(debug_log::TRACE, || format!("best {:?}, score {:.4}, surface {:.1}", position, growth, surface));
+            }
+
+            // Update best placement if this scores lower
+            if let Some((position, score)) = scored {
+                if score.partial_cmp(&best_score) == Some(Ordering::Less) {
+                    best_score = score;
+                    best_placement = Some((position, rotated_item));
+                }
+            }
+        }
+        if best_placement.is_some() {
+            break;
+        }
+    }
+
+    explain::close_attempt(best_placement.as_ref().map(|(position, _)| scored_position(*position, best_score)));
+    best_placement
+}
+
+fn scored_position(position: Position, (growth, surface_area): (f64, f64)) -> explain::ScoredPosition {
+    explain::ScoredPosition {
+        position,
+        growth,
+        surface_area,
+    }
+}
+
+fn weight_variance(boxes: &[PackedBox]) -> f64 {
+    let mean = boxes.iter().map(|b| b.weight).sum::<f64>() / boxes.len() as f64;
+    boxes.iter().map(|b| (b.weight - mean).powi(2)).sum::<f64>() / boxes.len() as f64
+}
+
+fn weight_spread(boxes: &[PackedBox]) -> f64 {
+    let heaviest = boxes.iter().map(|b| b.weight).fold(f64::MIN, f64::max);
+    let lightest = boxes.iter().map(|b| b.weight).fold(f64::MAX, f64::min);
+    heaviest - lightest
+}
+
+// Lower is better: the weight variance alone
+fn variance_score(boxes: &[PackedBox]) -> [f64; 2] {
+    [weight_variance(boxes), 0.0]
+}
+
+// Lower is better: the spread first, the variance to make progress while several boxes tie at an extreme
+fn spread_score(boxes: &[PackedBox]) -> [f64; 2] {
+    [weight_spread(boxes), weight_variance(boxes)]
+}
+
+fn lower_score(candidate: [f64; 2], current: [f64; 2]) -> bool {
+    const NOISE: f64 = 1e-9;
+    candidate[0] < current[0] - NOISE || ((candidate[0] - current[0]).abs() <= NOISE && candidate[1] < current[1] - NOISE)
+}
+
+// Move one item from a heavier box into a lighter one if that lowers the score
+fn try_balancing_move(boxes: &mut [PackedBox], options: &PackingOptions, score: fn(&[PackedBox]) -> [f64; 2]) -> bool {
+    let current = score(boxes);
+    let mut order: Vec<usize> = (0..boxes.len()).collect();
+    order.sort_by(|&a, &b| boxes[b].weight.partial_cmp(&boxes[a].weight).unwrap_or(Ordering::Equal));
+
+    for (rank, &heavy) in order.iter().enumerate() {
+        // Never empty a box; the box count stays what the greedy pass produced
+        if boxes[heavy].items.len() < 2 {
+            continue;
+        }
+        for &light in order[rank + 1..].iter().rev() {
+            let gap = boxes[heavy].weight - boxes[light].weight;
+            if gap <= 0.0 {
+                continue;
+            }
+
+            // Items that narrow the gap, the ones closest to half of it first
+            let mut candidates: Vec<usize> = (0..boxes[heavy].items.len())
+                .filter(|&i| boxes[heavy].items[i].weight < gap)
+                .collect();
+            candidates.sort_by(|&a, &b| {
+                let distance = |i: usize| (boxes[heavy].items[i].weight - gap / 2.0).abs();
+                distance(a).partial_cmp(&distance(b)).unwrap_or(Ordering::Equal)
+            });
+
+            let light_limits = box_constraints(&boxes[light], options);
+            for index in candidates {
+                let item = &boxes[heavy].items[index];
+                let Some((position, rotated_item)) = find_best_position_with_rotation(&boxes[light], item, &light_limits, options) else {
+                    continue;
+                };
+
+                let (saved_heavy, saved_light) = (boxes[heavy].clone(), boxes[light].clone());
+                boxes[heavy].remove_item(index);
+                boxes[light].add_item(rotated_item, position);
+
+                // Cardboard changes with the box sizes, so check the real outcome
+                if lower_score(score(boxes), current) {
+                    return true;
+                }
+                boxes[heavy] = saved_heavy;
+                boxes[light] = saved_light;
+            }
+        }
+    }
+
+    false
+}
+
+// Even out box weights within one destination without changing the number of boxes
+fn balance_box_weights(destination: &str, boxes: &mut [PackedBox], options: &PackingOptions, warnings: &mut Vec<String>) {
+    if boxes.len() < 2 {
+        return;
+    }
+
+    let before = weight_spread(boxes);
+    let item_count: usize = boxes.iter().map(|b| b.items.len()).sum();
+    let mut moves = 0;
+    // Every move strictly lowers the variance, the cap only bounds pathological inputs
+    while moves < item_count * boxes.len() && try_balancing_move(boxes, options, variance_score) {
+        moves += 1;
+    }
+
+    if moves > 0 {
+        warnings.push(format!(
+            "Weight balancing for {}: moved {} items, box weight spread {:.2} kg -> {:.2} kg",
+            destination,
+            moves,
+            before,
+            weight_spread(boxes)
+        ));
+    }
+}
+
+// Move items from heavier to lighter boxes of one destination until the spread between its heaviest
+// and lightest box is within the cap, without changing the number of boxes
+fn limit_weight_spread(destination: &str, boxes: &mut [PackedBox], cap: f64, options: &PackingOptions, warnings: &mut Vec<String>) {
+    if boxes.len() < 2 {
+        return;
+    }
+
+    let item_count: usize = boxes.iter().map(|b| b.items.len()).sum();
+    let mut moves = 0;
+    // Every move strictly lowers the score, the cap only bounds pathological inputs
+    while weight_spread(boxes) > cap && moves < item_count * boxes.len() && try_balancing_move(boxes, options, spread_score) {
+        moves += 1;
+    }
+
+    let spread = weight_spread(boxes);
+    if spread > cap {
+        warnings.push(format!(
+            "Weight spread for {}: {:.2} kg between the heaviest and lightest box, over the {:.2} kg cap with no moves left",
+            destination, spread, cap
+        ));
+    }
+}
+
+// Cheapest placement of the item across all existing boxes: the one that grows its box the least
+fn best_box_for_item(
+    boxes: &mut [PackedBox],
+    item: &Item,
+    destination: &DestinationConstraints,
+    options: &PackingOptions,
+    classes: &mut classes::ItemClasses,
+) -> Option<(usize, Position, Item)> {
+    let mut best: Option<(usize, Position, Item)> = None;
+    let mut best_score = (f64::MAX, f64::MAX);
+
+    for (index, box_data) in boxes.iter_mut().enumerate() {
+        if box_data.passed_over() || classes.fits_nowhere_in(classes::Attempt::Place, item, index, box_data) {
+            continue;
+        }
+        let limits = box_limits(box_data, destination, options);
+        let Some((position, rotated_item)) = find_best_position_with_rotation(box_data, item, &limits, options) else {
+            box_data.closed_reason = Some(closing::reason());
+            classes.record_no_fit(classes::Attempt::Place, item, index, box_data);
+            continue;
+        };
+        box_data.closed_reason = None;
+        let mut test_box = box_data.clone();
+        test_box.add_item(rotated_item.clone(), position);
+        let factor = order_factor(box_data, item, options) * pair_factor(box_data, item, options);
+        let score = (
+            factor * growth_score(box_data, &rotated_item, position, options),
+            factor * test_box.surface_area(),
+        );
+        if score.partial_cmp(&best_score) == Some(Ordering::Less) {
+            best_score = score;
+            best = Some((index, position, rotated_item));
+        }
+    }
+
+    best
+}
+
+const DEFAULT_ORDER_BONUS: f64 = 0.2;
+
+const DEFAULT_PAIR_BONUS: f64 = 0.5;
+
+const DEFAULT_ACCESS_BIAS: f64 = 0.5;
+
+// Whether the box already holds items of the item's order
+fn holds_order(box_data: &PackedBox, item: &Item) -> bool {
+    item.order_id
+        .as_ref()
+        .is_some_and(|order_id| box_data.items.iter().any(|other| other.order_id.as_ref() == Some(order_id)))
+}
+
+// Multiplier on a box's placement score: below 1 for a box already holding the item's order
+fn order_factor(box_data: &PackedBox, item: &Item, options: &PackingOptions) -> f64 {
+    if holds_order(box_data, item) {
+        1.0 - options.order_bonus.unwrap_or(DEFAULT_ORDER_BONUS).clamp(0.0, 1.0)
+    } else {
+        1.0
+    }
+}
+
+// Multiplier on a box's placement score: below 1 for a box already holding the item's parent
+fn pair_factor(box_data: &PackedBox, item: &Item, options: &PackingOptions) -> f64 {
+    if affinity::holds_parent(box_data, item) {
+        1.0 - options.pair_bonus.unwrap_or(DEFAULT_PAIR_BONUS).clamp(0.0, 1.0)
+    } else {
+        1.0
+    }
+}
+
+// Orders tried when re-packing a box from scratch
+const REPACK_ORDERS: [fn(&Item) -> f64; 4] = [
+    |item| item.volume(),
+    |item| item.length.max(item.width).max(item.height),
+    |item| {
+        let (a, b, c) = (item.length, item.width, item.height);
+        (a * b).max(a * c).max(b * c)
+    },
+    |item| item.weight,
+];
+
+// Re-pack a box's items together with one more item, trying several orders,
+// and return the new box if everything fits in one
+fn repack_box_with(
+    box_data: &PackedBox,
+    item: &Item,
+    destination: &DestinationConstraints,
+    options: &PackingOptions,
+) -> Option<PackedBox> {
+    let mut items = box_data.items.clone();
+    items.push(item.clone());
+    let limits = box_limits(box_data, destination, options);
+
+    REPACK_ORDERS.iter().find_map(|key| {
+        let mut ordered = items.clone();
+        ordered.sort_by(|a, b| key(b).partial_cmp(&key(a)).unwrap_or(Ordering::Equal));
+
+        let mut repacked = box_data.empty_like();
+        for next in &ordered {
+            let (position, rotated_item) = find_best_position_with_rotation(&repacked, next, &limits, options)?;
+            repacked.add_item(rotated_item, position);
+        }
+        Some(repacked)
+    })
+}
+
+// Sort the items' sides into length ≥ width ≥ height, only length ≥ width for items whose height
+// means something (kept upright, liquid or compressible), with a warning for those whose height
+// isn't their shortest side, as it may have been entered on the wrong axis. Items of an earlier
+// solution, oriented already, are left as they are.
+fn sort_dimensions(items: &mut [Item]) -> (Vec<DimensionSort>, Vec<String>) {
+    let mut sorts = Vec::new();
+    let mut warnings = Vec::new();
+    for item in items.iter_mut().filter(|item| item.original_dimensions.is_none()) {
+        let sides = [item.length, item.width, item.height];
+        let keeps_height = item.keep_upright || item.is_liquid || item.compressible_to_height.is_some();
+        let mut order = [0, 1, 2];
+        let sorted = if keeps_height { &mut order[..2] } else { &mut order[..] };
+        // Stable, so equal sides stay as entered
+        sorted.sort_by(|&a, &b| sides[b].partial_cmp(&sides[a]).unwrap_or(Ordering::Equal));
+
+        if keeps_height && item.height > item.length.min(item.width) {
+            let reason = if item.is_liquid {
+                "is a liquid"
+            } else if item.keep_upright {
+                "must stay upright"
+            } else {
+                "is compressible"
+            };
+            warnings.push(format!(
+                "Item {}: kept its entered height of {:.1} cm, longer than a side, because it {}; check the sides are in order",
+                item.id, item.height, reason
+            ));
+        }
+        if order == [0, 1, 2] {
+            continue;
+        }
+        item.length = sides[order[0]];
+        item.width = sides[order[1]];
+        item.height = sides[order[2]];
+        sorts.push(DimensionSort {
+            item_id: item.id.clone(),
+            rotation: ROTATIONS.iter().position(|rotation| *rotation == order).unwrap_or(0) as u8,
+        });
+    }
+    (sorts, warnings)
+}
+
+const DEFAULT_IRREGULAR_INFLATION_PCT: f64 = 10.0;
+
+// Grow irregular items by the safety margin for packing. The entered dimensions go into
+// `original_dimensions`, which rotations carry along, so `deflate_irregular` can restore them exactly.
+fn inflate_irregular(items: &mut [Item], options: &PackingOptions) -> f64 {
+    let factor = 1.0 + options.irregular_inflation_pct.unwrap_or(DEFAULT_IRREGULAR_INFLATION_PCT).max(0.0) / 100.0;
+    for item in items.iter_mut().filter(|item| item.irregular) {
+        item.original_dimensions = Some(item.unrotated_dimensions());
+        item.length *= factor;
+        item.width *= factor;
+        item.height *= factor;
+        item.compressible_to_height = item.compressible_to_height.map(|h| h * factor);
+    }
+    factor
+}
+
+// Report irregular items at their entered dimensions again; they keep the inflated slot's position
+fn deflate_irregular(item: &mut Item, factor: f64) {
+    if !item.irregular {
+        return;
+    }
+    let entered = item.unrotated_dimensions().to_array();
+    let permutation = ROTATIONS[item.rotation as usize % ROTATIONS.len()];
+    item.length = entered[permutation[0]];
+    item.width = entered[permutation[1]];
+    match item.nominal_height {
+        Some(_) => {
+            item.nominal_height = Some(entered[permutation[2]]);
+            item.height /= factor;
+        }
+        None => item.height = entered[permutation[2]],
+    }
+    item.compressible_to_height = item.compressible_to_height.map(|h| h / factor);
+}
+
+// Largest items first, the order the greedy pass packs in; fragile and unload-first items after
+// all the others, so nothing is put on them
+fn sort_by_volume(items: &mut [Item]) {
+    items.sort_by(|a, b| {
+        a.wants_access()
+            .cmp(&b.wants_access())
+            .then_with(|| b.volume().partial_cmp(&a.volume()).unwrap_or(Ordering::Equal))
+    });
+}
+
+// Boxes this close to a volume cap are pointed out, since a re-pack that grows them a little jumps the band
+const VOLUME_CAP_WARNING_RATIO: f64 = 0.95;
+
+fn warn_near_volume_cap(solution: &mut PackingSolution, options: &PackingOptions) {
+    for (index, box_data) in solution.boxes.iter().enumerate() {
+        let Some(max_volume) = box_constraints(box_data, options).max_box_volume else {
+            continue;
+        };
+        if box_data.volume() >= VOLUME_CAP_WARNING_RATIO * max_volume {
+            solution.warnings.push(format!(
+                "Box {} ({}): volume {:.0} cm³ is within 5% of the {:.0} cm³ cap",
+                index + 1,
+                box_data.destination,
+                box_data.volume(),
+                max_volume
+            ));
+        }
+    }
+}
+
+// List every box within the safety margin of its weight, dimension, girth or dimension sum limit;
+// the volume cap has its own warning
+fn warn_near_limits(solution: &mut PackingSolution, options: &PackingOptions) {
+    let margin = safety_margin(options);
+    if margin <= 0.0 {
+        return;
+    }
+
+    for (index, box_data) in solution.boxes.iter().enumerate() {
+        let constraints = box_constraints(box_data, options);
+        let (length, width, height) = (box_data.length, box_data.width, box_data.height);
+        let mut checks: Vec<(&str, f64, f64)> = vec![("max_box_weight", box_data.weight, constraints.max_box_weight)];
+        match constraints.alternative_dimensions {
+            Some(Dimensions { length: max_length, width: max_width, height: max_height }) => checks.extend([
+                ("box_length", length, max_length),
+                ("box_width", width, max_width),
+                ("box_height", height, max_height),
+            ]),
+            None => checks.push(("max_box_dimension", length.max(width).max(height), constraints.max_box_dimension)),
+        }
+        if let Some(limit) = constraints.max_length_plus_girth {
+            checks.push(("max_length_plus_girth", length_plus_girth(length, width, height), limit));
+        }
+        if let Some(limit) = constraints.max_dimension_sum {
+            checks.push(("max_dimension_sum", length + width + height, limit));
+        }
+
+        for (constraint, value, limit) in checks {
+            let headroom = limit - value;
+            if headroom >= limit * margin {
+                continue;
+            }
+            let unit = if constraint == "max_box_weight" { "kg" } else { "cm" };
+            solution.warnings.push(format!(
+                "Box {} ({}): {} is {:.1} {} against a limit of {:.1}, {:.2} {} of headroom inside the {:.0}% safety margin",
+                index + 1,
+                box_data.destination,
+                constraint.trim_start_matches("max_").replace('_', " "),
+                value,
+                unit,
+                limit,
+                headroom,
+                unit,
+                100.0 * margin
+            ));
+            solution.limit_warnings.push(LimitWarning {
+                box_index: index,
+                constraint: constraint.to_string(),
+                value,
+                limit,
+                headroom,
+            });
+        }
+    }
+}
+
+// Items that can't come out straight up in reverse packing order, box by box. The loading order
+// always takes items out from the top, so this is a guard on it.
+fn warn_trapped(solution: &mut PackingSolution, options: &PackingOptions) {
+    if !options.check_unpacking {
+        return;
+    }
+    for (index, box_data) in solution.boxes.iter().enumerate() {
+        let trapped: Vec<String> = verify::trapped_items(box_data)
+            .into_iter()
+            .map(|(trapped, above)| format!("{} under {}", box_data.items[trapped].id, box_data.items[above].id))
+            .collect();
+        if !trapped.is_empty() {
+            solution.warnings.push(format!(
+                "Box {} ({}): items can't be unpacked straight up in reverse order: {}",
+                index + 1,
+                box_data.destination,
+                trapped.join(", ")
+            ));
+        }
+    }
+}
+
+// Order the boxes by destination, heaviest first within one, and point every item at its box.
+// Returns the box indices of each destination.
+fn sort_boxes(boxes: &mut [PackedBox]) -> HashMap<String, Vec<usize>> {
+    boxes.sort_by(|a, b| a.destination.cmp(&b.destination).then_with(|| b.weight.total_cmp(&a.weight)));
+    index_boxes(boxes)
+}
+
+// Put every box's items in loading order and point each at its box and slot, returning the box
+// indices of each destination
+pub(crate) fn index_boxes(boxes: &mut [PackedBox]) -> HashMap<String, Vec<usize>> {
+    let mut by_destination: HashMap<String, Vec<usize>> = HashMap::new();
+    for (index, box_data) in boxes.iter_mut().enumerate() {
+        box_data.sort_loading_order();
+        for (slot, item) in box_data.items.iter_mut().enumerate() {
+            item.box_index = Some(index);
+            item.slot_index = Some(slot);
+        }
+        by_destination.entry(box_data.destination.clone()).or_default().push(index);
+    }
+    by_destination
+}
+
+// Whether the box is under the fill rate minimum and not the only box of its destination
+fn below_min_fill(solution: &PackingSolution, index: usize, minimum: f64) -> bool {
+    let box_data = &solution.boxes[index];
+    !box_data.weight_only &&
+        box_data.fill_rate() < minimum &&
+        solution.boxes.iter().filter(|b| !b.weight_only && b.destination == box_data.destination).count() > 1
+}
+
+// Give every box under the minimum fill rate, emptiest first, another try at moving its items into
+// the other boxes of its group
+fn redistribute_low_fill(solution: &mut PackingSolution, options: &PackingOptions) {
+    let Some(minimum) = options.min_fill_rate else {
+        return;
+    };
+
+    // Every success drops a box, so this ends
+    loop {
+        let mut low: Vec<usize> = (0..solution.boxes.len()).filter(|&index| below_min_fill(solution, index, minimum)).collect();
+        low.sort_by(|&a, &b| solution.boxes[a].fill_rate().total_cmp(&solution.boxes[b].fill_rate()));
+        let Some(emptied) = low.into_iter().find(|&index| search::empty_box(&mut solution.boxes, index, options, None)) else {
+            break;
+        };
+        debug_log::log(debug_log::INFO, || format!("low fill: moved the items of box {} into other boxes", emptied + 1));
+    }
+}
+
+// Flag the boxes still under the minimum fill rate, their warnings first
+fn flag_low_fill(solution: &mut PackingSolution, options: &PackingOptions) {
+    let Some(minimum) = options.min_fill_rate else {
+        return;
+    };
+
+    let mut warnings = Vec::new();
+    for index in 0..solution.boxes.len() {
+        if !below_min_fill(solution, index, minimum) {
+            continue;
+        }
+        let box_data = &mut solution.boxes[index];
+        box_data.low_fill = true;
+        let ids: Vec<&str> = box_data.items.iter().map(|item| item.id.as_str()).collect();
+        warnings.push(format!(
+            "LOW FILL: Box {} ({}) is {:.0}% full, below the {:.0}% minimum, and its items fit in no other box: {}",
+            index + 1,
+            box_data.destination,
+            100.0 * box_data.fill_rate(),
+            100.0 * minimum,
+            ids.join(", ")
+        ));
+    }
+    solution.warnings.splice(0..0, warnings);
+}
+
+// Exceptional boxes are pointed out so an approval is never used without anyone noticing
+// Phase two of PackingOptions.reserved_items: lay them into the room every box held back for
+// them, one on another from the top of its items. One that doesn't fit, which that room should
+// rule out, is left unpacked with a warning and fails try_pack_with_options.
+fn place_reserved_items(solution: &mut PackingSolution, options: &PackingOptions) {
+    for box_data in &mut solution.boxes {
+        if box_data.reservation.take().is_none() {
+            continue;
+        }
+        if box_data.weight_only {
+            for reserved in &options.reserved_items {
+                let item = Item {
+                    destination: box_data.destination.clone(),
+                    service: box_data.service.clone(),
+                    order_id: box_data.order_id().map(str::to_string),
+                    ..reserved.clone()
+                };
+                box_data.add_unplaced(item);
+            }
+            continue;
+        }
+        let clearance = box_data.clearance();
+        let floor = (box_data.length - clearance[0], box_data.width - clearance[1]);
+        let mut z = box_data
+            .items
+            .iter()
+            .filter_map(|item| item.position.map(|Position { z, .. }| z + item.height))
+            .fold(0.0, f64::max);
+        for reserved in &options.reserved_items {
+            let mut item = Item {
+                destination: box_data.destination.clone(),
+                service: box_data.service.clone(),
+                order_id: box_data.order_id().map(str::to_string),
+                ..lying_flat(reserved)
+            };
+            // Turned a quarter about the vertical axis when it only fits the floor that way
+            if item.length > floor.0 + CONTACT_EPSILON || item.width > floor.1 + CONTACT_EPSILON {
+                item = item.with_rotation(2);
+            }
+            let position = Position::new(0.0, 0.0, z);
+            let fits = BoundsMode::fixed(box_data).extent(box_data, &item, position).is_some() &&
+                !box_data.collides(&item, position) &&
+                options.hazmat_rules.allows(box_data, &item);
+            if !fits {
+                solution.warnings.push(format!(
+                    "Reserved item {} did not fit the room held back for it in a box for {}",
+                    item.id, box_data.destination
+                ));
+                solution.unpacked_details.push(UnpackedDetail {
+                    item_id: item.id.clone(),
+                    reason: UnpackedReason::NoReservedRoom,
+                    gaps: Vec::new(),
+                    best_rotation: None,
+                    accepted_by: Vec::new(),
+                    suggested_lanes: Vec::new(),
+                });
+                solution.unpacked_items.push(item);
+                continue;
+            }
+            z += item.height;
+            box_data.add_item(item, position);
+        }
+        // The room is no longer held back, so a reserved item left out leaves none behind
+        let material = box_data.material.clone();
+        box_data.recompute_from_items(&material);
+    }
+}
+
+fn warn_exceptional(solution: &mut PackingSolution) {
+    for (index, box_data) in solution.boxes.iter().enumerate().filter(|(_, b)| b.exceptional) {
+        let ids: Vec<&str> = box_data.items.iter().map(|item| item.id.as_str()).collect();
+        solution.warnings.push(format!(
+            "Box {} ({}): {:.1}×{:.1}×{:.1} cm, {:.2} kg, packed under a constraint exception for {}",
+            index + 1,
+            box_data.destination,
+            box_data.length,
+            box_data.width,
+            box_data.height,
+            box_data.weight,
+            ids.join(", ")
+        ));
+    }
+}
+
+// Exact shortfalls of the items rejected on size, weight or value, on the packing dimensions
+fn diagnose_unpacked(solution: &mut PackingSolution, options: &PackingOptions) {
+    for detail in &mut solution.unpacked_details {
+        if !matches!(
+            detail.reason,
+            UnpackedReason::ExceedsLimits | UnpackedReason::MustStayUpright | UnpackedReason::MustLieFlat
+        ) {
+            continue;
+        }
+        let Some(item) = solution.unpacked_items.iter().find(|item| item.id == detail.item_id) else {
+            continue;
+        };
+        (detail.gaps, detail.best_rotation) = diagnostics::constraint_gaps(item, options);
+        detail.accepted_by = diagnostics::accepting_destinations(item, options);
+        detail.suggested_lanes = diagnostics::suggest_lanes(item, options);
+    }
+}
+
+fn assess_stability(solution: &mut PackingSolution, options: &PackingOptions) {
+    for (index, box_data) in solution.boxes.iter_mut().enumerate() {
+        let stability = stability::assess(box_data);
+        if let Some(threshold) = options.stability_threshold.filter(|&threshold| stability.score < threshold) {
+            solution.warnings.push(format!(
+                "Box {} ({}): stability {:.2} is below {:.2}, add void fill or re-pack it",
+                index + 1,
+                box_data.destination,
+                stability.score,
+                threshold
+            ));
+        }
+        box_data.stability = Some(stability);
+    }
+}
+
+// Main packing algorithm implementation
+fn pack_items_impl(mut items: Vec<Item>, options: &PackingOptions) -> PackingSolution {
+    let started = platform::Instant::now();
+    if options.collect_stats {
+        stats::begin();
+    }
+    if options.explain {
+        explain::begin();
+    }
+    let level = options.debug_level.filter(|&level| level > 0);
+    if let Some(level) = level {
+        debug_log::begin(level);
+    }
+    debug_log::log(debug_log::INFO, || {
+        format!(
+            "packing {} items, {:?} strategy, {:?} objective, {:?} candidates",
+            items.len(),
+            options.strategy,
+            options.objective,
+            candidate_quality(options)
+        )
+    });
+    let (dimension_sorts, sort_warnings) = if options.sort_dimensions {
+        sort_dimensions(&mut items)
+    } else {
+        (Vec::new(), Vec::new())
+    };
+    let inflation = inflate_irregular(&mut items, options);
+    let pairing_warnings = affinity::reference_warnings(&items);
+    sort_by_volume(&mut items);
+    affinity::follow_parents(&mut items);
+    let greedy = pack_in_order(items.clone(), options);
+
+    let mut solution = match options.time_budget_ms {
+        Some(budget_ms) => search::improve_within_budget(greedy, items, options, budget_ms),
+        None => greedy,
+    };
+    redistribute_low_fill(&mut solution, options);
+    place_reserved_items(&mut solution, options);
+    solution.warnings.extend(sort_warnings);
+    solution.warnings.extend(pairing_warnings);
+    solution.dimension_sorts = dimension_sorts;
+    // Box numbers in the warnings below refer to this order
+    let boxes_by_destination = sort_boxes(&mut solution.boxes);
+    labels::assign_labels(&mut solution.boxes, options);
+    explain::locate(&mut solution.placements_debug, &solution.boxes);
+    flag_low_fill(&mut solution, options);
+
+    if let Some(increment) = options.dimension_rounding_cm {
+        round_box_dimensions(&mut solution, increment, options);
+    }
+
+    warn_near_volume_cap(&mut solution, options);
+    warn_near_limits(&mut solution, options);
+    warn_trapped(&mut solution, options);
+    warn_exceptional(&mut solution);
+    // Judged on the space the items were packed in, before irregular items shrink back
+    assess_stability(&mut solution, options);
+    diagnose_unpacked(&mut solution, options);
+
+    for item in solution.boxes.iter_mut().flat_map(|b| b.items.iter_mut()).chain(solution.unpacked_items.iter_mut()) {
+        deflate_irregular(item, inflation);
+    }
+
+    for box_data in &mut solution.boxes {
+        for item in &mut box_data.items {
+            let entered = Dimensions::new(item.length, item.width, item.nominal_height.unwrap_or(item.height));
+            item.original_dimensions.get_or_insert(entered);
+        }
+        box_data.categories = box_data.category_counts();
+        box_data.contents_summary = box_data.contents_by(&options.sku_key);
+        box_data.hazmat = hazmat::contents(&box_data.items);
+        box_data.value_cap = value_cap(box_data, options);
+        box_data.closed_reason.get_or_insert(closing::ClosedReason::InputExhausted);
+        // Costed on the final dimensions, after any rounding up
+        box_data.packaging_cost = box_data
+            .material
+            .price_per_sqm
+            .or(options.cardboard_price_per_sqm)
+            .map(|price| box_data.packaging_cost(price));
+    }
+
+    // Calculate total volume
+    solution.total_volume = solution.boxes.iter().map(PackedBox::volume).sum();
+    solution.packed_count = solution.boxes.iter().map(|b| b.items.len()).sum();
+    solution.box_usage = box_usage(&solution.boxes, options);
+    if !options.box_catalog.is_empty() {
+        solution.total_cost = Some(solution.boxes.iter().filter_map(|b| b.cost).sum());
+    }
+    solution.order_spans = order_spans(&solution.boxes);
+    solution.pairings = affinity::pairings(&solution.boxes, &solution.unpacked_items);
+    if options.separate_orders {
+        solution.order_splits = order_splits(&solution.boxes, &solution.unpacked_items);
+    }
+    solution.destination_summaries = destination_summaries(&solution.boxes);
+    (solution.packing_efficiency, solution.wasted_volume) = packing_efficiency(&solution.boxes);
+    solution.boxes_by_destination = boxes_by_destination;
+    if solution.boxes.iter().any(|b| b.packaging_cost.is_some()) {
+        solution.total_packaging_cost = Some(solution.boxes.iter().filter_map(|b| b.packaging_cost).sum());
+    }
+    if options.collect_stats {
+        solution.stats = stats::finish(started);
+    }
+    explain::finish();
+    if level.is_some() {
+        debug_log::log(debug_log::INFO, || {
+            format!("done: {} boxes, {} items unpacked", solution.boxes.len(), solution.unpacked_items.len())
+        });
+        solution.debug_log = debug_log::finish();
+    }
+
+    solution
+}
+
+// Catalog cartons that can take the item and are still in stock, smallest first
+fn box_spec_candidates(
+    item: &Item,
+    constraints: &DestinationConstraints,
+    options: &PackingOptions,
+    usage: &[u32],
+) -> Result<Vec<usize>, UnpackedReason> {
+    let mut fitting: Vec<usize> = (0..options.box_catalog.len())
+        .filter(|&index| {
+            let spec = &options.box_catalog[index];
+            if !within_dimension_limits(spec.length, spec.width, spec.height, constraints) {
+                return false;
+            }
+            let limits = constraints.intersect_spec(spec);
+            let reserved = reservation(options);
+            let tare = carton_weight(&options.material, spec.length, spec.width, spec.height) +
+                dunnage_weight(options.dunnage.as_ref(), (spec.length, spec.width, spec.height), item.volume()) +
+                reserved.map_or(0.0, |reserved| reserved.weight);
+            let clearance = clearance(&item.destination, item.service.as_deref(), options);
+            // The reserved layer has to fit the carton's floor, either way round
+            let (inside_length, inside_width) = (spec.length - clearance[0], spec.width - clearance[1]);
+            let takes_reserved = reserved.is_none_or(|reserved| {
+                (reserved.length <= inside_length && reserved.width <= inside_width) ||
+                (reserved.width <= inside_length && reserved.length <= inside_width)
+            });
+            takes_reserved &&
+            item.allowed_rotations()
+                .into_iter()
+                .any(|rot| fits_with_clearance(&item.with_rotation(rot), &limits, clearance)) &&
+            tare + item.weight <= limits.max_box_weight
+        })
+        .collect();
+    if fitting.is_empty() {
+        return Err(UnpackedReason::NoFittingBox);
+    }
+
+    fitting.sort_by(|&a, &b| {
+        options.box_catalog[a]
+            .volume()
+            .partial_cmp(&options.box_catalog[b].volume())
+            .unwrap_or(Ordering::Equal)
+    });
+    fitting.retain(|&index| options.box_catalog[index].available.is_none_or(|available| usage[index] < available));
+    if fitting.is_empty() {
+        return Err(UnpackedReason::OutOfBoxes);
+    }
+    Ok(fitting)
+}
+
+// Smallest catalog carton that can take the item and is still in stock
+fn choose_box_spec(
+    item: &Item,
+    constraints: &DestinationConstraints,
+    options: &PackingOptions,
+    usage: &[u32],
+) -> Result<usize, UnpackedReason> {
+    box_spec_candidates(item, constraints, options, usage).map(|candidates| candidates[0])
+}
+
+// Cartons of each catalog spec used by the boxes
+fn box_usage(boxes: &[PackedBox], options: &PackingOptions) -> Vec<BoxSpecUsage> {
+    options
+        .box_catalog
+        .iter()
+        .map(|spec| {
+            let used = boxes.iter().filter(|b| b.spec.as_deref() == Some(spec.name.as_str())).count() as u32;
+            BoxSpecUsage {
+                name: spec.name.clone(),
+                used,
+                available: spec.available,
+                remaining: spec.available.map(|available| available.saturating_sub(used)),
+            }
+        })
+        .collect()
+}
+
+// Boxes and leftovers of one destination while its items are being packed
+#[derive(Clone)]
+struct DestinationPacking {
+    destination: String,
+    service: Option<String>,
+    // The destination's limits with its carrier's, resolved once for all of its items
+    constraints: DestinationConstraints,
+    carrier: Option<String>,
+    boxes: Vec<PackedBox>,
+    unpacked: Vec<Item>,
+    unpacked_details: Vec<UnpackedDetail>,
+    // Cartons opened per catalog spec so far, across all destinations
+    spec_usage: Vec<u32>,
+    // Boxes opened under each of PackingOptions.exceptions so far, across all destinations
+    exception_usage: Vec<u32>,
+    // Why each item went where it did, with PackingOptions.explain
+    traces: Vec<explain::PlacementTrace>,
+    classes: classes::ItemClasses,
+}
+
+impl DestinationPacking {
+    fn reject(&mut self, item: Item, reason: UnpackedReason) {
+        debug_log::log(debug_log::DEBUG, || format!("unpacked: {:?}", reason));
+        self.unpacked_details.push(UnpackedDetail {
+            item_id: item.id.clone(),
+            reason,
+            gaps: Vec::new(),
+            best_rotation: None,
+            accepted_by: Vec::new(),
+            suggested_lanes: Vec::new(),
+        });
+        self.unpacked.push(item);
+    }
+
+    // Cost of the catalog cartons opened for this destination
+    fn cost(&self) -> f64 {
+        self.boxes.iter().filter_map(|b| b.cost).sum()
+    }
+
+    // With `lookahead`, each spec that could be opened is priced by packing the remaining items with it
+    fn pack_all(&mut self, mut items: VecDeque<Item>, options: &PackingOptions, lookahead: bool) {
+        self.classes.reset();
+        for box_data in &mut self.boxes {
+            box_data.closed = false;
+        }
+        // The lightest and smallest of the items from each one to the end
+        let mut floors = vec![(f64::INFINITY, f64::INFINITY); items.len() + 1];
+        for (index, item) in items.iter().enumerate().rev() {
+            let (lightest, smallest) = floors[index + 1];
+            floors[index] = (lightest.min(item.weight), smallest.min(item.least_volume()));
+        }
+        let mut floors = floors.into_iter().skip(1);
+        while let Some(item) = items.pop_front() {
+            self.pack_item(item, &items, options, lookahead);
+            if let Some(floor) = floors.next().filter(|_| !items.is_empty()) {
+                self.close_full_boxes(floor, options);
+            }
+        }
+    }
+
+    // Close the boxes no item still to come could go into. A trace lists every box tried, so
+    // nothing closes while one is taken, nor with close_full_boxes off.
+    fn close_full_boxes(&mut self, (lightest, smallest): (f64, f64), options: &PackingOptions) {
+        if explain::active() || options.close_full_boxes == Some(false) {
+            return;
+        }
+        for box_data in self.boxes.iter_mut().filter(|box_data| !box_data.closed) {
+            let limits = box_limits(box_data, &self.constraints, options);
+            if let Some(reason) = box_data.full_by(&limits, lightest, smallest) {
+                box_data.closed = true;
+                box_data.closed_reason = Some(reason);
+            }
+        }
+    }
+
+    fn pack_item(&mut self, item: Item, remaining: &VecDeque<Item>, options: &PackingOptions, lookahead: bool) {
+        let constraints = self.constraints.clone();
+        explain::begin_item();
+        let _span = debug_log::span(debug_log::DEBUG, || {
+            format!("item {} ({}×{}×{} cm, {} kg)", item.id, item.length, item.width, item.height, item.weight)
+        });
+        let logged_id = debug_log::enabled(debug_log::DEBUG).then(|| item.id.clone());
+
+        // No box of the destination may take it, exceptional ones included
+        if options.hazmat_rules.refuses(&item, &self.destination) {
+            self.reject(item, UnpackedReason::HazmatRestricted);
+            return;
+        }
+        // Not even an exceptional box may be insured for more
+        if options.insurance_cap_per_box.is_some_and(|cap| item.declared_value() > cap) {
+            self.reject(item, UnpackedReason::OverInsuranceCap);
+            return;
+        }
+
+        // Check if the item itself is too large for constraints (in any orientation)
+        let clearance = clearance(&self.destination, self.service.as_deref(), options);
+        let fits_at = |rotations: Vec<usize>| {
+            rotations
+                .into_iter()
+                .any(|rot| fits_with_clearance(&item.with_rotation(rot), &constraints, clearance))
+        };
+        if !fits_at(item.allowed_rotations()) {
+            let upright: Vec<usize> = (0..ROTATIONS.len()).filter(|&rot| item.keeps_upright(rot)).collect();
+            let reason = if !fits_at((0..ROTATIONS.len()).collect()) {
+                UnpackedReason::ExceedsLimits
+            } else if !fits_at(upright) {
+                UnpackedReason::MustStayUpright
+            } else {
+                UnpackedReason::MustLieFlat
+            };
+            if !self.pack_exceptional(&item, options) {
+                self.reject(item, reason);
+            }
+            return;
+        }
+
+        // A box of its own also weighs its cardboard and dunnage, and needs the reserved items' floor
+        // and weight (catalog cartons are checked at placement)
+        let own_box = own_box_size(&item, clearance, options);
+        let own_box_weight = item.weight +
+            reservation(options).map_or(0.0, |reserved| reserved.weight) +
+            carton_weight(&options.material, own_box.0, own_box.1, own_box.2) +
+            dunnage_weight(options.dunnage.as_ref(), own_box, item.volume());
+        let own_box_fits = within_dimension_limits(own_box.0, own_box.1, own_box.2, &constraints) &&
+            own_box_weight <= constraints.max_box_weight;
+        if options.box_catalog.is_empty() && !own_box_fits {
+            if !self.pack_exceptional(&item, options) {
+                self.reject(item, UnpackedReason::ExceedsLimits);
+            }
+            return;
+        }
+
+        // Index of the box the item went into, when it was added as its last item
+        let mut placed_in: Option<usize> = None;
+        let mut placed = false;
+        // For the trace: how it was decided, the box it went into and that box's surface before
+        let mut decision: Option<(explain::Decision, usize, f64)> = None;
+        let item_id = explain::active().then(|| item.id.clone());
+        let boxes = &mut self.boxes;
+        // A trace lists every box tried, so none are skipped for it
+        let mut no_classes = classes::ItemClasses::default();
+        let classes = if item_id.is_some() { &mut no_classes } else { &mut self.classes };
+
+        if options.objective == Objective::FewestBoxes {
+            // Look at every existing box, then try re-packing one, before opening another
+            if let Some((index, position, rotated_item)) = best_box_for_item(boxes, &item, &constraints, options, classes) {
+                decision = Some((explain::Decision::BestExistingBox, index, boxes[index].surface_area()));
+                boxes[index].add_item(rotated_item, position);
+                placed_in = Some(index);
+                placed = true;
+            } else if let Some((index, repacked)) = boxes.iter().enumerate().find_map(|(index, box_data)| {
+                if box_data.passed_over() || classes.fits_nowhere_in(classes::Attempt::Repack, &item, index, box_data) {
+                    return None;
+                }
+                let repacked = repack_box_with(box_data, &item, &constraints, options);
+                if repacked.is_none() {
+                    classes.record_no_fit(classes::Attempt::Repack, &item, index, box_data);
+                }
+                repacked.map(|repacked| (index, repacked))
+            }) {
+                decision = Some((explain::Decision::RepackedBox, index, boxes[index].surface_area()));
+                boxes[index] = repacked;
+                placed = true;
+            }
+        } else {
+            // Try to place in existing boxes, those already holding the item's parent or order first
+            let mut order: Vec<usize> = (0..boxes.len()).collect();
+            order.sort_by_key(|&index| {
+                let parent = options.pair_bonus != Some(0.0) && affinity::holds_parent(&boxes[index], &item);
+                let order = options.order_bonus != Some(0.0) && holds_order(&boxes[index], &item);
+                (!parent, !order)
+            });
+            for index in order {
+                let box_data = &mut boxes[index];
+                if box_data.passed_over() || classes.fits_nowhere_in(classes::Attempt::Place, &item, index, box_data) {
+                    continue;
+                }
+                let limits = box_limits(box_data, &constraints, options);
+                if let Some((position, rotated_item)) = find_best_position_with_rotation(box_data, &item, &limits, options) {
+                    decision = Some((explain::Decision::FirstExistingBox, index, box_data.surface_area()));
+                    box_data.add_item(rotated_item, position);
+                    placed_in = Some(index);
+                    placed = true;
+                    break;
+                }
+                box_data.closed_reason = Some(closing::reason());
+                classes.record_no_fit(classes::Attempt::Place, &item, index, box_data);
+            }
+        }
+
+        // Taken before a new box could move the others
+        let attempts = explain::take_attempts(&self.boxes);
+
+        // If not placed, create a new box
+        if !placed {
+            let mut new_box = if options.box_catalog.is_empty() {
+                PackedBox::cut(&self.destination, self.service.as_deref(), options)
+            } else {
+                let chosen = if lookahead {
+                    self.cheapest_box_spec(&item, remaining, &constraints, options)
+                } else {
+                    choose_box_spec(&item, &constraints, options, &self.spec_usage)
+                };
+                match chosen {
+                    Ok(index) => {
+                        self.spec_usage[index] += 1;
+                        PackedBox::from_spec(&self.destination, self.service.as_deref(), &options.box_catalog[index], options)
+                    }
+                    Err(reason) => {
+                        self.reject(item, reason);
+                        return;
+                    }
+                }
+            };
+            new_box.carrier = self.carrier.clone();
+            let new_limits = box_limits(&new_box, &constraints, options);
+
+            // For a new box, try all rotations and pick the one that fits constraints
+            let (position, rotated_item) = item
+                .allowed_rotations()
+                .into_iter()
+                .map(|rot| (rot, item.with_rotation(rot)))
+                .find(|(_, rotated)| {
+                    fits_with_clearance(rotated, &new_limits, new_box.clearance()) &&
+                    BoundsMode::for_box(&new_box, &new_limits).extent(&new_box, rotated, Position::ORIGIN).is_some()
+                })
+                .map(|(_, rotated)| (Position::ORIGIN, rotated))
+                .unwrap_or((Position::ORIGIN, item.clone()));
+
+            new_box.add_item(rotated_item, position);
+            self.boxes.push(new_box);
+            stats::record(|stats| stats.boxes_opened += 1);
+            placed_in = Some(self.boxes.len() - 1);
+            decision = Some((explain::Decision::NewBox, self.boxes.len() - 1, 0.0));
+        }
+
+        if let (Some(item_id), Some((decision, index, _))) = (&logged_id, decision) {
+            let placed = self.boxes[index].items.iter().rev().find(|placed| &placed.id == item_id);
+            debug_log::log(debug_log::DEBUG, || match placed {
+                Some(placed) => format!(
+                    "{:?}: box {} at {:?}, rotation {}",
+                    decision,
+                    index + 1,
+                    placed.position.unwrap_or_default(),
+                    placed.rotation
+                ),
+                None => format!("{:?}: box {}", decision, index + 1),
+            });
+        }
+
+        if let (Some(item_id), Some((decision, index, surface_before))) = (item_id, decision) {
+            self.record_trace(&item_id, decision, index, surface_before, attempts);
+        }
+
+        // Whatever turned an item away from the box before, it took this one
+        if let Some(index) = placed_in {
+            self.boxes[index].closed_reason = None;
+        }
+    }
+
+    // Open a box of its own for an item over the standard limits under the first exception of the
+    // destination that takes it and has boxes left; false if there is none
+    fn pack_exceptional(&mut self, item: &Item, options: &PackingOptions) -> bool {
+        let clearance = clearance(&self.destination, self.service.as_deref(), options);
+        let fitting = |limits: &DestinationConstraints| {
+            item.allowed_rotations()
+                .into_iter()
+                .map(|rot| item.with_rotation(rot))
+                .find(|rotated| {
+                    let own_box = own_box_size(rotated, clearance, options);
+                    let tare = carton_weight(&options.material, own_box.0, own_box.1, own_box.2) +
+                        dunnage_weight(options.dunnage.as_ref(), own_box, rotated.volume()) +
+                        reservation(options).map_or(0.0, |reserved| reserved.weight);
+                    fits_with_clearance(rotated, limits, clearance) &&
+                    within_dimension_limits(own_box.0, own_box.1, own_box.2, limits) &&
+                    rotated.weight + tare <= limits.max_box_weight
+                })
+        };
+        let chosen = options.exceptions.iter().enumerate().find_map(|(index, exception)| {
+            if !same_destination(&exception.destination, &self.destination) || self.exception_usage[index] >= exception.max_boxes {
+                return None;
+            }
+            fitting(&exception.limits).map(|rotated| (index, rotated))
+        });
+        let Some((index, rotated_item)) = chosen else {
+            return false;
+        };
+
+        self.exception_usage[index] += 1;
+        let mut new_box = PackedBox::cut(&self.destination, self.service.as_deref(), options);
+        new_box.carrier = self.carrier.clone();
+        new_box.exceptional = true;
+        new_box.override_limits = Some(options.exceptions[index].limits.clone());
+        new_box.add_item(rotated_item, Position::ORIGIN);
+        self.boxes.push(new_box);
+        stats::record(|stats| stats.boxes_opened += 1);
+        true
+    }
+
+    // Trace of the item just placed in the box, the last one with its id there
+    fn record_trace(
+        &mut self,
+        item_id: &str,
+        decision: explain::Decision,
+        index: usize,
+        surface_before: f64,
+        attempts: Vec<explain::BoxAttempt>,
+    ) {
+        let box_data = &self.boxes[index];
+        let Some(item) = box_data.items.iter().rev().find(|item| item.id == item_id) else {
+            return;
+        };
+        self.traces.push(explain::PlacementTrace {
+            item_id: item_id.to_string(),
+            destination: self.destination.clone(),
+            decision,
+            box_index: None,
+            slot_index: None,
+            position: item.position.unwrap_or_default(),
+            rotation: item.rotation,
+            candidate_positions: attempts
+                .iter()
+                .flat_map(|attempt| &attempt.rotations)
+                .map(|trial| trial.candidate_positions)
+                .sum(),
+            attempts,
+            surface_before,
+            surface_after: box_data.surface_area(),
+        });
+    }
+
+    // Spec to open for the item that leads to the lowest total cost for the destination,
+    // projected by packing the remaining items (without further lookahead) after opening it
+    fn cheapest_box_spec(
+        &self,
+        item: &Item,
+        remaining: &VecDeque<Item>,
+        constraints: &DestinationConstraints,
+        options: &PackingOptions,
+    ) -> Result<usize, UnpackedReason> {
+        let candidates = box_spec_candidates(item, constraints, options, &self.spec_usage)?;
+        let mut best: Option<(usize, [f64; 2])> = None;
+
+        for index in candidates {
+            let mut projected = self.clone();
+            projected.spec_usage[index] += 1;
+            let mut new_box = PackedBox::from_spec(&self.destination, self.service.as_deref(), &options.box_catalog[index], options);
+            new_box.carrier = self.carrier.clone();
+            projected.boxes.push(new_box);
+            let box_index = projected.boxes.len() - 1;
+            let limits = box_limits(&projected.boxes[box_index], constraints, options);
+            if let Some((position, rotated_item)) = find_best_position_with_rotation(&projected.boxes[box_index], item, &limits, options) {
+                projected.boxes[box_index].add_item(rotated_item, position);
+            }
+            projected.pack_all(remaining.clone(), options, false);
+
+            // Leaving items behind is never cheaper
+            let score = [projected.unpacked.len() as f64, projected.cost()];
+            if best.is_none_or(|(_, best_score)| score.partial_cmp(&best_score) == Some(Ordering::Less)) {
+                best = Some((index, score));
+            }
+        }
+
+        best.map(|(index, _)| index).ok_or(UnpackedReason::OutOfBoxes)
+    }
+}
+
+// Items split by order, keeping their order within each group; items without an order form one group
+fn group_by_order(items: Vec<Item>) -> Vec<Vec<Item>> {
+    let mut groups: Vec<(Option<String>, Vec<Item>)> = Vec::new();
+    for item in items {
+        match groups.iter_mut().find(|(order_id, _)| *order_id == item.order_id) {
+            Some((_, group)) => group.push(item),
+            None => groups.push((item.order_id.clone(), vec![item])),
+        }
+    }
+    groups.into_iter().map(|(_, group)| group).collect()
+}
+
+// Pack items per destination in the order given, without the final rounding
+fn pack_in_order(items: Vec<Item>, options: &PackingOptions) -> PackingSolution {
+    let mut solution = PackingSolution {
+        boxes: Vec::new(),
+        total_volume: 0.0,
+        unpacked_items: Vec::new(),
+        warnings: Vec::new(),
+        search: None,
+        unpacked_details: Vec::new(),
+        box_usage: Vec::new(),
+        total_cost: None,
+        packed_count: 0,
+        stats: None,
+        total_packaging_cost: None,
+        order_spans: Vec::new(),
+        order_splits: Vec::new(),
+        destination_summaries: Vec::new(),
+        packing_efficiency: 0.0,
+        wasted_volume: 0.0,
+        limit_warnings: Vec::new(),
+        pairings: Vec::new(),
+        boxes_by_destination: HashMap::new(),
+        placements_debug: Vec::new(),
+        debug_log: Vec::new(),
+        dimension_sorts: Vec::new(),
+        schema_version: SOLUTION_SCHEMA_VERSION,
+    };
+    // Cartons opened per catalog spec, shared by all destinations since they draw on the same stock
+    let mut spec_usage = vec![0u32; options.box_catalog.len()];
+    let mut exception_usage = vec![0u32; options.exceptions.len()];
+
+    // Group items by canonical destination so "usa" and "USA" share boxes, and by service, as
+    // items of two services ship as different products
+    let mut items_by_destination: HashMap<(String, Option<String>), Vec<Item>> = HashMap::new();
+    // Unknown destinations are grouped by normalized spelling, keeping the first spelling seen,
+    // and so are services
+    let mut unknown_destinations: HashMap<String, String> = HashMap::new();
+    let mut service_spellings: HashMap<String, String> = HashMap::new();
+
+    for mut item in items {
+        match resolve_destination(&item.destination, options) {
+            Some(canonical) => item.destination = canonical,
+            None => {
+                let spelling = unknown_destinations
+                    .entry(normalize_destination(&item.destination))
+                    .or_insert_with(|| item.destination.trim().to_string());
+                item.destination = spelling.clone();
+            }
+        }
+        item.service = item.service.as_ref().map(|service| {
+            service_spellings
+                .entry(normalize_destination(service))
+                .or_insert_with(|| service.trim().to_string())
+                .clone()
+        });
+        items_by_destination
+            .entry((item.destination.clone(), item.service.clone()))
+            .or_default()
+            .push(item);
+    }
+
+    let destination_count = items_by_destination.keys().map(|(destination, _)| destination).collect::<HashSet<_>>().len();
+    stats::record(|stats| stats.destination_count = destination_count);
+    let mut unknown_names: Vec<&String> = unknown_destinations.values().collect();
+    unknown_names.sort();
+    for name in unknown_names {
+        solution.warnings.push(format!(
+            "Unknown destination \"{}\" ({} items), packed with the default constraints",
+            name,
+            items_by_destination
+                .iter()
+                .filter(|((destination, _), _)| destination == name)
+                .map(|(_, items)| items.len())
+                .sum::<usize>()
+        ));
+    }
+
+    // Process each destination and service separately, in a fixed order so limited stock is shared
+    // out the same way every run
+    let mut destinations: Vec<_> = items_by_destination.into_iter().collect();
+    destinations.sort_by(|a, b| a.0.cmp(&b.0));
+    let destination_count = destinations.len();
+    for (destination_index, ((destination, service), mut destination_items)) in destinations.into_iter().enumerate() {
+        let started = platform::Instant::now();
+        let class_of = classes::canonicalize(&mut destination_items);
+        let item_classes = match options.share_class_search {
+            Some(false) => classes::ItemClasses::default(),
+            _ => classes::ItemClasses::new(class_of),
+        };
+        // Strategy::Auto packs the destination with the heuristic its item count calls for
+        let heuristic = auto::heuristic(destination_items.len(), options);
+        let tuned = heuristic.map(|heuristic| auto::tuned_options(heuristic, options));
+        let options = tuned.as_ref().unwrap_or(options);
+        let constraints = effective_constraints(&destination, service.as_deref(), options);
+        let carrier_name = carrier_for_destination(&destination, options);
+        if let Some(name) = service.as_deref().filter(|&name| service_constraints(&destination, name, options).is_none()) {
+            solution.warnings.push(format!(
+                "Unknown service \"{}\" for {}, packed with the destination's parcel limits",
+                name, destination
+            ));
+        }
+        let _span = debug_log::span(debug_log::INFO, || {
+            format!(
+                "{}{}: {} items, carrier {}, limits {:?} cm, {} kg",
+                destination,
+                service.as_ref().map_or(String::new(), |service| format!(" ({})", service)),
+                destination_items.len(),
+                carrier_name.unwrap_or("none"),
+                constraints.axis_limits(),
+                constraints.max_box_weight
+            ) + &heuristic.map_or(String::new(), |heuristic| format!(", auto: {:?}", heuristic))
+        });
+        let carrier = carrier_name.and_then(|name| find_carrier(name, options));
+        if let (Some(name), None) = (carrier_name, &carrier) {
+            let warning = format!("Unknown carrier \"{}\" for {}, packed against destination limits only", name, destination);
+            // Once per destination, not per service
+            if !solution.warnings.contains(&warning) {
+                solution.warnings.push(warning);
+            }
+        }
+
+        // With separate orders each order is packed on its own, so its boxes hold nothing else
+        let groups = if options.separate_orders {
+            group_by_order(destination_items)
+        } else {
+            vec![destination_items]
+        };
+        for group_items in groups {
+            let _order = options.separate_orders.then(|| {
+                debug_log::span(debug_log::INFO, || match group_items.first().and_then(|item| item.order_id.as_ref()) {
+                    Some(order) => format!("order {}: {} items", order, group_items.len()),
+                    None => format!("{} items without an order", group_items.len()),
+                })
+            });
+            let mut packing = DestinationPacking {
+                destination: destination.clone(),
+                service: service.clone(),
+                constraints: constraints.clone(),
+                carrier: carrier.as_ref().map(|c| c.name.clone()),
+                boxes: Vec::new(),
+                unpacked: Vec::new(),
+                unpacked_details: Vec::new(),
+                spec_usage: std::mem::take(&mut spec_usage),
+                exception_usage: std::mem::take(&mut exception_usage),
+                traces: Vec::new(),
+                classes: item_classes.clone(),
+            };
+
+            // Loose items are parcels of their own, and items whose sizes can't be trusted go by
+            // weight alone into boxes of their own, both kept out of the others' way until those
+            // are packed and balanced
+            let (loose_items, group_items): (Vec<Item>, Vec<Item>) = group_items.into_iter().partition(|item| item.ship_loose);
+            let mut own_boxes = Vec::new();
+            for (item, reason) in loose::pack_loose(loose_items, &constraints, options, &mut own_boxes) {
+                packing.reject(item, reason);
+            }
+            let margined = margined_constraints(&constraints, options);
+            let (weight_items, group_items): (Vec<Item>, Vec<Item>) =
+                group_items.into_iter().partition(|item| item.dimensions_unreliable);
+            for (item, reason) in weights::pack_by_weight(weight_items, &constraints, &margined, options, &mut own_boxes) {
+                packing.reject(item, reason);
+            }
+
+            // Grids, layers and walls don't check the hazmat rules, so hazmat items go through the
+            // heuristic, which does
+            let (hazmat_items, group_items): (Vec<Item>, Vec<Item>) =
+                group_items.into_iter().partition(|item| item.hazmat_class.is_some());
+
+            // Large runs of identical items fill boxes grid-first, the heuristic packs the rest
+            let group_items = grid::pack_grids(
+                group_items,
+                &margined,
+                options,
+                &mut packing.boxes,
+            );
+            let group_items = match options.strategy {
+                Strategy::Layers => layers::pack_layers(
+                    group_items,
+                    &margined,
+                    options,
+                    &mut packing.boxes,
+                ),
+                Strategy::Walls => walls::pack_walls(
+                    group_items,
+                    &margined,
+                    options,
+                    &mut packing.boxes,
+                ),
+                Strategy::ExtremePoints | Strategy::FreeSpaces | Strategy::Auto => group_items,
+            };
+            // Back in packing order; the sort is stable and the items were sorted before
+            let mut group_items = group_items;
+            group_items.extend(hazmat_items);
+            sort_by_volume(&mut group_items);
+            if !packing.boxes.is_empty() {
+                debug_log::log(debug_log::INFO, || {
+                    let placed: usize = packing.boxes.iter().map(|b| b.items.len()).sum();
+                    format!("{} boxes filled as grids, layers or walls with {} items, {} items left", packing.boxes.len(), placed, group_items.len())
+                });
+            }
+            for box_data in &mut packing.boxes {
+                box_data.carrier = packing.carrier.clone();
+            }
+
+            packing.pack_all(group_items.into(), options, options.objective == Objective::LowestCost);
+            if let Some(heuristic) = heuristic {
+                auto::improve(heuristic, &mut packing.boxes, &packing.constraints, options);
+            }
+
+            if options.balance_weight {
+                balance_box_weights(&destination, &mut packing.boxes, options, &mut solution.warnings);
+            }
+            if let Some(cap) = constraints.max_weight_spread_kg {
+                limit_weight_spread(&destination, &mut packing.boxes, cap, options, &mut solution.warnings);
+            }
+            for box_data in &mut own_boxes {
+                box_data.carrier = packing.carrier.clone();
+            }
+            packing.boxes.extend(own_boxes);
+
+            // Add to solution
+            spec_usage = packing.spec_usage;
+            exception_usage = packing.exception_usage;
+            solution.boxes.extend(packing.boxes);
+            solution.unpacked_items.extend(packing.unpacked);
+            solution.unpacked_details.extend(packing.unpacked_details);
+            solution.placements_debug.extend(packing.traces);
+        }
+        stats::record_destination(&destination, heuristic, started);
+        queue::report_progress(destination_index + 1, destination_count);
+    }
+
+    solution.total_volume = solution.boxes.iter().map(PackedBox::volume).sum();
+
+    solution
+}
+
+// Check a request before packing: every item packable on its own, ids used consistently and,
+// with strict_destinations, every destination known
+fn validate_items(items: &[Item], options: &PackingOptions) -> Result<(), PackError> {
+    let invalid = |item: &Item, field: &str, reason: &str| PackError::InvalidItem {
+        id: item.id.clone(),
+        field: field.to_string(),
+        reason: reason.to_string(),
+    };
+    let non_negative = |value: f64| value.is_finite() && value >= 0.0;
+    let mut first_with_id: HashMap<&str, &Item> = HashMap::new();
+    let length_factor = options.units.length.cm_factor();
+    let weight_factor = options.units.weight.kg_factor();
+    let max_dimension = options.max_item_dimension_cm.unwrap_or(DEFAULT_MAX_ITEM_DIMENSION_CM);
+    let max_weight = options.max_item_weight_kg.unwrap_or(DEFAULT_MAX_ITEM_WEIGHT_KG);
+    let too_long = format!("is over {} cm; raise max_item_dimension_cm if that is right", max_dimension);
+
+    // An empty solution would read as "everything packed"
+    if items.is_empty() {
+        return Err(PackError::EmptyInput);
+    }
+
+    for item in items {
+        if item.ship_loose && item.dimensions_unreliable {
+            return Err(invalid(item, "ship_loose", "needs reliable dimensions, the parcel is sized by them"));
+        }
+        for (field, size) in [("length", item.length), ("width", item.width), ("height", item.height)] {
+            // Sizes known to be wrong are only carried through
+            if item.dimensions_unreliable {
+                if !non_negative(size) {
+                    return Err(invalid(item, field, "must be zero or more"));
+                }
+                continue;
+            }
+            if !(size.is_finite() && size > 0.0) {
+                return Err(invalid(item, field, "must be a positive number"));
+            }
+            if size * length_factor > max_dimension {
+                return Err(invalid(item, field, &too_long));
+            }
+        }
+        if !non_negative(item.weight) {
+            return Err(invalid(item, "weight", "must be zero or more"));
+        }
+        if item.weight * weight_factor > max_weight {
+            let reason = format!("is over {} kg; raise max_item_weight_kg if that is right", max_weight);
+            return Err(invalid(item, "weight", &reason));
+        }
+        if item.value.is_some_and(|value| !non_negative(value)) {
+            return Err(invalid(item, "value", "must be zero or more"));
+        }
+        if item.max_height_above_cm.is_some_and(|limit| !non_negative(limit)) {
+            return Err(invalid(item, "max_height_above_cm", "must be zero or more"));
+        }
+        if item.max_load_kg.is_some_and(|limit| !non_negative(limit)) {
+            return Err(invalid(item, "max_load_kg", "must be zero or more"));
+        }
+        if item.hazmat_quantity.is_some_and(|quantity| !non_negative(quantity)) {
+            return Err(invalid(item, "hazmat_quantity", "must be zero or more"));
+        }
+        if item
+            .compressible_to_height
+            .is_some_and(|height| !(height.is_finite() && height > 0.0 && height <= item.height))
+        {
+            return Err(invalid(item, "compressible_to_height", "must be positive and at most the height"));
+        }
+        if normalize_destination(&item.destination).is_empty() {
+            return Err(invalid(item, "destination", "must not be empty"));
+        }
+        if options.strict_destinations && resolve_destination(&item.destination, options).is_none() {
+            return Err(PackError::UnknownDestination {
+                name: item.destination.clone(),
+            });
+        }
+
+        // Repeated ids are quantities of one item, so they have to agree
+        let first = *first_with_id.entry(&item.id).or_insert(item);
+        let same_item = (first.length, first.width, first.height) == (item.length, item.width, item.height) &&
+            first.weight == item.weight &&
+            first.pair_with == item.pair_with &&
+            same_service(first.service.as_deref(), item.service.as_deref()) &&
+            same_destination(&first.destination, &item.destination);
+        if !same_item {
+            return Err(PackError::DuplicateId { id: item.id.clone() });
+        }
+    }
+
+    let limit = options.max_destinations.unwrap_or(DEFAULT_MAX_DESTINATIONS);
+    if !options.allow_many_destinations {
+        let counts = destination_counts(items, options);
+        if counts.len() > limit {
+            let count = counts.len();
+            let mut destinations: Vec<(bool, error::DestinationCount)> = counts.into_iter().collect();
+            destinations.sort_by(|a, b| {
+                a.0.cmp(&b.0)
+                    .then(a.1.items.cmp(&b.1.items))
+                    .then_with(|| a.1.destination.cmp(&b.1.destination))
+            });
+            return Err(PackError::TooManyDestinations {
+                count,
+                limit,
+                destinations: destinations.into_iter().take(MAX_LISTED_DESTINATIONS).map(|(_, count)| count).collect(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+const DEFAULT_MAX_DESTINATIONS: usize = 50;
+
+// Destinations named in TooManyDestinations
+const MAX_LISTED_DESTINATIONS: usize = 10;
+
+// Items per destination grouped as the packer groups them, each with whether it is a known destination
+fn destination_counts(items: &[Item], options: &PackingOptions) -> Vec<(bool, error::DestinationCount)> {
+    let mut counts: Vec<(bool, error::DestinationCount)> = Vec::new();
+    let mut index_of: HashMap<String, usize> = HashMap::new();
+    for item in items {
+        let resolved = resolve_destination(&item.destination, options);
+        let key = resolved.clone().unwrap_or_else(|| normalize_destination(&item.destination));
+        let index = *index_of.entry(key).or_insert_with(|| {
+            let destination = resolved.clone().unwrap_or_else(|| item.destination.trim().to_string());
+            counts.push((resolved.is_some(), error::DestinationCount { destination, items: 0 }));
+            counts.len() - 1
+        });
+        counts[index].1.items += 1;
+    }
+    counts
+}
+
+// Default plausible size and weight of an item
+const DEFAULT_MAX_ITEM_DIMENSION_CM: f64 = 500.0;
+const DEFAULT_MAX_ITEM_WEIGHT_KG: f64 = 1000.0;
+// Highest either limit may be raised to: every item then measures and weighs a few orders of
+// magnitude below what a box's volume, cardboard area or weight total could overflow to inf at
+const MAX_INPUT_LIMIT: f64 = 1e6;
+
+// Why a set of box limits can't be packed against, if it can't: sizes and weights above 0 and at
+// most MAX_INPUT_LIMIT (the volume its cube), the spread and headspace zero or more and the value
+// cap any amount of zero or more
+fn check_limits(limits: &DestinationConstraints) -> Result<(), String> {
+    let alternative = limits.alternative_dimensions;
+    let positive = [
+        ("max_box_dimension", Some(limits.max_box_dimension), MAX_INPUT_LIMIT),
+        ("max_box_weight", Some(limits.max_box_weight), MAX_INPUT_LIMIT),
+        ("alternative_dimensions.length", alternative.map(|d| d.length), MAX_INPUT_LIMIT),
+        ("alternative_dimensions.width", alternative.map(|d| d.width), MAX_INPUT_LIMIT),
+        ("alternative_dimensions.height", alternative.map(|d| d.height), MAX_INPUT_LIMIT),
+        ("max_length_plus_girth", limits.max_length_plus_girth, MAX_INPUT_LIMIT),
+        ("max_dimension_sum", limits.max_dimension_sum, MAX_INPUT_LIMIT),
+        ("max_box_volume", limits.max_box_volume, MAX_INPUT_LIMIT.powi(3)),
+    ];
+    for (name, value, max) in positive {
+        if value.is_some_and(|value| !(value > 0.0 && value <= max)) {
+            return Err(format!("{} must be above 0 and at most {}", name, max));
+        }
+    }
+    for (name, value) in [("max_weight_spread_kg", limits.max_weight_spread_kg), ("headspace_cm", limits.headspace_cm)] {
+        if value.is_some_and(|value| !(0.0..=MAX_INPUT_LIMIT).contains(&value)) {
+            return Err(format!("{} must be zero or more and at most {}", name, MAX_INPUT_LIMIT));
+        }
+    }
+    if limits.max_box_value.is_some_and(|cap| !(cap.is_finite() && cap >= 0.0)) {
+        return Err("max_box_value must be zero or more".to_string());
+    }
+    Ok(())
+}
+
+// Check the options that can't be used as given
+fn validate_options(options: &PackingOptions) -> Result<(), PackError> {
+    // Lengths, percentages, biases and prices of zero or more
+    let non_negative = [
+        ("flap_clearance_cm", options.flap_clearance_cm),
+        ("headspace_cm", options.headspace_cm),
+        ("layer_tolerance_cm", options.layer_tolerance_cm),
+        ("allow_overhang_cm", options.allow_overhang_cm),
+        ("irregular_inflation_pct", options.irregular_inflation_pct),
+        ("cube_bias", options.cube_bias),
+        ("access_bias", options.access_bias),
+        ("cardboard_price_per_sqm", options.cardboard_price_per_sqm),
+    ];
+    for (field, value) in non_negative {
+        if value.is_some_and(|value| !(0.0..=MAX_INPUT_LIMIT).contains(&value)) {
+            return Err(PackError::InvalidOption {
+                field: field.to_string(),
+                reason: format!("must be zero or more and at most {}", MAX_INPUT_LIMIT),
+            });
+        }
+    }
+    // All of a limit held back would leave no box any room
+    if options.safety_margin_pct.is_some_and(|margin| !(0.0..100.0).contains(&margin)) {
+        return Err(PackError::InvalidOption {
+            field: "safety_margin_pct".to_string(),
+            reason: "must be at least 0 and below 100".to_string(),
+        });
+    }
+    let shares = [
+        ("min_fill_rate", options.min_fill_rate),
+        ("stability_threshold", options.stability_threshold),
+        ("order_bonus", options.order_bonus),
+        ("pair_bonus", options.pair_bonus),
+    ];
+    for (field, value) in shares {
+        if value.is_some_and(|value| !(0.0..=1.0).contains(&value)) {
+            return Err(PackError::InvalidOption {
+                field: field.to_string(),
+                reason: "must be between 0 and 1".to_string(),
+            });
+        }
+    }
+    if options.insurance_cap_per_box.is_some_and(|cap| !(cap.is_finite() && cap >= 0.0)) {
+        return Err(PackError::InvalidOption {
+            field: "insurance_cap_per_box".to_string(),
+            reason: "must be zero or more".to_string(),
+        });
+    }
+    let limits = [
+        ("max_item_dimension_cm", options.max_item_dimension_cm),
+        ("max_item_weight_kg", options.max_item_weight_kg),
+        ("dimension_rounding_cm", options.dimension_rounding_cm),
+    ];
+    for (field, limit) in limits {
+        if limit.is_some_and(|limit| !(limit > 0.0 && limit <= MAX_INPUT_LIMIT)) {
+            return Err(PackError::InvalidOption {
+                field: field.to_string(),
+                reason: format!("must be positive and at most {}", MAX_INPUT_LIMIT),
+            });
+        }
+    }
+    for item in &options.reserved_items {
+        let sides = [item.length, item.width, item.height];
+        let measures_ok = sides.iter().all(|&side| side > 0.0 && side <= MAX_INPUT_LIMIT) &&
+            (0.0..=MAX_INPUT_LIMIT).contains(&item.weight) &&
+            item.value.is_none_or(|value| value.is_finite() && value >= 0.0);
+        if !measures_ok {
+            return Err(PackError::InvalidOption {
+                field: "reserved_items".to_string(),
+                reason: format!("item \"{}\" needs sides above 0 and a weight and value of zero or more", item.id),
+            });
+        }
+    }
+    for spec in &options.box_catalog {
+        let sides = [spec.length, spec.width, spec.height];
+        if !sides.iter().all(|&side| side > 0.0 && side <= MAX_INPUT_LIMIT) {
+            return Err(PackError::InvalidOption {
+                field: "box_catalog".to_string(),
+                reason: format!("carton \"{}\" needs sides above 0 and at most {} cm", spec.name, MAX_INPUT_LIMIT),
+            });
+        }
+    }
+    let mut destinations: Vec<_> = options.custom_destinations.iter().collect();
+    destinations.sort_by(|a, b| a.0.cmp(b.0));
+    let lanes = destinations
+        .into_iter()
+        .map(|(destination, limits)| ("custom_destinations", format!("destination \"{}\"", destination), limits.clone()))
+        .chain(options.exceptions.iter().map(|exception| {
+            ("exceptions", format!("exception for \"{}\"", exception.destination), exception.limits.clone())
+        }))
+        .chain(options.services.iter().map(|service| {
+            ("services", format!("service \"{}\" to \"{}\"", service.service, service.destination), service.limits.clone())
+        }))
+        .chain(options.carriers.iter().map(|carrier| {
+            let limits = DestinationConstraints {
+                max_box_dimension: carrier.max_box_dimension,
+                max_box_weight: carrier.max_box_weight,
+                alternative_dimensions: carrier.alternative_dimensions,
+                max_length_plus_girth: carrier.max_length_plus_girth,
+                ..Default::default()
+            };
+            ("carriers", format!("carrier \"{}\"", carrier.name), limits)
+        }));
+    for (field, owner, limits) in lanes {
+        check_limits(&limits).map_err(|reason| PackError::InvalidOption {
+            field: field.to_string(),
+            reason: format!("{}: {}", owner, reason),
+        })?;
+    }
+    // A NaN weight would make every score compare false
+    if let Some(weights) = &options.score_weights {
+        score::check_weights(weights)?;
+    }
+    if let Some(weights) = &options.placement_weights {
+        placement::check_weights(weights)?;
+    }
+    if let Some(template) = &options.label_template {
+        labels::check_template(template, options).map_err(|reason| PackError::InvalidOption {
+            field: "label_template".to_string(),
+            reason,
+        })?;
+    }
+    Ok(())
+}
+
+// The checks of a request: the options, then the items against them
+pub(crate) fn validate_request(items: &[Item], options: &PackingOptions) -> Result<(), PackError> {
+    validate_options(options)?;
+    validate_items(items, options)
+}
+
+// Validate the request, then pack it
+pub fn try_pack_with_options(items: Vec<Item>, options: &PackingOptions) -> Result<PackingSolution, PackError> {
+    validate_request(&items, options)?;
+    pack_catching_panics(items, options).and_then(reserved_items_placed)
+}
+
+// The room for the reserved items was promised to every box at the start
+pub(crate) fn reserved_items_placed(solution: PackingSolution) -> Result<PackingSolution, PackError> {
+    if let Some(detail) = solution.unpacked_details.iter().find(|detail| detail.reason == UnpackedReason::NoReservedRoom) {
+        return Err(PackError::ReservedItemUnplaced {
+            id: detail.item_id.clone(),
+        });
+    }
+    Ok(solution)
+}
+
+// A panic in the packer, or with verification on a solution failing the audit, comes back as an
+// internal error rather than taking the command down or reaching the UI broken
+fn pack_catching_panics(items: Vec<Item>, options: &PackingOptions) -> Result<PackingSolution, PackError> {
+    let audit = options.verify.unwrap_or(cfg!(debug_assertions));
+    let (solution, violations) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| pack_and_audit(items, options, audit)))
+        .map_err(|panic| {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "packing failed".to_string());
+            PackError::InternalError { message }
+        })?;
+    if !violations.is_empty() {
+        let details: Vec<String> = violations.into_iter().map(|violation| violation.message).collect();
+        return Err(PackError::InternalError {
+            message: format!("Solution failed verification (cm, kg): {}", details.join("; ")),
+        });
+    }
+    Ok(solution)
+}
+
+// Decimal places for serialized lengths unless the caller overrides it
+const DEFAULT_DIMENSION_PRECISION: u32 = 2;
+
+// Convert a finished metric solution into the caller's units and round it for serializing
+fn to_caller(solution: &mut PackingSolution, options: &PackingOptions) {
+    units::solution_from_metric(solution, options.units);
+    let precision = options
+        .weight_precision
+        .unwrap_or_else(|| options.units.weight.default_precision());
+    units::round_weights(solution, precision);
+    units::round_dimensions(solution, options.dimension_precision.unwrap_or(DEFAULT_DIMENSION_PRECISION));
+}
+
+// Pack items given in the caller's units and return the solution in those units, checked as
+// try_pack_with_options checks them but with a panic or a bad solution left to the caller
+pub fn pack_with_options(items: Vec<Item>, options: &PackingOptions) -> Result<PackingSolution, PackError> {
+    validate_request(&items, options)?;
+    Ok(pack_unchecked(items, options))
+}
+
+// Pack a request already checked, e.g. once more with another strategy
+pub(crate) fn pack_unchecked(items: Vec<Item>, options: &PackingOptions) -> PackingSolution {
+    pack_and_audit(items, options, false).0
+}
+
+// The solution, plus what the audit found in it while still in metric when `audit` is set
+fn pack_and_audit(mut items: Vec<Item>, options: &PackingOptions, audit: bool) -> (PackingSolution, Vec<verify::Violation>) {
+    // Pack in metric and convert only at the boundary, so rounding can't flip a fit decision
+    units::items_to_metric(&mut items, options.units);
+    let mut solution = pack_items_impl(items, options);
+    let violations = if audit { verify::validate_solution(&solution, options) } else { Vec::new() };
+    to_caller(&mut solution, options);
+    (solution, violations)
+}
+
+// Put an item by hand into a built box of the solution at the position, the item sized as it is
+// to stand there; everything in the caller's units. The box keeps its size, so the item has to fit
+// inside it as well as pass the collision, support and weight checks.
+pub fn place_item(
+    mut solution: PackingSolution,
+    box_index: usize,
+    mut item: Item,
+    position: Position,
+    options: &PackingOptions,
+) -> Result<PackingSolution, PackError> {
+    validate_request(std::slice::from_ref(&item), options)?;
+    units::solution_to_metric(&mut solution, options.units);
+    item.position = Some(position);
+    units::items_to_metric(std::slice::from_mut(&mut item), options.units);
+    let position = item.position.take().unwrap_or_default();
+
+    let label = format!("Box {}", box_index + 1);
+    let invalid = |reason: String| PackError::InvalidPlacement { reason };
+    let box_data = solution.boxes.get_mut(box_index).ok_or_else(|| invalid(format!("{} does not exist", label)))?;
+    if box_data.weight_only {
+        return Err(invalid(format!("{} is packed by weight alone, its items have no positions to place among", label)));
+    }
+    if box_data.ship_loose {
+        return Err(invalid(format!("{} is an item shipping loose in its own packaging", label)));
+    }
+    let constraints = box_constraints(box_data, options);
+    check_placement(box_data, &item, position, BoundsMode::fixed(box_data), &constraints, options).map_err(|rejection| {
+        invalid(match rejection {
+            Rejection::Bounds | Rejection::Volume => format!("Item {} does not fit inside {} there", item.id, label),
+            Rejection::Collision => format!("Item {} overlaps an item already in {}", item.id, label),
+            Rejection::Support => format!("Item {} has no footing it may stand on there in {}", item.id, label),
+            Rejection::Weight => format!("Item {} takes {} over its weight limit", item.id, label),
+            Rejection::Value => format!("Item {} takes {} over its declared value cap", item.id, label),
+            Rejection::Hazmat => format!("Item {} may not be packed with the hazmat in {}", item.id, label),
+        })
+    })?;
+
+    // Placing an item the packer left out takes it off the unpacked list
+    if let Some(index) = solution.unpacked_items.iter().position(|unpacked| unpacked.id == item.id) {
+        solution.unpacked_items.remove(index);
+        if let Some(detail) = solution.unpacked_details.iter().position(|detail| detail.item_id == item.id) {
+            solution.unpacked_details.remove(detail);
+        }
+    }
+    box_data.add_item(item, position);
+    box_data.categories = box_data.category_counts();
+    box_data.contents_summary = box_data.contents_by(&options.sku_key);
+    box_data.hazmat = hazmat::contents(&box_data.items);
+    box_data.stability = Some(stability::assess(box_data));
+    solution.pairings = affinity::pairings(&solution.boxes, &solution.unpacked_items);
+    solution.packed_count += 1;
+    solution.boxes_by_destination = index_boxes(&mut solution.boxes);
+    solution.destination_summaries = destination_summaries(&solution.boxes);
+    (solution.packing_efficiency, solution.wasted_volume) = packing_efficiency(&solution.boxes);
+
+    to_caller(&mut solution, options);
+    Ok(solution)
+}
+
+// Everything wrong with a solution handed back by the caller, e.g. after edits, in its units
+pub fn validate_solution(mut solution: PackingSolution, options: &PackingOptions) -> Vec<verify::Violation> {
+    units::solution_to_metric(&mut solution, options.units);
+    verify::validate_solution(&solution, options)
+}
+
+// Outcome of packing the same items with one strategy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyComparison {
+    pub strategy: Strategy,
+    pub box_count: usize,
+    pub total_volume: f64,
+    // Item volume over box volume, 0..1
+    pub fill_rate: f64,
+    pub unpacked_count: usize,
+    pub solution: PackingSolution,
+}
+
+// Pack the items once per strategy, all other options unchanged
+pub fn compare_strategies(items: Vec<Item>, options: &PackingOptions) -> Result<Vec<StrategyComparison>, PackError> {
+    validate_request(&items, options)?;
+    let comparisons = Strategy::ALL
+        .iter()
+        .map(|&strategy| {
+            let options = PackingOptions {
+                strategy,
+                ..options.clone()
+            };
+            let solution = pack_unchecked(items.clone(), &options);
+            StrategyComparison {
+                strategy,
+                box_count: solution.boxes.len(),
+                total_volume: solution.total_volume,
+                fill_rate: solution_fill_rate(&solution),
+                unpacked_count: solution.unpacked_items.len(),
+                solution,
+            }
+        })
+        .collect();
+    Ok(comparisons)
+}
+
+// Item volume over box volume of a whole solution, 0..1
+fn solution_fill_rate(solution: &PackingSolution) -> f64 {
+    let item_volume: f64 = solution.boxes.iter().flat_map(|b| &b.items).map(Item::volume).sum();
+    if solution.total_volume > 0.0 { item_volume / solution.total_volume } else { 0.0 }
+}
+
+// Outcome of packing the same items at one candidate quality
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityReport {
+    pub quality: CandidateQuality,
+    pub box_count: usize,
+    // Item volume over box volume, 0..1
+    pub fill_rate: f64,
+    pub unpacked_count: usize,
+    // Candidate positions checked over the whole run
+    pub candidate_evaluations: u64,
+    pub elapsed_ms: f64,
+}
+
+// Pack the items once per candidate quality, all other options unchanged, to weigh fuller boxes
+// against packing time; the levels run one after the other so the timings stay comparable
+pub fn tune_quality(items: Vec<Item>, options: &PackingOptions) -> Result<Vec<QualityReport>, PackError> {
+    validate_request(&items, options)?;
+    let reports = CandidateQuality::ALL
+        .iter()
+        .map(|&quality| {
+            let options = PackingOptions {
+                candidate_quality: Some(quality),
+                collect_stats: true,
+                ..options.clone()
+            };
+            let solution = pack_unchecked(items.clone(), &options);
+            let stats = solution.stats.clone().unwrap_or_default();
+            QualityReport {
+                quality,
+                box_count: solution.boxes.len(),
+                fill_rate: solution_fill_rate(&solution),
+                unpacked_count: solution.unpacked_items.len(),
+                candidate_evaluations: stats.candidate_positions,
+                elapsed_ms: stats.elapsed_ms,
+            }
+        })
+        .collect();
+    Ok(reports)
+}
+
+// Pack each named batch as its own pack_items call would, all at once; a bad batch fails alone
+pub fn pack_batches(
+    batches: Vec<(String, Vec<Item>)>,
+    options: &PackingOptions,
+) -> Vec<(String, Result<PackingSolution, PackError>)> {
+    let jobs = batches
+        .into_iter()
+        .map(|(name, items)| -> platform::Job<'_, _> { Box::new(move || (name, try_pack_with_options(items, options))) })
+        .collect();
+    platform::run_all(jobs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Shared with the tests of the other modules
+    pub(crate) fn item(id: &str, destination: &str, (length, width, height): (f64, f64, f64), weight: f64) -> Item {
+        let item: Item = serde_json::from_value(serde_json::json!({
+            "id": id,
+            "destination": destination,
+            "length": 1.0,
+            "width": 1.0,
+            "height": 1.0,
+            "weight": 0.0,
+        }))
+        .unwrap();
+        // Set afterwards, as JSON has no NaN or infinity
+        Item { length, width, height, weight, ..item }
+    }
+
+    // Options packing for the destination "Test" under these limits
+    fn test_lane(limits: DestinationConstraints) -> PackingOptions {
+        PackingOptions {
+            custom_destinations: HashMap::from([("Test".to_string(), limits)]),
+            ..Default::default()
+        }
+    }
+
+    fn unpacked_reason(solution: &PackingSolution, id: &str) -> Option<UnpackedReason> {
+        solution.unpacked_details.iter().find(|detail| detail.item_id == id).map(|detail| detail.reason)
+    }
+
+    // Per lane: an item inside its limits, one over its length plus girth (or dimension sum) with
+    // no side too long, and one over its weight
+    #[test]
+    fn lanes_take_items_within_their_limits_only() {
+        let lanes = [
+            ("Canada", (100.0, 40.0, 30.0), 25.0, (150.0, 50.0, 40.0), 31.0),
+            ("France", (60.0, 40.0, 30.0), 25.0, (70.0, 50.0, 40.0), 31.0),
+            ("Italy", (100.0, 40.0, 30.0), 18.0, (140.0, 50.0, 40.0), 21.0),
+            ("Spain", (80.0, 60.0, 50.0), 25.0, (100.0, 70.0, 50.0), 31.0),
+            ("Netherlands", (100.0, 60.0, 40.0), 20.0, (100.0, 90.0, 70.0), 24.0),
+            ("Belgium", (100.0, 40.0, 30.0), 25.0, (140.0, 50.0, 40.0), 31.0),
+            ("Sweden", (100.0, 40.0, 30.0), 18.0, (140.0, 50.0, 40.0), 21.0),
+            ("Poland", (100.0, 80.0, 60.0), 16.0, (140.0, 100.0, 70.0), 21.0),
+        ];
+        for (destination, size, weight, oversize, overweight) in lanes {
+            let limits = get_destination_constraints(destination, &PackingOptions::default());
+            let (length, width, height) = oversize;
+            assert!(length <= limits.max_box_dimension, "{destination}");
+            assert!(!within_dimension_limits(length, width, height, &limits), "{destination}");
+            assert!(weight < limits.max_box_weight && overweight > limits.max_box_weight, "{destination}");
+
+            let items = vec![
+                item("fits", destination, size, weight),
+                item("oversize", destination, oversize, 1.0),
+                item("overweight", destination, size, overweight),
+            ];
+            let solution = try_pack_with_options(items, &PackingOptions::default()).unwrap();
+            assert_eq!(unpacked_reason(&solution, "fits"), None, "{destination}");
+            assert_eq!(unpacked_reason(&solution, "oversize"), Some(UnpackedReason::ExceedsLimits), "{destination}");
+            assert_eq!(unpacked_reason(&solution, "overweight"), Some(UnpackedReason::ExceedsLimits), "{destination}");
+            let packed = &solution.boxes[0];
+            assert!(packed.weight <= limits.max_box_weight, "{destination}");
+            assert!(within_dimension_limits(packed.length, packed.width, packed.height, &limits), "{destination}");
+        }
+    }
+
+    // Eight items FewestBoxes gets into one 30 cm cube, where SmallestVolume's compact boxes take two
+    #[test]
+    fn fewest_boxes_trades_compact_boxes_for_fewer() {
+        let sizes = [
+            (5.0, 15.0, 10.0),
+            (15.0, 10.0, 10.0),
+            (10.0, 20.0, 20.0),
+            (20.0, 15.0, 20.0),
+            (10.0, 20.0, 15.0),
+            (15.0, 20.0, 20.0),
+            (5.0, 15.0, 20.0),
+            (10.0, 20.0, 5.0),
+        ];
+        let items: Vec<Item> = sizes.iter().enumerate().map(|(i, &size)| item(&format!("I{i}"), "Test", size, 1.0)).collect();
+        let options = test_lane(DestinationConstraints {
+            max_box_dimension: 30.0,
+            max_box_weight: 100.0,
+            ..Default::default()
+        });
+        let compact = pack_with_options(items.clone(), &options).unwrap();
+        let fewest = pack_with_options(items, &PackingOptions { objective: Objective::FewestBoxes, ..options }).unwrap();
+        assert_eq!((compact.boxes.len(), fewest.boxes.len()), (2, 1));
+        assert!(fewest.boxes.iter().all(|b| b.length <= 30.0 && b.width <= 30.0 && b.height <= 30.0));
+    }
+
+    fn spec(name: &str, (length, width, height): (f64, f64, f64), available: Option<u32>, cost: f64) -> BoxSpec {
+        BoxSpec {
+            name: name.to_string(),
+            length,
+            width,
+            height,
+            max_weight: None,
+            available,
+            cost,
+        }
+    }
+
+    fn spec_usage<'a>(solution: &'a PackingSolution, name: &str) -> &'a BoxSpecUsage {
+        solution.box_usage.iter().find(|usage| usage.name == name).unwrap()
+    }
+
+    // Two cubes fill a small carton; once both in stock are used the rest go into a large one
+    #[test]
+    fn runs_out_of_a_spec_and_switches_to_a_larger_one() {
+        let cubes = |count: usize| (0..count).map(|i| item(&format!("B{i}"), "USA", (20.0, 20.0, 20.0), 1.0)).collect::<Vec<_>>();
+        let options = PackingOptions {
+            box_catalog: vec![spec("small", (20.0, 20.0, 40.0), Some(2), 0.0), spec("large", (40.0, 40.0, 40.0), None, 0.0)],
+            ..Default::default()
+        };
+        let solution = pack_with_options(cubes(6), &options).unwrap();
+        assert!(solution.unpacked_items.is_empty());
+        let small = spec_usage(&solution, "small");
+        assert_eq!((small.used, small.remaining), (2, Some(0)));
+        assert_eq!(spec_usage(&solution, "large").used, 1);
+        let in_large = solution.boxes.iter().filter(|b| b.spec.as_deref() == Some("large")).map(|b| b.items.len()).sum::<usize>();
+        assert_eq!(in_large, 2);
+
+        // Without a larger spec the cubes past the stock are reported, not packed
+        let options = PackingOptions {
+            box_catalog: vec![spec("small", (20.0, 20.0, 40.0), Some(1), 0.0)],
+            ..Default::default()
+        };
+        let solution = pack_with_options(cubes(4), &options).unwrap();
+        let out = solution.unpacked_details.iter().filter(|detail| detail.reason == UnpackedReason::OutOfBoxes).count();
+        assert_eq!((solution.boxes.len(), out), (1, 2));
+    }
+
+    // Two cubes in two small cartons at 1.00 each, or together in one large one at 1.60
+    #[test]
+    fn lowest_cost_takes_one_large_box_over_two_small() {
+        let cubes: Vec<Item> = (0..2).map(|i| item(&format!("B{i}"), "USA", (20.0, 20.0, 20.0), 1.0)).collect();
+        let catalog = vec![spec("small", (20.0, 20.0, 20.0), None, 1.0), spec("large", (40.0, 20.0, 20.0), None, 1.6)];
+        let smallest = pack_with_options(cubes.clone(), &PackingOptions { box_catalog: catalog.clone(), ..Default::default() }).unwrap();
+        let cheapest = pack_with_options(cubes, &PackingOptions {
+            box_catalog: catalog,
+            objective: Objective::LowestCost,
+            ..Default::default()
+        }).unwrap();
+        assert_eq!((smallest.boxes.len(), smallest.total_cost), (2, Some(2.0)));
+        assert_eq!(cheapest.boxes.len(), 1);
+        assert_eq!(cheapest.boxes[0].spec.as_deref(), Some("large"));
+        assert_eq!((cheapest.boxes[0].cost, cheapest.total_cost), (Some(1.6), Some(1.6)));
+    }
+
+    // A plank across two blocks 10 cm apart is held to the gap between them, and to what it sticks
+    // out past their ends
+    #[test]
+    fn bridging_two_supports_is_allowed_within_the_tolerance() {
+        let mut box_data = PackedBox::new("Test");
+        box_data.add_item(item("left", "Test", (10.0, 10.0, 10.0), 1.0), Position::ORIGIN);
+        box_data.add_item(item("right", "Test", (10.0, 10.0, 10.0), 1.0), Position::new(20.0, 0.0, 0.0));
+        let limits = DestinationConstraints {
+            max_box_dimension: 100.0,
+            max_box_weight: 100.0,
+            ..Default::default()
+        };
+        let place = |length: f64, tolerance: Option<f64>| {
+            let options = PackingOptions { allow_overhang_cm: tolerance, ..Default::default() };
+            let plank = item("plank", "Test", (length, 10.0, 1.0), 1.0);
+            check_placement(&box_data, &plank, Position::new(0.0, 0.0, 10.0), BoundsMode::Growable(&limits), &limits, &options)
+        };
+        assert!(place(30.0, None).is_ok());
+        assert!(place(30.0, Some(10.0)).is_ok());
+        assert!(matches!(place(30.0, Some(5.0)), Err(Rejection::Support)));
+        assert!(place(35.0, Some(10.0)).is_ok());
+        assert!(matches!(place(45.0, Some(10.0)), Err(Rejection::Support)));
+
+        // Packed, the plank only goes on the blocks when the tolerance allows for its overhang
+        let mut options = test_lane(DestinationConstraints {
+            max_box_dimension: 30.0,
+            max_box_weight: 100.0,
+            alternative_dimensions: Some(Dimensions::new(30.0, 10.0, 20.0)),
+            ..Default::default()
+        });
+        let items = vec![
+            item("c1", "Test", (10.0, 10.0, 10.0), 1.0),
+            item("c2", "Test", (10.0, 10.0, 10.0), 1.0),
+            item("plank", "Test", (30.0, 10.0, 1.0), 1.0),
+        ];
+        let mut boxes = |tolerance: Option<f64>| {
+            options.allow_overhang_cm = tolerance;
+            pack_with_options(items.clone(), &options).unwrap().boxes.len()
+        };
+        assert_eq!((boxes(None), boxes(Some(10.0)), boxes(Some(5.0))), (1, 1, 2));
+    }
+
+    // Flat items stacked with the cube bias fill squarer boxes, lighter by dimensional weight (volume /
+    // 5000) than the flat boxes packed without it
+    #[test]
+    fn cube_bias_lowers_the_chargeable_weight() {
+        let chargeable = |solution: &PackingSolution| {
+            solution.boxes.iter().map(|b| b.weight.max(b.volume() / 5000.0)).sum::<f64>()
+        };
+        let items: Vec<Item> = (0..8).map(|i| item(&format!("flat{i}"), "USA", (30.0, 20.0, 3.0), 0.2)).collect();
+        let options = PackingOptions { grid_min_items: Some(0), ..Default::default() };
+        let plain = pack_with_options(items.clone(), &options).unwrap();
+        let biased = pack_with_options(items, &PackingOptions { cube_bias: Some(0.5), ..options }).unwrap();
+        assert_eq!(biased.packed_count, 8);
+        assert!(chargeable(&biased) < chargeable(&plain));
+    }
+
+    // No input is an error; input that all fails the limits is a solution with nothing packed and a
+    // reason for each item
+    #[test]
+    fn empty_input_is_an_error_and_all_rejected_input_is_not() {
+        let options = PackingOptions::default();
+        let error = try_pack_with_options(vec![], &options).unwrap_err();
+        assert_eq!(error, PackError::EmptyInput);
+        assert_eq!(serde_json::to_value(&error).unwrap(), serde_json::json!({"kind": "empty_input"}));
+
+        let items = vec![
+            item("huge", "Japan", (200.0, 10.0, 10.0), 1.0),
+            item("heavy", "UK", (10.0, 10.0, 10.0), 50.0),
+        ];
+        let solution = try_pack_with_options(items, &options).unwrap();
+        assert_eq!(solution.packed_count, 0);
+        assert!(solution.boxes.is_empty());
+        assert_eq!(solution.unpacked_items.len(), 2);
+        assert_eq!(solution.unpacked_details.len(), 2);
+        assert!(solution.unpacked_details.iter().all(|d| d.reason == UnpackedReason::ExceedsLimits));
+    }
+
+    // A cube and a flat item of the same 8000 cm³ are priced by the cardboard around them: the flat
+    // box has more of it and costs more
+    #[test]
+    fn packaging_cost_scales_with_surface_area_not_volume() {
+        let options = PackingOptions { cardboard_price_per_sqm: Some(2.0), ..Default::default() };
+        let cube = pack_with_options(vec![item("cube", "USA", (20.0, 20.0, 20.0), 1.0)], &options).unwrap();
+        let flat = pack_with_options(vec![item("flat", "USA", (40.0, 40.0, 5.0), 1.0)], &options).unwrap();
+        let (cube, flat) = (&cube.boxes[0], &flat.boxes[0]);
+        assert_eq!(cube.volume(), flat.volume());
+        let (cube_cost, flat_cost) = (cube.packaging_cost.unwrap(), flat.packaging_cost.unwrap());
+        assert!((cube_cost - 2.0 * cube.outer_surface_sqm()).abs() < 1e-9);
+        assert!((flat_cost - 2.0 * flat.outer_surface_sqm()).abs() < 1e-9);
+        assert!(flat_cost > cube_cost * 1.2, "{flat_cost} {cube_cost}");
+    }
+
+    // A cube filling a 50 cm lane fits without headspace and not with 3 cm of it, cut to fit or in a
+    // 50 cm catalog carton; the box reported around a smaller item includes the headspace
+    #[test]
+    fn headspace_rejects_an_item_filling_the_nominal_height() {
+        let mut options = test_lane(DestinationConstraints {
+            max_box_dimension: 50.0,
+            max_box_weight: 30.0,
+            ..Default::default()
+        });
+        let packed = |solution: &PackingSolution| (solution.boxes.len(), solution.unpacked_items.len());
+        let filling = || vec![item("filling", "Test", (50.0, 50.0, 50.0), 1.0)];
+        assert_eq!(packed(&pack_with_options(filling(), &options).unwrap()), (1, 0));
+
+        options.headspace_cm = Some(3.0);
+        assert_eq!(packed(&pack_with_options(filling(), &options).unwrap()), (0, 1));
+        let small = pack_with_options(vec![item("small", "Test", (20.0, 20.0, 20.0), 1.0)], &options).unwrap();
+        assert_eq!(small.boxes[0].height, 23.0);
+
+        options.box_catalog = vec![spec("carton", (50.0, 50.0, 50.0), None, 1.0)];
+        let tall = pack_with_options(vec![item("tall", "Test", (49.0, 49.0, 49.0), 1.0)], &options).unwrap();
+        assert_eq!(packed(&tall), (0, 1));
+        let low = pack_with_options(vec![item("low", "Test", (49.0, 49.0, 47.0), 1.0)], &options).unwrap();
+        assert_eq!(packed(&low), (1, 0));
+    }
+
+    // A full 10 cm cube, a 40×30×20 box holding 18000 cm³ and a 20×20×10 one holding 3000 cm³, and a
+    // weight-only box left out of the volumes
+    #[test]
+    fn efficiency_is_item_volume_over_box_volume() {
+        let mut cube = PackedBox::new("USA");
+        cube.add_item(item("cube", "USA", (10.0, 10.0, 10.0), 1.0), Position::ORIGIN);
+        let mut large = PackedBox::new("UK");
+        large.add_item(item("base", "UK", (40.0, 30.0, 10.0), 2.0), Position::ORIGIN);
+        large.add_item(item("top", "UK", (20.0, 30.0, 10.0), 1.0), Position::new(0.0, 0.0, 10.0));
+        let mut small = PackedBox::new("USA");
+        small.add_item(item("long", "USA", (20.0, 10.0, 10.0), 1.0), Position::ORIGIN);
+        small.add_item(item("short", "USA", (10.0, 10.0, 10.0), 1.0), Position::new(0.0, 10.0, 0.0));
+        let mut weight_only = PackedBox::new("USA");
+        weight_only.weight_only = true;
+        weight_only.add_unplaced(item("sizeless", "USA", (50.0, 50.0, 50.0), 1.0));
+        let boxes = vec![cube, large, small, weight_only];
+
+        assert_eq!(packing_efficiency(&boxes), (22000.0 / 29000.0, 7000.0));
+        let summary = |destination: &str, box_count, weight_only_count, item_volume, box_volume, worst_box| DestinationSummary {
+            destination: destination.to_string(),
+            box_count,
+            weight_only_count,
+            fill_rate: item_volume / box_volume,
+            low_fill_count: 0,
+            item_volume,
+            box_volume,
+            wasted_volume: box_volume - item_volume,
+            worst_box: Some(worst_box),
+            worst_fill_rate: 0.75,
+        };
+        assert_eq!(
+            destination_summaries(&boxes),
+            [summary("UK", 1, 0, 18000.0, 24000.0, 1), summary("USA", 3, 1, 4000.0, 5000.0, 2)]
+        );
+    }
+
+    // The solution's figures are those of its boxes as returned
+    #[test]
+    fn efficiency_is_of_the_final_boxes() {
+        let items: Vec<Item> = (0..12).map(|i| item(&format!("I{i}"), ["USA", "UK"][i % 2], (9.0 + i as f64, 7.0, 5.0), 0.5)).collect();
+        let solution = pack_with_options(items, &PackingOptions::default()).unwrap();
+        assert_eq!((solution.packing_efficiency, solution.wasted_volume), packing_efficiency(&solution.boxes));
+        assert_eq!(solution.destination_summaries, destination_summaries(&solution.boxes));
+    }
+
+    // The error of each entry point that packs, places or scores, None where it took the request
+    fn entry_point_errors(items: Vec<Item>, options: &PackingOptions) -> Vec<Option<PackError>> {
+        let solution = pack_with_options(vec![item("good", "USA", (10.0, 10.0, 10.0), 1.0)], &PackingOptions::default()).unwrap();
+        let scored = PackingSolution {
+            boxes: vec![PackedBox {
+                items: items.clone(),
+                ..solution.boxes[0].clone()
+            }],
+            ..solution.clone()
+        };
+        let weights = options.score_weights.clone().unwrap_or_default();
+        vec![
+            try_pack_with_options(items.clone(), options).err(),
+            pack_with_options(items.clone(), options).err(),
+            compare_strategies(items.clone(), options).err(),
+            tune_quality(items.clone(), options).err(),
+            frontier::utilization_frontier(items.clone(), options).err(),
+            scenarios::what_if(items.clone(), Vec::new(), options).err(),
+            jobs::JobsState::default().start(items.clone(), options.clone(), |_| {}).err(),
+            queue::QueueState::default().enqueue(items.clone(), options.clone()).err(),
+            place_item(solution, 0, items[0].clone(), Position::new(10.0, 0.0, 0.0), options).err(),
+            score::try_score_solution(&scored, &weights).err(),
+        ]
+    }
+
+    // Sizes and weights out of reach of f64, not numbers or far past any parcel, such as a length
+    // pasted in micrometres, are turned down up front by every entry point
+    #[test]
+    fn implausible_numbers_fail_cleanly() {
+        let options = PackingOptions::default();
+        let invalid_item = |field: &str, error: &Option<PackError>| matches!(error, Some(PackError::InvalidItem { field: actual, .. }) if actual == field);
+        for length in [f64::MAX, f64::MAX / 2.0, f64::INFINITY, f64::NAN, 6.3e7, 500.5, -f64::MAX] {
+            let errors = entry_point_errors(vec![item("absurd", "USA", (length, 10.0, 10.0), 1.0)], &options);
+            assert!(errors.iter().all(|error| invalid_item("length", error)), "{length}: {errors:?}");
+        }
+        for weight in [f64::MAX, f64::INFINITY, f64::NAN, 1000.5] {
+            let errors = entry_point_errors(vec![item("absurd", "USA", (10.0, 10.0, 10.0), weight)], &options);
+            assert!(errors.iter().all(|error| invalid_item("weight", error)), "{weight}: {errors:?}");
+        }
+
+        // Limits and cartons beyond what the packer can size boxes for without overflowing
+        let good = || vec![item("good", "USA", (10.0, 10.0, 10.0), 1.0)];
+        let invalid_option = |field: &str, error: &Option<PackError>| matches!(error, Some(PackError::InvalidOption { field: actual, .. }) if actual == field);
+        let bad_options = [
+            ("max_item_dimension_cm", PackingOptions { max_item_dimension_cm: Some(f64::MAX), ..Default::default() }),
+            ("max_item_weight_kg", PackingOptions { max_item_weight_kg: Some(f64::NAN), ..Default::default() }),
+            ("box_catalog", PackingOptions { box_catalog: vec![spec("huge", (f64::MAX, 10.0, 10.0), None, 1.0)], ..Default::default() }),
+            (
+                "score_weights",
+                PackingOptions {
+                    score_weights: Some(score::ScoreWeights { box_count: f64::NAN, ..Default::default() }),
+                    ..Default::default()
+                },
+            ),
+        ];
+        for (field, options) in bad_options {
+            let errors = entry_point_errors(good(), &options);
+            // Scoring sees the weights alone
+            let checked = if field == "score_weights" { &errors[..] } else { &errors[..errors.len() - 1] };
+            assert!(checked.iter().all(|error| invalid_option(field, error)), "{field}: {errors:?}");
+        }
+    }
+
+    // Option values at the edge of f64, not numbers, negative or out of their range, those of the
+    // destination, exception, service and carrier limits included, are turned down up front too
+    #[test]
+    fn implausible_option_values_fail_cleanly() {
+        let good = || vec![item("good", "USA", (10.0, 10.0, 10.0), 1.0)];
+        let huge = f64::MAX / 2.0;
+        let lane = |limits: DestinationConstraints| PackingOptions {
+            custom_destinations: HashMap::from([("USA".to_string(), limits)]),
+            ..Default::default()
+        };
+        let bad_options = [
+            ("headspace_cm", PackingOptions { headspace_cm: Some(1e308), ..Default::default() }),
+            ("headspace_cm", PackingOptions { headspace_cm: Some(-1.0), ..Default::default() }),
+            ("safety_margin_pct", PackingOptions { safety_margin_pct: Some(f64::NAN), ..Default::default() }),
+            ("dimension_rounding_cm", PackingOptions { dimension_rounding_cm: Some(0.0), ..Default::default() }),
+            ("allow_overhang_cm", PackingOptions { allow_overhang_cm: Some(f64::INFINITY), ..Default::default() }),
+            ("layer_tolerance_cm", PackingOptions { layer_tolerance_cm: Some(f64::MAX), ..Default::default() }),
+            ("irregular_inflation_pct", PackingOptions { irregular_inflation_pct: Some(huge), ..Default::default() }),
+            ("cube_bias", PackingOptions { cube_bias: Some(-f64::MAX), ..Default::default() }),
+            ("safety_margin_pct", PackingOptions { safety_margin_pct: Some(150.0), ..Default::default() }),
+            ("safety_margin_pct", PackingOptions { safety_margin_pct: Some(100.0), ..Default::default() }),
+            ("safety_margin_pct", PackingOptions { safety_margin_pct: Some(-5.0), ..Default::default() }),
+            ("min_fill_rate", PackingOptions { min_fill_rate: Some(1.5), ..Default::default() }),
+            ("order_bonus", PackingOptions { order_bonus: Some(f64::MAX), ..Default::default() }),
+            ("pair_bonus", PackingOptions { pair_bonus: Some(-0.1), ..Default::default() }),
+            ("custom_destinations", lane(DestinationConstraints { max_box_weight: f64::MAX, ..Default::default() })),
+            ("custom_destinations", lane(DestinationConstraints { max_box_volume: Some(f64::INFINITY), ..Default::default() })),
+            (
+                "exceptions",
+                PackingOptions {
+                    exceptions: vec![ConstraintException {
+                        destination: "USA".to_string(),
+                        limits: DestinationConstraints { max_box_dimension: f64::NAN, ..Default::default() },
+                        max_boxes: 1,
+                    }],
+                    ..Default::default()
+                },
+            ),
+            (
+                "services",
+                PackingOptions {
+                    services: vec![ServiceLimits {
+                        service: "express".to_string(),
+                        destination: "USA".to_string(),
+                        limits: DestinationConstraints { headspace_cm: Some(huge), ..Default::default() },
+                    }],
+                    ..Default::default()
+                },
+            ),
+            (
+                "carriers",
+                PackingOptions {
+                    carriers: vec![CarrierProfile {
+                        name: "Huge".to_string(),
+                        max_box_dimension: 100.0,
+                        max_box_weight: 30.0,
+                        max_length_plus_girth: Some(f64::MAX),
+                        alternative_dimensions: None,
+                    }],
+                    ..Default::default()
+                },
+            ),
+        ];
+        let invalid_option = |field: &str, error: &Option<PackError>| matches!(error, Some(PackError::InvalidOption { field: actual, .. }) if actual == field);
+        for (field, options) in bad_options {
+            let errors = entry_point_errors(good(), &options);
+            // Scoring sees the weights alone
+            assert!(errors[..errors.len() - 1].iter().all(|error| invalid_option(field, error)), "{field}: {errors:?}");
+        }
+
+        // The largest values allowed still pack, without numbers past what the limits can reach
+        let roomy = PackingOptions { headspace_cm: Some(MAX_INPUT_LIMIT), ..Default::default() };
+        let solution = pack_with_options(good(), &roomy).unwrap();
+        let gaps = &solution.unpacked_details[0].gaps;
+        let plausible = |gap: &diagnostics::ConstraintGap| gap.needed <= 2.0 * MAX_INPUT_LIMIT.powi(3) && gap.message.len() < 80;
+        assert!(!gaps.is_empty() && gaps.iter().all(plausible), "{gaps:?}");
+    }
+
+    fn assert_recomputes_the_same(box_data: &PackedBox) {
+        let mut recomputed = box_data.clone();
+        recomputed.recompute_from_items(&box_data.material);
+        let measures = |b: &PackedBox| [b.length, b.width, b.height, b.weight, b.value, b.cardboard_weight()];
+        let (incremental, scratch) = (measures(box_data), measures(&recomputed));
+        assert!(incremental.iter().zip(&scratch).all(|(a, b)| (a - b).abs() < 1e-9), "{incremental:?} {scratch:?}");
+    }
+
+    // A box built item by item and the same box recomputed from its items agree, bare and with the
+    // headspace, dunnage, reserved items and catalog cartons a packing may bring
+    #[test]
+    fn incremental_and_recomputed_boxes_agree() {
+        let mut box_data = PackedBox::new("USA");
+        box_data.add_item(item("base", "USA", (30.0, 20.0, 10.0), 2.0), Position::ORIGIN);
+        box_data.add_item(item("side", "USA", (10.0, 25.0, 5.0), 0.5), Position::new(30.0, 0.0, 0.0));
+        box_data.add_item(item("top", "USA", (15.0, 15.0, 12.0), 1.25), Position::new(0.0, 0.0, 10.0));
+        assert_recomputes_the_same(&box_data);
+        box_data.remove_item(1);
+        assert_eq!((box_data.length, box_data.width, box_data.height), (30.0, 20.0, 22.0));
+        assert_recomputes_the_same(&box_data);
+
+        let items: Vec<Item> = (0..24)
+            .map(|i| item(&format!("I{i}"), ["USA", "UK"][i % 2], (5.0 + (i * 7 % 13) as f64, 4.0 + (i * 5 % 11) as f64, 3.0 + (i % 4) as f64), 0.3 + 0.1 * (i % 3) as f64))
+            .collect();
+        let runs = [
+            PackingOptions::default(),
+            PackingOptions {
+                headspace_cm: Some(2.0),
+                flap_clearance_cm: Some(0.5),
+                dunnage: Some(Dunnage::Density { grams_per_litre: 8.0 }),
+                reserved_items: vec![item("card", "", (10.0, 5.0, 0.1), 0.02)],
+                ..Default::default()
+            },
+            PackingOptions {
+                box_catalog: vec![spec("small", (20.0, 15.0, 10.0), None, 1.0), spec("large", (40.0, 30.0, 20.0), None, 2.0)],
+                ..Default::default()
+            },
+        ];
+        for options in runs {
+            for box_data in &pack_items_impl(items.clone(), &options).boxes {
+                assert_recomputes_the_same(box_data);
+            }
+        }
+    }
+
+    // Heavy items two to a box by weight: closing the full boxes skips them in the search, without
+    // it every box is tried again, and the boxes come out the same either way
+    #[test]
+    fn closing_full_boxes_skips_them_and_keeps_the_boxes() {
+        let items: Vec<Item> = (0..40)
+            .map(|i| item(&format!("I{i}"), "Test", (20.0 + (i % 7) as f64, 20.0 + (i % 5) as f64, 15.0 + (i % 3) as f64), 10.0 + 0.25 * (i % 8) as f64))
+            .collect();
+        let options = PackingOptions {
+            collect_stats: true,
+            ..test_lane(DestinationConstraints {
+                max_box_dimension: 100.0,
+                max_box_weight: 25.0,
+                ..Default::default()
+            })
+        };
+        let closing = pack_with_options(items.clone(), &options).unwrap();
+        let open = pack_with_options(items, &PackingOptions { close_full_boxes: Some(false), ..options }).unwrap();
+        let skips = |solution: &PackingSolution| solution.stats.as_ref().unwrap().closed_skips;
+        assert_eq!(closing.boxes.len(), 20);
+        assert!(skips(&closing) > 0);
+        assert_eq!(skips(&open), 0);
+        let boxes = |solution: &PackingSolution| serde_json::to_value(&solution.boxes).unwrap();
+        assert_eq!(boxes(&closing), boxes(&open));
+    }
+
+    // Items with room to spare but a declared value of 60 under a 100 cap go one to a box, turned
+    // down and closed for their value and not their weight; 12 kg items under 25 kg, two to a box.
+    // The boxes are left open, so each reason comes from the search and not from closing full boxes.
+    #[test]
+    fn value_and_weight_caps_are_told_apart() {
+        let limits = DestinationConstraints {
+            max_box_dimension: 100.0,
+            max_box_weight: 25.0,
+            max_box_value: Some(100.0),
+            ..Default::default()
+        };
+        let options = PackingOptions {
+            collect_stats: true,
+            close_full_boxes: Some(false),
+            ..test_lane(limits)
+        };
+        let pack = |value: f64, weight: f64| {
+            let items = (0..4).map(|i| Item { value: Some(value), ..item(&format!("I{i}"), "Test", (10.0, 10.0, 10.0), weight) }).collect();
+            pack_with_options(items, &options).unwrap()
+        };
+        let reasons = |solution: &PackingSolution| solution.boxes.iter().map(|b| b.closed_reason).collect::<Vec<_>>();
+
+        let valuable = pack(60.0, 1.0);
+        let stats = valuable.stats.as_ref().unwrap();
+        assert!(stats.rejected_value > 0 && stats.rejected_weight == 0, "{stats:?}");
+        assert_eq!(valuable.boxes.len(), 4);
+        assert!(reasons(&valuable)[..3].iter().all(|reason| *reason == Some(closing::ClosedReason::ValueCap)), "{:?}", reasons(&valuable));
+
+        let heavy = pack(10.0, 12.0);
+        let stats = heavy.stats.as_ref().unwrap();
+        assert!(stats.rejected_weight > 0 && stats.rejected_value == 0, "{stats:?}");
+        assert_eq!(heavy.boxes.len(), 2);
+        assert_eq!(reasons(&heavy)[0], Some(closing::ClosedReason::WeightLimit));
+    }
+
+    // First fit puts an item in the first box with room, even right after one of the same size that
+    // had to open a box of its own: the lighter third item joins the first box, not the second
+    #[test]
+    fn a_run_of_one_size_still_fills_the_first_box_with_room() {
+        let options = test_lane(DestinationConstraints {
+            max_box_dimension: 100.0,
+            max_box_weight: 10.0,
+            ..Default::default()
+        });
+        let items = vec![
+            item("A", "Test", (20.0, 20.0, 20.0), 6.0),
+            item("B", "Test", (20.0, 20.0, 20.0), 6.0),
+            item("C", "Test", (20.0, 20.0, 20.0), 3.0),
+        ];
+        let solution = pack_with_options(items, &options).unwrap();
+        let ids: Vec<Vec<&str>> = solution.boxes.iter().map(|b| b.items.iter().map(|item| item.id.as_str()).collect()).collect();
+        assert_eq!(ids, [vec!["A", "C"], vec!["B"]]);
+    }
+}
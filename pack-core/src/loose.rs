@@ -0,0 +1,45 @@
+// Items that ship loose (Item.ship_loose): goods already in shipping-ready packaging of their own,
+// left out of cartonization. Each becomes a parcel of its own, sized as the item in the first
+// orientation the destination's limits take, with no cardboard, dunnage or reserved items added.
+use crate::{stats, within_dimension_limits, within_value_cap, DestinationConstraints, Item, PackedBox, PackingOptions, Position, UnpackedReason};
+
+// Push a parcel per item onto `boxes`, returning the items the limits don't take and why
+pub(crate) fn pack_loose(
+    items: Vec<Item>,
+    constraints: &DestinationConstraints,
+    options: &PackingOptions,
+    boxes: &mut Vec<PackedBox>,
+) -> Vec<(Item, UnpackedReason)> {
+    let mut rejected = Vec::new();
+
+    for item in items {
+        if options.hazmat_rules.refuses(&item, &item.destination) {
+            rejected.push((item, UnpackedReason::HazmatRestricted));
+            continue;
+        }
+        if options.insurance_cap_per_box.is_some_and(|cap| item.declared_value() > cap) {
+            rejected.push((item, UnpackedReason::OverInsuranceCap));
+            continue;
+        }
+        let turned = item
+            .allowed_rotations()
+            .into_iter()
+            .map(|rotation| item.with_rotation(rotation))
+            .find(|turned| within_dimension_limits(turned.length, turned.width, turned.height, constraints));
+        let Some(turned) = turned.filter(|_| {
+            item.weight <= constraints.max_box_weight && within_value_cap(item.declared_value(), constraints)
+        }) else {
+            rejected.push((item, UnpackedReason::ExceedsLimits));
+            continue;
+        };
+
+        let mut box_data = PackedBox::new(&item.destination);
+        box_data.service = item.service.clone();
+        box_data.ship_loose = true;
+        box_data.add_item(turned, Position::ORIGIN);
+        boxes.push(box_data);
+        stats::record(|stats| stats.boxes_opened += 1);
+    }
+
+    rejected
+}
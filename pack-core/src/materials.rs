@@ -0,0 +1,111 @@
+// Packaging materials consumed by a solution, for sustainability reporting. A pure aggregation of
+// the per-box figures, so the totals always agree with the boxes. Expects the solution in cm and kg.
+use crate::{PackedBox, PackingSolution};
+use serde::{Deserialize, Serialize};
+
+// Tape estimate: one seam along the length of the top and one of the bottom, each running this far
+// down both end faces
+const TAPE_END_DROP_CM: f64 = 5.0;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MaterialTotals {
+    pub box_count: usize,
+    // Outer surface area of the cartons
+    pub corrugate_sqm: f64,
+    // Tare weight of the cartons
+    pub cardboard_kg: f64,
+    pub tape_m: f64,
+    // Box volume not taken up by items
+    pub void_fill_litres: f64,
+}
+
+impl MaterialTotals {
+    fn add_box(&mut self, packed_box: &PackedBox) {
+        self.box_count += 1;
+        self.corrugate_sqm += packed_box.outer_surface_sqm();
+        self.cardboard_kg += packed_box.cardboard_weight();
+        self.tape_m += tape_length_m(packed_box);
+        self.void_fill_litres += packed_box.void_volume() / 1000.0;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DestinationMaterials {
+    pub destination: String,
+    pub totals: MaterialTotals,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MaterialReport {
+    pub overall: MaterialTotals,
+    // Sorted by destination
+    pub destinations: Vec<DestinationMaterials>,
+}
+
+// Tape for one box, measured over the outer dimensions
+pub fn tape_length_m(packed_box: &PackedBox) -> f64 {
+    let thickness = packed_box.material.thickness_cm;
+    let seam = packed_box.length + 2.0 * thickness + 2.0 * TAPE_END_DROP_CM;
+    2.0 * seam / 100.0
+}
+
+pub fn material_report(solution: &PackingSolution) -> MaterialReport {
+    let mut report = MaterialReport::default();
+    for packed_box in &solution.boxes {
+        report.overall.add_box(packed_box);
+        let index = match report.destinations.iter().position(|d| d.destination == packed_box.destination) {
+            Some(index) => index,
+            None => {
+                report.destinations.push(DestinationMaterials {
+                    destination: packed_box.destination.clone(),
+                    totals: MaterialTotals::default(),
+                });
+                report.destinations.len() - 1
+            }
+        };
+        report.destinations[index].totals.add_box(packed_box);
+    }
+    report.destinations.sort_by(|a, b| a.destination.cmp(&b.destination));
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::item;
+    use crate::{pack_with_options, PackingOptions, Position};
+
+    fn assert_totals(totals: &MaterialTotals, box_count: usize, corrugate_sqm: f64, tape_m: f64, void_fill_litres: f64) {
+        assert_eq!(totals.box_count, box_count);
+        assert!((totals.corrugate_sqm - corrugate_sqm).abs() < 1e-9, "{}", totals.corrugate_sqm);
+        // Default cardboard, 0.54 kg/m²
+        assert!((totals.cardboard_kg - corrugate_sqm * 0.54).abs() < 1e-9, "{}", totals.cardboard_kg);
+        assert!((totals.tape_m - tape_m).abs() < 1e-9, "{}", totals.tape_m);
+        assert!((totals.void_fill_litres - void_fill_litres).abs() < 1e-9, "{}", totals.void_fill_litres);
+    }
+
+    // A 40×30×20 USA box a quarter empty and two full 10 cm cubes, one for USA and one for UK, with
+    // 0.6 cm cardboard walls:
+    // - 41.2×31.2×21.2 cm outside: 0.564064 m², 2 × (40 + 1.2 + 2 × 5) cm = 1.024 m of tape, 6 l void
+    // - 11.2 cm cubes outside: 0.075264 m², 2 × (10 + 1.2 + 2 × 5) cm = 0.424 m of tape, no void
+    #[test]
+    fn totals_add_up_the_boxes() {
+        let mut large = PackedBox::new("USA");
+        large.add_item(item("base", "USA", (40.0, 30.0, 10.0), 2.0), Position::ORIGIN);
+        large.add_item(item("top", "USA", (20.0, 30.0, 10.0), 1.0), Position::new(0.0, 0.0, 10.0));
+        let cube = |destination: &str| {
+            let mut packed_box = PackedBox::new(destination);
+            packed_box.add_item(item("cube", destination, (10.0, 10.0, 10.0), 1.0), Position::ORIGIN);
+            packed_box
+        };
+        let mut solution = pack_with_options(vec![item("any", "USA", (1.0, 1.0, 1.0), 1.0)], &PackingOptions::default()).unwrap();
+        solution.boxes = vec![cube("USA"), large, cube("UK")];
+
+        let report = material_report(&solution);
+        assert_totals(&report.overall, 3, 0.564064 + 2.0 * 0.075264, 1.024 + 2.0 * 0.424, 6.0);
+        let destinations: Vec<&str> = report.destinations.iter().map(|d| d.destination.as_str()).collect();
+        assert_eq!(destinations, ["UK", "USA"]);
+        assert_totals(&report.destinations[0].totals, 1, 0.075264, 0.424, 0.0);
+        assert_totals(&report.destinations[1].totals, 2, 0.564064 + 0.075264, 1.024 + 0.424, 6.0);
+    }
+}
@@ -0,0 +1,165 @@
+// Second stage for the freight forwarder: the packed boxes of a solution stacked onto pallets.
+// Each box goes through the carton packer as an item standing the right way up, on a fixed
+// pallet-sized carton of weightless cardboard. Expects the solution in cm and kg.
+use crate::export::box_label;
+use crate::{
+    pack_in_order, BoxSpec, DestinationConstraints, Dimensions, Item, MaterialSpec, PackedBox, PackingOptions, PackingSolution,
+    UnpackedReason,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PalletSpec {
+    // Deck size
+    pub length: f64,
+    pub width: f64,
+    // Tallest stack of boxes on the deck
+    pub max_stack_height: f64,
+    // Most weight of boxes one pallet takes
+    pub max_weight: f64,
+}
+
+// A packed box placed on a pallet or in a container
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlacedBox {
+    // Zero-based index into the solution's boxes
+    pub box_index: usize,
+    pub label: String,
+    // Min-corner position on the deck or container floor in cm
+    pub position: (f64, f64, f64),
+    // Outer dimensions as placed
+    pub dimensions: (f64, f64, f64),
+    // Turned a quarter about the vertical axis
+    pub turned: bool,
+    pub weight: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pallet {
+    pub destination: String,
+    pub boxes: Vec<PlacedBox>,
+    pub total_weight: f64,
+    // Height of the stack above the deck
+    pub height: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnplacedBox {
+    pub box_index: usize,
+    pub label: String,
+    pub reason: UnpackedReason,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PalletPlan {
+    pub pallets: Vec<Pallet>,
+    // Boxes too big or too heavy for any pallet
+    pub unplaced: Vec<UnplacedBox>,
+}
+
+// A packed box as an item for the second stage: its outer size and gross weight, this side up
+pub(crate) fn box_as_item(solution: &PackingSolution, box_index: usize) -> Item {
+    let packed_box = &solution.boxes[box_index];
+    let wall = 2.0 * packed_box.material.thickness_cm;
+    Item {
+        id: box_label(solution, box_index),
+        destination: packed_box.destination.clone(),
+        length: packed_box.length + wall,
+        width: packed_box.width + wall,
+        height: packed_box.height + wall,
+        weight: packed_box.weight,
+        keep_upright: true,
+        ..Default::default()
+    }
+}
+
+// Stack boxes, given as items in solution order (see box_as_item), into fixed loads of the given
+// size, per destination, heaviest first so they end up at the bottom. Returns the loads with the
+// boxes as items, the unplaced boxes, and the box index of every label.
+pub(crate) fn stack_boxes(
+    mut items: Vec<Item>,
+    load: &BoxSpec,
+    allow_overhang_cm: Option<f64>,
+) -> (Vec<PackedBox>, Vec<UnplacedBox>, HashMap<String, usize>) {
+    let indices: HashMap<String, usize> = items.iter().enumerate().map(|(index, item)| (item.id.clone(), index)).collect();
+    items.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+
+    let limits = DestinationConstraints {
+        max_box_dimension: load.length.max(load.width).max(load.height),
+        max_box_weight: load.max_weight.unwrap_or(f64::INFINITY),
+        alternative_dimensions: Some(Dimensions::new(load.length, load.width, load.height)),
+        ..Default::default()
+    };
+    let options = PackingOptions {
+        custom_destinations: items.iter().map(|item| (item.destination.clone(), limits.clone())).collect(),
+        material: MaterialSpec {
+            thickness_cm: 0.0,
+            weight_kg_per_sqm: 0.0,
+            price_per_sqm: None,
+        },
+        box_catalog: vec![load.clone()],
+        grid_min_items: Some(0),
+        allow_overhang_cm,
+        order_bonus: Some(0.0),
+        ..Default::default()
+    };
+
+    let stacked = pack_in_order(items, &options);
+    let unplaced = stacked
+        .unpacked_items
+        .iter()
+        .zip(&stacked.unpacked_details)
+        .map(|(item, detail)| UnplacedBox {
+            box_index: indices[&item.id],
+            label: item.id.clone(),
+            reason: detail.reason,
+        })
+        .collect();
+    (stacked.boxes, unplaced, indices)
+}
+
+// The boxes of a load where they were placed
+pub(crate) fn placed_boxes(load: &PackedBox, indices: &HashMap<String, usize>) -> Vec<PlacedBox> {
+    load.items
+        .iter()
+        .map(|item| PlacedBox {
+            box_index: indices[&item.id],
+            label: item.id.clone(),
+            position: item.position.unwrap_or_default().into(),
+            dimensions: (item.length, item.width, item.height),
+            turned: item.rotation != 0,
+            weight: item.weight,
+        })
+        .collect()
+}
+
+pub fn palletize(solution: &PackingSolution, spec: &PalletSpec) -> PalletPlan {
+    let deck = BoxSpec {
+        name: "pallet".to_string(),
+        length: spec.length,
+        width: spec.width,
+        height: spec.max_stack_height,
+        max_weight: Some(spec.max_weight),
+        available: None,
+        cost: 0.0,
+    };
+    // Boxes rest fully on the deck or on other boxes
+    let items = (0..solution.boxes.len()).map(|index| box_as_item(solution, index)).collect();
+    let (loads, unplaced, indices) = stack_boxes(items, &deck, Some(0.0));
+
+    let pallets = loads
+        .into_iter()
+        .map(|load| {
+            let boxes = placed_boxes(&load, &indices);
+            Pallet {
+                destination: load.destination.clone(),
+                total_weight: load.items_weight(),
+                height: boxes.iter().map(|b| b.position.2 + b.dimensions.2).fold(0.0, f64::max),
+                boxes,
+            }
+        })
+        .collect();
+
+    PalletPlan { pallets, unplaced }
+}
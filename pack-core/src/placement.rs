@@ -0,0 +1,262 @@
+// Weighted placement scoring, for requests that trade box count, dimensional weight and stability
+// differently. Every component is normalized so that 0 is ideal and about 1 is a poor placement;
+// the position and orientation with the lowest weighted sum wins. Unset, the packer keeps its
+// first-fit placement with the surface area as tie-break.
+use crate::{Item, PackError, PackedBox, Position, CONTACT_EPSILON};
+use serde::{Deserialize, Serialize};
+
+// Relative importance of each component; 0 leaves it out
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlacementWeights {
+    pub surface_area_increase: f64,
+    pub volume_increase: f64,
+    pub aspect_ratio: f64,
+    pub support: f64,
+    pub height: f64,
+    pub access: f64,
+}
+
+// Growth first and surface area next, the order the first-fit placement compares orientations in
+impl Default for PlacementWeights {
+    fn default() -> Self {
+        PlacementWeights {
+            surface_area_increase: 0.1,
+            volume_increase: 1.0,
+            aspect_ratio: 0.0,
+            support: 0.0,
+            height: 0.0,
+            access: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlacementComponents {
+    // Box surface area gained, over the item's own surface area
+    pub surface_area_increase: f64,
+    // Box volume gained, over the item's volume
+    pub volume_increase: f64,
+    // Longest over shortest side of the grown box, less 1
+    pub aspect_ratio: f64,
+    // Share of the item's base resting on neither the floor nor a top face, 0..1
+    pub support: f64,
+    // Base height over the grown box height, 0..1
+    pub height: f64,
+    // How far the item is from the opening (the top unless PackedBox.opening_axis says otherwise)
+    // and the front (the far side along the width) of the grown box, each over that side of it and
+    // averaged, 0..1: 0 is reached first when it is opened
+    pub access: f64,
+}
+
+impl PlacementWeights {
+    pub fn score(&self, components: &PlacementComponents) -> f64 {
+        self.surface_area_increase * components.surface_area_increase +
+            self.volume_increase * components.volume_increase +
+            self.aspect_ratio * components.aspect_ratio +
+            self.support * components.support +
+            self.height * components.height +
+            self.access * components.access
+    }
+}
+
+// Weights that rank placements: all finite
+pub(crate) fn check_weights(weights: &PlacementWeights) -> Result<(), PackError> {
+    let rates = [
+        weights.surface_area_increase,
+        weights.volume_increase,
+        weights.aspect_ratio,
+        weights.support,
+        weights.height,
+        weights.access,
+    ];
+    if rates.iter().all(|rate| rate.is_finite()) {
+        return Ok(());
+    }
+    Err(PackError::InvalidOption {
+        field: "placement_weights".to_string(),
+        reason: "must be finite numbers".to_string(),
+    })
+}
+
+fn surface_area([length, width, height]: [f64; 3]) -> f64 {
+    2.0 * (length * width + length * height + width * height)
+}
+
+// Share of the base at the position with nothing under it; irregular items hold nothing up, as in
+// the overhang check
+fn unsupported_share(box_data: &PackedBox, item: &Item, Position { x, y, z }: Position) -> f64 {
+    let base = item.length * item.width;
+    if z <= CONTACT_EPSILON || base <= 0.0 {
+        return 0.0;
+    }
+    // Items never overlap, so neither do their top faces
+    let supported: f64 = box_data
+        .items
+        .iter()
+        .filter_map(|other| {
+            let Position { x: ox, y: oy, z: oz } = other.position?;
+            if other.irregular || (oz + other.height - z).abs() > CONTACT_EPSILON {
+                return None;
+            }
+            let dx = (x + item.length).min(ox + other.length) - x.max(ox);
+            let dy = (y + item.width).min(oy + other.width) - y.max(oy);
+            (dx > 0.0 && dy > 0.0).then_some(dx * dy)
+        })
+        .sum();
+    (1.0 - supported / base).clamp(0.0, 1.0)
+}
+
+// The components of putting the item into the box at the position, the box growing to take it
+pub fn components(box_data: &PackedBox, item: &Item, position: Position) -> PlacementComponents {
+    let Position { x, y, z } = position;
+    let current = [box_data.length, box_data.width, box_data.height];
+    let clearance = box_data.clearance();
+    let grown = [
+        (x + item.length + clearance[0]).max(box_data.length),
+        (y + item.width + clearance[1]).max(box_data.width),
+        (z + item.height + clearance[2]).max(box_data.height),
+    ];
+    let own = [item.length, item.width, item.height];
+    let ratio = |gained: f64, own: f64| if own > 0.0 { gained.max(0.0) / own } else { 0.0 };
+
+    let opening = box_data.opening_axis.index();
+    let start = [x, y, z];
+    let below_opening = if grown[opening] > 0.0 {
+        (grown[opening] - clearance[opening] - start[opening] - own[opening]).max(0.0) / grown[opening]
+    } else {
+        0.0
+    };
+    let behind_front = if grown[1] > 0.0 { (grown[1] - clearance[1] - y - item.width).max(0.0) / grown[1] } else { 0.0 };
+
+    let longest = grown.iter().copied().fold(f64::MIN, f64::max);
+    let shortest = grown.iter().copied().fold(f64::MAX, f64::min);
+    PlacementComponents {
+        surface_area_increase: ratio(surface_area(grown) - surface_area(current), surface_area(own)),
+        volume_increase: ratio(grown.iter().product::<f64>() - current.iter().product::<f64>(), item.volume()),
+        aspect_ratio: if shortest > 0.0 { longest / shortest - 1.0 } else { 0.0 },
+        support: unsupported_share(box_data, item, position),
+        height: if grown[2] > 0.0 { z / grown[2] } else { 0.0 },
+        access: (below_opening + behind_front) / 2.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::item;
+    use crate::{pack_with_options, PackingOptions};
+
+    fn cube() -> Item {
+        item("cube", "USA", (10.0, 10.0, 10.0), 1.0)
+    }
+
+    // A box holding a 20×10×10 base, 2000 cm³ with a surface area of 1000 cm²
+    fn base_box() -> PackedBox {
+        let mut box_data = PackedBox::new("USA");
+        box_data.add_item(item("base", "USA", (20.0, 10.0, 10.0), 1.0), Position::ORIGIN);
+        box_data
+    }
+
+    fn assert_components(actual: PlacementComponents, expected: PlacementComponents) {
+        let pairs = [
+            (actual.surface_area_increase, expected.surface_area_increase),
+            (actual.volume_increase, expected.volume_increase),
+            (actual.aspect_ratio, expected.aspect_ratio),
+            (actual.support, expected.support),
+            (actual.height, expected.height),
+            (actual.access, expected.access),
+        ];
+        assert!(pairs.iter().all(|(a, e)| (a - e).abs() < 1e-9), "{actual:?}");
+    }
+
+    // Beside the base: a 30×10×10 box, 400 cm² over the cube's 600 and 1000 cm³ over its 1000
+    #[test]
+    fn growing_along_the_floor() {
+        let expected = PlacementComponents {
+            surface_area_increase: 400.0 / 600.0,
+            volume_increase: 1.0,
+            aspect_ratio: 2.0,
+            ..Default::default()
+        };
+        assert_components(components(&base_box(), &cube(), Position::new(20.0, 0.0, 0.0)), expected);
+    }
+
+    // On top of the base: a 20×10×20 box, its base halfway up
+    #[test]
+    fn stacking_on_the_base() {
+        let expected = PlacementComponents {
+            surface_area_increase: 1.0,
+            volume_increase: 2.0,
+            aspect_ratio: 1.0,
+            height: 0.5,
+            ..Default::default()
+        };
+        assert_components(components(&base_box(), &cube(), Position::new(0.0, 0.0, 10.0)), expected);
+    }
+
+    // Half over the end of the base: a 25×10×20 box, half the cube's base resting on nothing
+    #[test]
+    fn half_supported() {
+        let expected = PlacementComponents {
+            surface_area_increase: 1.5,
+            volume_increase: 3.0,
+            aspect_ratio: 1.5,
+            support: 0.5,
+            height: 0.5,
+            ..Default::default()
+        };
+        assert_components(components(&base_box(), &cube(), Position::new(15.0, 0.0, 10.0)), expected);
+    }
+
+    // Beside a 20 cm cube the item is half way down from the top and half way back from the front
+    #[test]
+    fn far_from_the_opening_and_the_front() {
+        let mut box_data = PackedBox::new("USA");
+        box_data.add_item(item("tall", "USA", (20.0, 20.0, 20.0), 1.0), Position::ORIGIN);
+        let access = components(&box_data, &cube(), Position::new(20.0, 0.0, 0.0)).access;
+        assert!((access - 0.5).abs() < 1e-9, "{access}");
+    }
+
+    #[test]
+    fn score_is_the_weighted_sum() {
+        let weights = PlacementWeights {
+            surface_area_increase: 1.0,
+            volume_increase: 2.0,
+            aspect_ratio: 3.0,
+            support: 4.0,
+            height: 5.0,
+            access: 6.0,
+        };
+        let components = PlacementComponents {
+            surface_area_increase: 1.0,
+            volume_increase: 1.0,
+            aspect_ratio: 1.0,
+            support: 1.0,
+            height: 1.0,
+            access: 0.5,
+        };
+        assert_eq!(weights.score(&components), 18.0);
+    }
+
+    // The default weights put the cube beside the base along its length, where the box grows least;
+    // weighing the aspect ratio and height instead puts it beside the base along its width
+    #[test]
+    fn weights_change_the_placement() {
+        let cube_position = |placement_weights| {
+            let items = vec![item("base", "USA", (20.0, 10.0, 10.0), 1.0), cube()];
+            let solution = pack_with_options(items, &PackingOptions { placement_weights, ..Default::default() }).unwrap();
+            assert_eq!(solution.boxes.len(), 1);
+            solution.boxes[0].items.iter().find(|item| item.id == "cube").unwrap().position.unwrap()
+        };
+        assert_eq!(cube_position(Some(PlacementWeights::default())), Position::new(20.0, 0.0, 0.0));
+        let squat = PlacementWeights {
+            surface_area_increase: 0.0,
+            volume_increase: 0.0,
+            aspect_ratio: 1.0,
+            height: 1.0,
+            ..Default::default()
+        };
+        assert_eq!(cube_position(Some(squat)), Position::new(0.0, 10.0, 0.0));
+    }
+}
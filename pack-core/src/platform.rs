@@ -0,0 +1,64 @@
+// What differs between native builds and wasm32-unknown-unknown, where std has no clock and no
+// threads: with the wasm feature the clocks read the browser's Date, and work meant for several
+// threads runs one job after the other. Results don't depend on either, only timings do.
+use std::time::Duration;
+
+pub(crate) type Job<'a, R> = Box<dyn FnOnce() -> R + Send + 'a>;
+
+// Jobs run side by side where threads exist; the results come back in the order of the jobs,
+// and a panic in one is passed on
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn run_all<R: Send>(jobs: Vec<Job<'_, R>>) -> Vec<R> {
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = jobs.into_iter().map(|job| scope.spawn(job)).collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+            .collect()
+    })
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn run_all<R: Send>(jobs: Vec<Job<'_, R>>) -> Vec<R> {
+    jobs.into_iter().map(|job| job()).collect()
+}
+
+#[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
+pub(crate) use std::time::Instant;
+
+#[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
+pub(crate) fn since_epoch() -> Duration {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+pub(crate) fn since_epoch() -> Duration {
+    Duration::from_secs_f64(js_sys::Date::now().max(0.0) / 1000.0)
+}
+
+// Milliseconds since the epoch, with the operations the packer uses of std's Instant
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub(crate) struct Instant(f64);
+
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+impl Instant {
+    pub(crate) fn now() -> Self {
+        Instant(js_sys::Date::now())
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        Duration::from_secs_f64((Instant::now().0 - self.0).max(0.0) / 1000.0)
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+impl std::ops::Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, duration: Duration) -> Instant {
+        Instant(self.0 + duration.as_secs_f64() * 1000.0)
+    }
+}
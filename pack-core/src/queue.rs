@@ -0,0 +1,212 @@
+// Queued packing jobs for several requests at once: each job is a whole pack_items call, run on a
+// small pool of worker threads and polled by id. The queue lives in the app, not the webview, so
+// a reload loses nothing; finished results wait until fetched or cleared.
+use crate::{try_pack_with_options, validate_request, Item, PackError, PackingOptions, PackingSolution};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    // Share of the destinations packed, 0..1; any time-budgeted search runs after it reaches 1
+    Running { progress: f64 },
+    Done,
+    Failed { error: PackError },
+    Cancelled,
+}
+
+struct QueuedJob {
+    status: JobStatus,
+    // Taken by the worker that runs the job
+    request: Option<(Vec<Item>, PackingOptions)>,
+    result: Option<PackingSolution>,
+}
+
+struct Queue {
+    jobs: HashMap<String, QueuedJob>,
+    pending: VecDeque<String>,
+    // Worker threads alive
+    workers: usize,
+    concurrency: usize,
+    next_id: u64,
+}
+
+// At most `concurrency` jobs pack at a time, half the cores unless set
+pub struct QueueState {
+    queue: Arc<Mutex<Queue>>,
+}
+
+impl Default for QueueState {
+    fn default() -> Self {
+        let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+        QueueState {
+            queue: Arc::new(Mutex::new(Queue {
+                jobs: HashMap::new(),
+                pending: VecDeque::new(),
+                workers: 0,
+                concurrency: (cores / 2).max(1),
+                next_id: 0,
+            })),
+        }
+    }
+}
+
+// Takes the share of the job done, 0..1
+type Reporter = Box<dyn Fn(f64)>;
+
+thread_local! {
+    static REPORTER: RefCell<Option<Reporter>> = const { RefCell::new(None) };
+}
+
+// Called by the packer after each destination of a pass; only a queue worker listens
+pub(crate) fn report_progress(done: usize, total: usize) {
+    REPORTER.with(|reporter| {
+        if let Some(report) = reporter.borrow().as_ref() {
+            report(if total > 0 { done as f64 / total as f64 } else { 1.0 });
+        }
+    });
+}
+
+fn unavailable() -> PackError {
+    PackError::InternalError {
+        message: "job queue unavailable".to_string(),
+    }
+}
+
+// Run jobs off the queue until it is empty or there are more workers than allowed
+fn work(queue: Arc<Mutex<Queue>>) {
+    loop {
+        let (job_id, items, options) = {
+            let Ok(mut state) = queue.lock() else {
+                return;
+            };
+            let next = if state.workers > state.concurrency { None } else { state.pending.pop_front() };
+            let Some(job_id) = next else {
+                state.workers -= 1;
+                return;
+            };
+            let Some(job) = state.jobs.get_mut(&job_id) else {
+                continue;
+            };
+            let Some((items, options)) = job.request.take() else {
+                continue;
+            };
+            job.status = JobStatus::Running { progress: 0.0 };
+            (job_id, items, options)
+        };
+
+        let progress_queue = Arc::clone(&queue);
+        let progress_id = job_id.clone();
+        REPORTER.with(|reporter| {
+            *reporter.borrow_mut() = Some(Box::new(move |fraction| {
+                let Ok(mut queue) = progress_queue.lock() else {
+                    return;
+                };
+                // Search passes re-pack from the start; progress never goes back
+                if let Some(JobStatus::Running { progress }) = queue.jobs.get_mut(&progress_id).map(|job| &mut job.status) {
+                    *progress = progress.max(fraction);
+                }
+            }))
+        });
+        let result = try_pack_with_options(items, &options);
+        REPORTER.with(|reporter| *reporter.borrow_mut() = None);
+
+        let Ok(mut queue) = queue.lock() else {
+            return;
+        };
+        // Gone or cancelled while running: the result is dropped
+        let Some(job) = queue.jobs.get_mut(&job_id).filter(|job| job.status != JobStatus::Cancelled) else {
+            continue;
+        };
+        match result {
+            Ok(solution) => {
+                job.status = JobStatus::Done;
+                job.result = Some(solution);
+            }
+            Err(error) => job.status = JobStatus::Failed { error },
+        }
+    }
+}
+
+impl QueueState {
+    // Start workers for waiting jobs, up to the concurrency; running workers pick up the next job
+    // themselves
+    fn spawn_workers(&self, queue: &mut Queue) {
+        let mut waiting = queue.pending.len();
+        while queue.workers < queue.concurrency && waiting > 0 {
+            queue.workers += 1;
+            waiting -= 1;
+            let queue = Arc::clone(&self.queue);
+            std::thread::spawn(move || work(queue));
+        }
+    }
+
+    // Validate the request and queue it behind the jobs already waiting
+    pub fn enqueue(&self, items: Vec<Item>, options: PackingOptions) -> Result<String, PackError> {
+        validate_request(&items, &options)?;
+        let mut queue = self.queue.lock().map_err(|_| unavailable())?;
+        queue.next_id += 1;
+        let job_id = format!("queued-{}", queue.next_id);
+        queue.jobs.insert(
+            job_id.clone(),
+            QueuedJob {
+                status: JobStatus::Queued,
+                request: Some((items, options)),
+                result: None,
+            },
+        );
+        queue.pending.push_back(job_id.clone());
+        self.spawn_workers(&mut queue);
+        Ok(job_id)
+    }
+
+    // None for an unknown job or one already fetched or cleared
+    pub fn status(&self, job_id: &str) -> Option<JobStatus> {
+        let queue = self.queue.lock().ok()?;
+        queue.jobs.get(job_id).map(|job| job.status.clone())
+    }
+
+    // The solution of a finished job, handed over once; the job is forgotten after it
+    pub fn take_result(&self, job_id: &str) -> Option<PackingSolution> {
+        let mut queue = self.queue.lock().ok()?;
+        if queue.jobs.get(job_id)?.status != JobStatus::Done {
+            return None;
+        }
+        queue.jobs.remove(job_id).and_then(|job| job.result)
+    }
+
+    // A waiting job never starts; a running one finishes but its result is dropped. False for an
+    // unknown job or one that already ended.
+    pub fn cancel(&self, job_id: &str) -> bool {
+        let Ok(mut queue) = self.queue.lock() else {
+            return false;
+        };
+        let Some(job) = queue.jobs.get_mut(job_id) else {
+            return false;
+        };
+        if !matches!(job.status, JobStatus::Queued | JobStatus::Running { .. }) {
+            return false;
+        }
+        job.status = JobStatus::Cancelled;
+        job.request = None;
+        queue.pending.retain(|id| id != job_id);
+        true
+    }
+
+    // Forget a job whatever its state, cancelling it first
+    pub fn clear(&self, job_id: &str) -> bool {
+        self.cancel(job_id);
+        self.queue.lock().is_ok_and(|mut queue| queue.jobs.remove(job_id).is_some())
+    }
+
+    // Jobs allowed to pack at the same time, at least 1; extra workers stop after their current job
+    pub fn set_concurrency(&self, concurrency: usize) {
+        if let Ok(mut queue) = self.queue.lock() {
+            queue.concurrency = concurrency.max(1);
+            self.spawn_workers(&mut queue);
+        }
+    }
+}
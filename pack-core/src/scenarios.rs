@@ -0,0 +1,112 @@
+// What-if packing: the same items under hypothetical destination limits, to see what a carrier
+// concession would be worth before asking for it. Scenarios pack on their own threads, where there are threads.
+use crate::{
+    find_destination_entry, get_destination_constraints, pack_unchecked, platform, same_destination, score, units,
+    validate_request, DestinationConstraints, Dimensions, Item, PackError, PackingOptions,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Limits to replace for one destination; fields left out keep the destination's own value
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConstraintOverride {
+    pub max_box_dimension: Option<f64>,
+    pub max_box_weight: Option<f64>,
+    pub alternative_dimensions: Option<Dimensions>,
+    pub max_box_value: Option<f64>,
+    pub max_length_plus_girth: Option<f64>,
+    pub max_dimension_sum: Option<f64>,
+    pub max_box_volume: Option<f64>,
+    pub max_weight_spread_kg: Option<f64>,
+    pub headspace_cm: Option<f64>,
+}
+
+impl ConstraintOverride {
+    fn apply(&self, constraints: &mut DestinationConstraints) {
+        if let Some(value) = self.max_box_dimension {
+            constraints.max_box_dimension = value;
+        }
+        if let Some(value) = self.max_box_weight {
+            constraints.max_box_weight = value;
+        }
+        constraints.alternative_dimensions = self.alternative_dimensions.or(constraints.alternative_dimensions);
+        constraints.max_box_value = self.max_box_value.or(constraints.max_box_value);
+        constraints.max_length_plus_girth = self.max_length_plus_girth.or(constraints.max_length_plus_girth);
+        constraints.max_dimension_sum = self.max_dimension_sum.or(constraints.max_dimension_sum);
+        constraints.max_box_volume = self.max_box_volume.or(constraints.max_box_volume);
+        constraints.max_weight_spread_kg = self.max_weight_spread_kg.or(constraints.max_weight_spread_kg);
+        constraints.headspace_cm = self.headspace_cm.or(constraints.headspace_cm);
+    }
+}
+
+// Limits are in cm and kg; carrier limits still apply on top
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScenarioOverride {
+    // Label for the comparison table
+    pub name: Option<String>,
+    // By destination name, spelled any way the packer accepts
+    pub destinations: HashMap<String, ConstraintOverride>,
+}
+
+// Headline numbers of one packing, in the request's units
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioResult {
+    // None for the baseline under the unchanged options
+    pub scenario: Option<ScenarioOverride>,
+    pub box_count: usize,
+    pub total_volume: f64,
+    // Larger of gross and volumetric weight, summed over the boxes
+    pub chargeable_weight: f64,
+    pub unpacked_count: usize,
+}
+
+// The options with the scenario's limits written over the destinations' own
+fn scenario_options(options: &PackingOptions, scenario: &ScenarioOverride) -> PackingOptions {
+    let mut options = options.clone();
+    for (destination, change) in &scenario.destinations {
+        let mut constraints = find_destination_entry(&options.custom_destinations, destination)
+            .cloned()
+            .unwrap_or_else(|| get_destination_constraints(destination, &options));
+        change.apply(&mut constraints);
+        options.custom_destinations.retain(|key, _| !same_destination(key, destination));
+        options.custom_destinations.insert(destination.clone(), constraints);
+    }
+    options
+}
+
+// Items and options are already metric; the figures come back in the caller's units
+fn run(items: Vec<Item>, options: &PackingOptions, caller_units: units::Units, scenario: Option<ScenarioOverride>) -> ScenarioResult {
+    let solution = pack_unchecked(items, options);
+    let divisor = options
+        .score_weights
+        .as_ref()
+        .map_or(score::DEFAULT_VOLUMETRIC_DIVISOR, |weights| weights.volumetric_divisor);
+    let chargeable_weight: f64 = solution.boxes.iter().map(|b| score::chargeable_weight(b, divisor)).sum();
+    ScenarioResult {
+        scenario,
+        box_count: solution.boxes.len(),
+        total_volume: units::volume_from_metric(solution.total_volume, caller_units),
+        chargeable_weight: units::weight_from_metric(chargeable_weight, caller_units),
+        unpacked_count: solution.unpacked_items.len(),
+    }
+}
+
+// The baseline first, then each scenario in the order given; the request is checked as given
+pub fn what_if(mut items: Vec<Item>, scenarios: Vec<ScenarioOverride>, options: &PackingOptions) -> Result<Vec<ScenarioResult>, PackError> {
+    validate_request(&items, options)?;
+    let caller_units = options.units;
+    units::items_to_metric(&mut items, caller_units);
+    let options = PackingOptions {
+        units: units::Units::default(),
+        ..options.clone()
+    };
+
+    let mut jobs: Vec<platform::Job<'_, ScenarioResult>> = vec![Box::new(|| run(items.clone(), &options, caller_units, None))];
+    jobs.extend(scenarios.into_iter().map(|scenario| -> platform::Job<'_, ScenarioResult> {
+        let (items, options) = (items.clone(), scenario_options(&options, &scenario));
+        Box::new(move || run(items, &options, caller_units, Some(scenario)))
+    }));
+    Ok(platform::run_all(jobs))
+}
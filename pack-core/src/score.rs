@@ -0,0 +1,181 @@
+// One comparable number for a solution, lower is better. The time-budgeted search minimizes the
+// same score, so a hand-edited solution can be held against what the packer chased.
+use crate::{stability, validate_request, Objective, PackError, PackedBox, PackingOptions, PackingSolution};
+use serde::{Deserialize, Serialize};
+
+// Parcel services bill the larger of the actual weight and the outer volume over this, cm³ per kg
+pub const DEFAULT_VOLUMETRIC_DIVISOR: f64 = 5000.0;
+
+// Relative importance of each component; 0 leaves it out. Volumes are in cm³, weights in kg.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoreWeights {
+    // Per box
+    pub box_count: f64,
+    // Per cm³ of box volume
+    pub total_volume: f64,
+    // Per kg of chargeable weight
+    pub chargeable_weight: f64,
+    // Per unit of average fill rate short of 1
+    pub fill_rate: f64,
+    // Per unit of average stability score short of 1
+    pub stability: f64,
+    // Per unit of carton cost in fixed-box-catalog mode
+    pub cost: f64,
+    pub volumetric_divisor: f64,
+}
+
+// About one box is worth 100 litres, 20 kg, or a tenth of fill
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        ScoreWeights {
+            box_count: 1.0,
+            total_volume: 1e-5,
+            chargeable_weight: 0.05,
+            fill_rate: 10.0,
+            stability: 1.0,
+            cost: 0.0,
+            volumetric_divisor: DEFAULT_VOLUMETRIC_DIVISOR,
+        }
+    }
+}
+
+impl ScoreWeights {
+    // What the search minimizes without explicit weights: the objective's own measure, with the
+    // runner-up a distant tie-break
+    pub fn for_objective(objective: Objective) -> Self {
+        let none = ScoreWeights {
+            box_count: 0.0,
+            total_volume: 0.0,
+            chargeable_weight: 0.0,
+            fill_rate: 0.0,
+            stability: 0.0,
+            cost: 0.0,
+            volumetric_divisor: DEFAULT_VOLUMETRIC_DIVISOR,
+        };
+        match objective {
+            Objective::FewestBoxes => ScoreWeights {
+                box_count: 1.0,
+                total_volume: 1e-9,
+                ..none
+            },
+            Objective::SmallestVolume => ScoreWeights {
+                total_volume: 1.0,
+                box_count: 1e-3,
+                ..none
+            },
+            Objective::LowestCost => ScoreWeights {
+                cost: 1.0,
+                total_volume: 1e-9,
+                ..none
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScoreComponent {
+    // The measured quantity
+    pub value: f64,
+    // What it adds to the total
+    pub weighted: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScoreBreakdown {
+    // Sum of the weighted components, lower is better
+    pub total: f64,
+    pub box_count: ScoreComponent,
+    pub total_volume: ScoreComponent,
+    pub chargeable_weight: ScoreComponent,
+    // Mean item volume over box volume of the boxes, 0..1
+    pub fill_rate: ScoreComponent,
+    // Mean stability score of the boxes, 0..1
+    pub stability: ScoreComponent,
+    pub cost: ScoreComponent,
+}
+
+// Larger of the gross weight and the volumetric weight of the outer carton
+pub(crate) fn chargeable_weight(box_data: &PackedBox, divisor: f64) -> f64 {
+    let wall = 2.0 * box_data.material.thickness_cm;
+    let outer = (box_data.length + wall) * (box_data.width + wall) * (box_data.height + wall);
+    if divisor > 0.0 { box_data.weight.max(outer / divisor) } else { box_data.weight }
+}
+
+// Weights a score can be computed with: all finite, with a positive divisor
+pub(crate) fn check_weights(weights: &ScoreWeights) -> Result<(), PackError> {
+    let rates = [
+        weights.box_count,
+        weights.total_volume,
+        weights.chargeable_weight,
+        weights.fill_rate,
+        weights.stability,
+        weights.cost,
+    ];
+    if rates.iter().all(|rate| rate.is_finite()) && weights.volumetric_divisor.is_finite() && weights.volumetric_divisor > 0.0 {
+        return Ok(());
+    }
+    Err(PackError::InvalidOption {
+        field: "score_weights".to_string(),
+        reason: "must be finite numbers, with a volumetric_divisor above 0".to_string(),
+    })
+}
+
+fn mean(values: impl Iterator<Item = f64>, count: usize) -> f64 {
+    if count > 0 { values.sum::<f64>() / count as f64 } else { 1.0 }
+}
+
+// Check the weights and every item of the solution as a request would be checked, then score it
+pub fn try_score_solution(solution: &PackingSolution, weights: &ScoreWeights) -> Result<ScoreBreakdown, PackError> {
+    let options = PackingOptions {
+        score_weights: Some(weights.clone()),
+        ..Default::default()
+    };
+    // One by one, as an item turned differently in another box is no duplicate
+    for item in solution.boxes.iter().flat_map(|b| &b.items) {
+        validate_request(std::slice::from_ref(item), &options)?;
+    }
+    Ok(score_solution(solution, weights))
+}
+
+// Expects the solution in cm and kg
+pub fn score_solution(solution: &PackingSolution, weights: &ScoreWeights) -> ScoreBreakdown {
+    let boxes = &solution.boxes;
+    let component = |value: f64, weight: f64| ScoreComponent { value, weighted: weight * value };
+    let shortfall = |value: f64, weight: f64| ScoreComponent {
+        value,
+        weighted: weight * (1.0 - value),
+    };
+
+    let mut breakdown = ScoreBreakdown {
+        total: 0.0,
+        box_count: component(boxes.len() as f64, weights.box_count),
+        total_volume: component(boxes.iter().map(PackedBox::volume).sum(), weights.total_volume),
+        chargeable_weight: component(
+            boxes.iter().map(|b| chargeable_weight(b, weights.volumetric_divisor)).sum(),
+            weights.chargeable_weight,
+        ),
+        fill_rate: shortfall(mean(boxes.iter().map(PackedBox::fill_rate), boxes.len()), weights.fill_rate),
+        // Recomputed when missing, e.g. for a solution edited by hand
+        stability: shortfall(
+            mean(
+                boxes.iter().map(|b| b.stability.as_ref().map_or_else(|| stability::assess(b).score, |s| s.score)),
+                boxes.len(),
+            ),
+            weights.stability,
+        ),
+        cost: component(boxes.iter().filter_map(|b| b.cost).sum(), weights.cost),
+    };
+    breakdown.total = [
+        &breakdown.box_count,
+        &breakdown.total_volume,
+        &breakdown.chargeable_weight,
+        &breakdown.fill_rate,
+        &breakdown.stability,
+        &breakdown.cost,
+    ]
+    .iter()
+    .map(|c| c.weighted)
+    .sum();
+    breakdown
+}
@@ -0,0 +1,248 @@
+// Anytime improvement of the greedy result within a caller-given time budget
+use crate::platform::{self, Instant};
+use crate::debug_log;
+use crate::score::{score_solution, ScoreWeights};
+use crate::{
+    box_constraints, find_best_position_with_rotation, pack_in_order, same_box_group, DestinationConstraints, Item, PackedBox,
+    PackingOptions, PackingSolution, REPACK_ORDERS,
+};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::time::Duration;
+
+// Local search stops after this many attempts in a row without an improvement
+const MAX_STALE_ATTEMPTS: u32 = 200;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchReport {
+    pub budget_ms: u64,
+    pub elapsed_ms: u64,
+    // Improvement attempts that ran after the greedy pass
+    pub iterations: u32,
+    // Whether the deadline cut the search short
+    pub budget_exhausted: bool,
+    // Whether the returned solution is better than the greedy one
+    pub improved: bool,
+    // Seed the local search ran with, PackingOptions.seed or the one drawn for the request
+    pub seed: u64,
+}
+
+// Lower is better: unpacked items always count first, then the solution score
+fn cost(solution: &PackingSolution, weights: &ScoreWeights) -> [f64; 2] {
+    [solution.unpacked_items.len() as f64, score_solution(solution, weights).total]
+}
+
+fn is_better(candidate: &PackingSolution, best: &PackingSolution, weights: &ScoreWeights) -> bool {
+    cost(candidate, weights).partial_cmp(&cost(best, weights)) == Some(Ordering::Less)
+}
+
+// Small deterministic generator so a given request always searches the same way
+pub(crate) struct XorShift(u64);
+
+impl XorShift {
+    // Any seed works: it is scrambled first (splitmix64) so small seeds don't start with small
+    // numbers, and a zero state, which would stay zero forever, is avoided
+    pub(crate) fn new(seed: u64) -> Self {
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        XorShift(if z == 0 { 0x9e37_79b9_7f4a_7c15 } else { z })
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    // Seed for a request without one, different from call to call
+    fn fresh_seed() -> u64 {
+        let nanos = platform::since_epoch().as_nanos() as u64;
+        XorShift::new(nanos).next()
+    }
+
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+
+    // Uniform in [0, 1)
+    pub(crate) fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+// Move every item of the source box into the other boxes of its group and drop the box; the
+// boxes stay as they were if an item fits nowhere else or the deadline passes first
+pub(crate) fn empty_box(boxes: &mut Vec<PackedBox>, source: usize, options: &PackingOptions, deadline: Option<Instant>) -> bool {
+    let mut others: Vec<PackedBox> = Vec::new();
+    let mut other_indices = Vec::new();
+    for (index, box_data) in boxes.iter().enumerate() {
+        if index != source && same_box_group(box_data, &boxes[source], options) {
+            others.push(box_data.clone());
+            other_indices.push(index);
+        }
+    }
+    if others.is_empty() {
+        return false;
+    }
+    let limits: Vec<DestinationConstraints> = others.iter().map(|target| box_constraints(target, options)).collect();
+
+    let moved_all = boxes[source].items.iter().all(|item| {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return false;
+        }
+        others.iter_mut().zip(&limits).any(|(target, limits)| {
+            match find_best_position_with_rotation(target, item, limits, options) {
+                Some((position, rotated_item)) => target.add_item(rotated_item, position),
+                None => false,
+            }
+        })
+    });
+
+    if moved_all {
+        for (index, target) in other_indices.into_iter().zip(others) {
+            boxes[index] = target;
+        }
+        boxes.remove(source);
+    }
+    moved_all
+}
+
+// Try to empty the box with the fewest items into the other boxes of its destination
+pub(crate) fn consolidate_once(boxes: &mut Vec<PackedBox>, options: &PackingOptions, deadline: Option<Instant>) -> bool {
+    let mut order: Vec<usize> = (0..boxes.len()).collect();
+    order.sort_by_key(|&i| boxes[i].items.len());
+    order.into_iter().any(|source| empty_box(boxes, source, options, deadline))
+}
+
+fn consolidate(mut solution: PackingSolution, options: &PackingOptions, deadline: Instant) -> PackingSolution {
+    while Instant::now() < deadline && consolidate_once(&mut solution.boxes, options, Some(deadline)) {}
+    solution.total_volume = solution.boxes.iter().map(PackedBox::volume).sum();
+    solution
+}
+
+// Improve on the greedy solution until the budget runs out, returning the best one seen.
+// `items` is the order the greedy pass used.
+pub fn improve_within_budget(
+    greedy: PackingSolution,
+    items: Vec<Item>,
+    options: &PackingOptions,
+    budget_ms: u64,
+) -> PackingSolution {
+    let start = Instant::now();
+    let deadline = start + Duration::from_millis(budget_ms);
+    let weights = options
+        .score_weights
+        .clone()
+        .unwrap_or_else(|| ScoreWeights::for_objective(options.objective));
+
+    let mut best = greedy.clone();
+    let mut best_order = items;
+    let mut iterations = 0;
+
+    let _span = debug_log::span(debug_log::INFO, || format!("search within {} ms", budget_ms));
+    // Swaps are logged a level down, there are many more of them than of the other passes
+    let consider = |pass: &dyn Fn() -> String, level: u8, candidate: PackingSolution, order: Vec<Item>, best: &mut PackingSolution, best_order: &mut Vec<Item>| {
+        let candidate = debug_log::muted(|| consolidate(candidate, options, deadline));
+        debug_log::log(level, || {
+            format!(
+                "{}: {} boxes, cost {:?} against the best {:?}",
+                pass(),
+                candidate.boxes.len(),
+                cost(&candidate, &weights),
+                cost(best, &weights)
+            )
+        });
+        if is_better(&candidate, best, &weights) {
+            *best = candidate;
+            *best_order = order;
+            true
+        } else {
+            false
+        }
+    };
+
+    // Consolidating the greedy result itself
+    if Instant::now() < deadline {
+        iterations += 1;
+        let pass = || "consolidated greedy solution".to_string();
+        consider(&pass, debug_log::INFO, greedy.clone(), best_order.clone(), &mut best, &mut best_order);
+    }
+
+    // Alternate sort orders
+    for (index, key) in REPACK_ORDERS.iter().enumerate() {
+        if Instant::now() >= deadline {
+            break;
+        }
+        iterations += 1;
+        let mut order = best_order.clone();
+        order.sort_by(|a, b| key(b).partial_cmp(&key(a)).unwrap_or(Ordering::Equal));
+        let candidate = debug_log::muted(|| pack_in_order(order.clone(), options));
+        let pass = || format!("alternate order {}", index + 1);
+        consider(&pass, debug_log::INFO, candidate, order, &mut best, &mut best_order);
+    }
+
+    // Local search: swap two items in the best order found so far and re-pack
+    let seed = options.seed.unwrap_or_else(XorShift::fresh_seed);
+    let mut random = XorShift::new(seed);
+    let mut stale = 0;
+    while best_order.len() > 1 && stale < MAX_STALE_ATTEMPTS && Instant::now() < deadline {
+        iterations += 1;
+        let mut order = best_order.clone();
+        let a = random.below(order.len());
+        let b = random.below(order.len());
+        order.swap(a, b);
+        let candidate = debug_log::muted(|| pack_in_order(order.clone(), options));
+        let swapped = debug_log::enabled(debug_log::DEBUG).then(|| (order[a].id.clone(), order[b].id.clone()));
+        let pass = || swapped.as_ref().map_or_else(String::new, |(a, b)| format!("swapped {} and {}", a, b));
+        if consider(&pass, debug_log::DEBUG, candidate, order, &mut best, &mut best_order) {
+            stale = 0;
+        } else {
+            stale += 1;
+        }
+    }
+
+    best.search = Some(SearchReport {
+        budget_ms,
+        elapsed_ms: start.elapsed().as_millis() as u64,
+        iterations,
+        budget_exhausted: Instant::now() >= deadline,
+        improved: is_better(&best, &greedy, &weights),
+        seed,
+    });
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::item;
+    use crate::{pack_with_options, Item, PackingOptions};
+
+    // The budget is long enough for the search to go stale first, so only the clock differs
+    fn seeded_run(items: &[Item], seed: Option<u64>) -> (u64, String) {
+        let options = PackingOptions { time_budget_ms: Some(20_000), seed, ..Default::default() };
+        let mut solution = pack_with_options(items.to_vec(), &options).unwrap();
+        let report = solution.search.as_mut().unwrap();
+        report.elapsed_ms = 0;
+        (report.seed, serde_json::to_string(&solution).unwrap())
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_solution() {
+        let items: Vec<Item> = (0..16)
+            .map(|i| {
+                let sides = (5.0 + (i * 7 % 23) as f64, 4.0 + (i * 5 % 17) as f64, 3.0 + (i * 3 % 11) as f64);
+                item(&format!("I{i}"), "USA", sides, 0.5)
+            })
+            .collect();
+        let (seed, first) = seeded_run(&items, Some(42));
+        assert_eq!(seed, 42);
+        assert_eq!(first, seeded_run(&items, Some(42)).1);
+
+        // A drawn seed is reported, and reproduces its solution
+        let (drawn, unseeded) = seeded_run(&items, None);
+        assert_eq!(seeded_run(&items, Some(drawn)).1, unseeded);
+    }
+}
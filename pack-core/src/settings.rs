@@ -0,0 +1,36 @@
+// Operator preferences persisted between sessions
+use crate::PackingOptions;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub const SETTINGS_FILE: &str = "settings.json";
+
+// Everything the operator sets once per workstation. The packing options carry the
+// strategy knobs together with the material spec, unit preferences, and custom
+// destination constraints, so a saved file can be used as a request's options as-is.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub options: PackingOptions,
+}
+
+// Read saved settings; a missing or corrupt file yields the defaults instead of an error
+pub fn load_settings_file(path: &Path) -> AppSettings {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(_) => return AppSettings::default(),
+    };
+
+    serde_json::from_str(&text).unwrap_or_else(|error| {
+        eprintln!("Ignoring unreadable settings file {}: {}", path.display(), error);
+        AppSettings::default()
+    })
+}
+
+pub fn save_settings_file(path: &Path, settings: &AppSettings) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| e.to_string())
+}
@@ -0,0 +1,190 @@
+// Maximal empty spaces of a box for Strategy::FreeSpaces: every largest axis-aligned block not
+// taken by an item, so a pocket between items shows up as a place to put something. The box may
+// still grow, so the spaces are open-ended away from the origin and the placement check decides
+// what the limits allow. Built from the items on the first lookup, then split by
+// PackedBox::add_item as items go in.
+use crate::{Item, Position};
+use std::sync::OnceLock;
+
+// Thinner than this is no space at all
+const SLIVER: f64 = 1e-6;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Space {
+    min: [f64; 3],
+    max: [f64; 3],
+}
+
+impl Space {
+    fn contains(&self, other: &Space) -> bool {
+        (0..3).all(|axis| self.min[axis] <= other.min[axis] && other.max[axis] <= self.max[axis])
+    }
+
+    fn overlaps(&self, other: &Space) -> bool {
+        (0..3).all(|axis| self.min[axis] < other.max[axis] - SLIVER && other.min[axis] < self.max[axis] - SLIVER)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Spaces {
+    list: Vec<Space>,
+    // Items accounted for, from the start of the box's item list
+    len: usize,
+}
+
+impl Spaces {
+    fn build(items: &[Item]) -> Self {
+        let mut spaces = Spaces {
+            list: vec![Space {
+                min: [0.0; 3],
+                max: [f64::INFINITY; 3],
+            }],
+            len: 0,
+        };
+        for item in items {
+            spaces.push(item);
+        }
+        spaces
+    }
+
+    // Carve the item out of every space it cuts into, keeping only the maximal leftovers
+    fn push(&mut self, item: &Item) {
+        self.len += 1;
+        let Some(Position { x, y, z }) = item.position else {
+            return;
+        };
+        let taken = Space {
+            min: [x, y, z],
+            max: [x + item.length, y + item.width, z + item.height],
+        };
+
+        let (cut, kept): (Vec<Space>, Vec<Space>) = self.list.iter().partition(|space| space.overlaps(&taken));
+        let mut pieces: Vec<Space> = Vec::new();
+        for space in cut {
+            for axis in 0..3 {
+                if taken.min[axis] > space.min[axis] + SLIVER {
+                    let mut below = space;
+                    below.max[axis] = taken.min[axis];
+                    pieces.push(below);
+                }
+                if taken.max[axis] < space.max[axis] - SLIVER {
+                    let mut above = space;
+                    above.min[axis] = taken.max[axis];
+                    pieces.push(above);
+                }
+            }
+        }
+
+        // Untouched spaces were maximal already, so only the new pieces can be inside another
+        let mut maximal: Vec<Space> = Vec::with_capacity(pieces.len());
+        for (index, piece) in pieces.iter().enumerate() {
+            let inside_other = kept.iter().any(|space| space.contains(piece)) ||
+                pieces
+                    .iter()
+                    .enumerate()
+                    .any(|(other, space)| other != index && space.contains(piece) && (space != piece || other < index));
+            if !inside_other {
+                maximal.push(*piece);
+            }
+        }
+        self.list = kept;
+        self.list.extend(maximal);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FreeSpaces(OnceLock<Spaces>);
+
+impl FreeSpaces {
+    // Split the spaces, if there are any yet, by the item just added to the end of the list
+    pub(crate) fn push(&mut self, item: &Item) {
+        if let Some(spaces) = self.0.get_mut() {
+            spaces.push(item);
+        }
+    }
+
+    // Forget the spaces, after items were moved or removed
+    pub(crate) fn reset(&mut self) {
+        self.0 = OnceLock::new();
+    }
+
+    // The corner nearest the origin of every free space
+    pub(crate) fn corners(&self, items: &[Item]) -> Vec<Position> {
+        let corners = |spaces: &Spaces| spaces.list.iter().map(|space| Position::from_array(space.min)).collect();
+        let spaces = self.0.get_or_init(|| Spaces::build(items));
+        if spaces.len == items.len() {
+            corners(spaces)
+        } else {
+            // The list changed behind the spaces' back
+            corners(&Spaces::build(items))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::item;
+    use crate::{pack_with_options, try_pack_with_options, DestinationConstraints, PackingOptions, Strategy};
+    use std::collections::HashMap;
+
+    fn placed(id: &str, sides: (f64, f64, f64), position: Position) -> Item {
+        Item {
+            position: Some(position),
+            ..item(id, "Test", sides, 1.0)
+        }
+    }
+
+    // Two items along the x and y walls leave a 10 cm pocket in the corner between them
+    #[test]
+    fn a_pocket_between_items_is_a_free_space() {
+        let items = vec![
+            placed("along-x", (20.0, 10.0, 10.0), Position::ORIGIN),
+            placed("along-y", (10.0, 20.0, 10.0), Position::new(0.0, 10.0, 0.0)),
+        ];
+        let spaces = Spaces::build(&items);
+        let pocket = Space {
+            min: [10.0, 10.0, 0.0],
+            max: [f64::INFINITY, f64::INFINITY, f64::INFINITY],
+        };
+        assert!(spaces.list.contains(&pocket), "{:?}", spaces.list);
+        // None of the spaces reaches into an item
+        for item in &items {
+            let Position { x, y, z } = item.position.unwrap();
+            let taken = Space {
+                min: [x, y, z],
+                max: [x + item.length, y + item.width, z + item.height],
+            };
+            assert!(spaces.list.iter().all(|space| !space.overlaps(&taken)), "{:?}", spaces.list);
+        }
+        assert!(FreeSpaces::default().corners(&items).contains(&Position::new(10.0, 10.0, 0.0)));
+    }
+
+    // The fourth item needs a second box from the extreme points, and goes in the pocket the first
+    // three leave with free spaces
+    #[test]
+    fn free_spaces_fill_a_pocket_extreme_points_miss() {
+        let options = PackingOptions {
+            grid_min_items: Some(0),
+            verify: Some(true),
+            custom_destinations: HashMap::from([(
+                "Test".to_string(),
+                DestinationConstraints {
+                    max_box_dimension: 30.0,
+                    max_box_weight: 1000.0,
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        };
+        let items = vec![
+            item("A", "Test", (20.0, 20.0, 5.0), 1.0),
+            item("B", "Test", (15.0, 30.0, 15.0), 1.0),
+            item("C", "Test", (25.0, 10.0, 25.0), 1.0),
+            item("D", "Test", (20.0, 25.0, 5.0), 1.0),
+        ];
+        assert_eq!(pack_with_options(items.clone(), &options).unwrap().boxes.len(), 2);
+        let solution = try_pack_with_options(items, &PackingOptions { strategy: Strategy::FreeSpaces, ..options }).unwrap();
+        assert_eq!((solution.boxes.len(), solution.packed_count), (1, 4));
+    }
+}
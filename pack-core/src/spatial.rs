@@ -0,0 +1,105 @@
+// Items of a box bucketed by the grid cells their extent covers, so a collision test only looks at
+// the items near the candidate instead of every item in the box. Built from the items on the
+// first lookup, so boxes that were cloned or deserialized start without one, then kept up by
+// PackedBox::add_item.
+use crate::{Item, Position};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+// Below this many items scanning them all is cheaper than the lookup
+const MIN_INDEXED_ITEMS: usize = 32;
+
+type Cell = (i64, i64, i64);
+
+#[derive(Debug, Default)]
+pub(crate) struct SpatialIndex {
+    // About the size of the items, so each covers only a few cells
+    cell: f64,
+    cells: HashMap<Cell, Vec<usize>>,
+    // Items indexed, from the start of the box's item list
+    len: usize,
+}
+
+impl SpatialIndex {
+    fn build(items: &[Item]) -> Self {
+        let sizes: f64 = items.iter().map(|item| item.length.max(item.width).max(item.height)).sum();
+        let mut index = SpatialIndex {
+            cell: (sizes / items.len().max(1) as f64).max(1.0),
+            ..Default::default()
+        };
+        for item in items {
+            index.push(item);
+        }
+        index
+    }
+
+    // Cells from the one holding the start to the one holding the end, both included
+    fn cells(&self, position: Position, (length, width, height): (f64, f64, f64)) -> impl Iterator<Item = Cell> {
+        let span = |start: f64, size: f64| (start / self.cell).floor() as i64..=((start + size) / self.cell).floor() as i64;
+        let (xs, ys, zs) = (span(position.x, length), span(position.y, width), span(position.z, height));
+        xs.flat_map(move |x| {
+            let zs = zs.clone();
+            ys.clone().flat_map(move |y| zs.clone().map(move |z| (x, y, z)))
+        })
+    }
+
+    // Index the item that just went to the end of the box's list
+    fn push(&mut self, item: &Item) {
+        if let Some(position) = item.position {
+            let cells: Vec<Cell> = self.cells(position, (item.length, item.width, item.height)).collect();
+            for cell in cells {
+                self.cells.entry(cell).or_default().push(self.len);
+            }
+        }
+        self.len += 1;
+    }
+
+    // Indices of the items that may overlap the space; an item spanning several cells comes up
+    // once for each
+    fn near(&self, position: Position, size: (f64, f64, f64)) -> impl Iterator<Item = usize> + '_ {
+        self.cells(position, size)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+    }
+}
+
+// The index of one box; a clone starts empty, since copies are mostly made to try a single item
+#[derive(Debug, Default)]
+pub(crate) struct LazyIndex(OnceLock<SpatialIndex>);
+
+impl Clone for LazyIndex {
+    fn clone(&self) -> Self {
+        LazyIndex::default()
+    }
+}
+
+impl LazyIndex {
+    // Keep a built index up with the item just added to the end of the list
+    pub(crate) fn push(&mut self, item: &Item) {
+        if let Some(index) = self.0.get_mut() {
+            index.push(item);
+        }
+    }
+
+    // Forget the index, after items were moved or removed
+    pub(crate) fn reset(&mut self) {
+        self.0 = OnceLock::new();
+    }
+
+    // Whether any item that may overlap the space passes the test, or None when a scan of all
+    // items is cheaper or the index has fallen behind the list
+    pub(crate) fn any_near(
+        &self,
+        items: &[Item],
+        position: Position,
+        size: (f64, f64, f64),
+        test: impl Fn(&Item) -> bool,
+    ) -> Option<bool> {
+        if items.len() < MIN_INDEXED_ITEMS {
+            return None;
+        }
+        let index = self.0.get_or_init(|| SpatialIndex::build(items));
+        (index.len == items.len()).then(|| index.near(position, size).any(|slot| test(&items[slot])))
+    }
+}
@@ -0,0 +1,71 @@
+// How steady a packed box is, for the operator deciding where extra void fill or a re-pack is
+// needed. Reported only; placement never depends on it.
+use crate::{footprints_overlap, overhang, Item, PackedBox, Position, CONTACT_EPSILON};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Stability {
+    // Mean of the three components below, each turned so that 1 is steady
+    pub score: f64,
+    // Share of the items standing on the floor or fully on other items
+    pub supported_fraction: f64,
+    // Height of the center of gravity over the box height, 0..1 (lower is steadier)
+    pub center_of_gravity: f64,
+    // Share of the items resting on another that are no heavier than what they rest on; 1 when nothing is stacked
+    pub weight_order: f64,
+}
+
+pub fn assess(box_data: &PackedBox) -> Stability {
+    let placed: Vec<_> = box_data.items.iter().filter_map(|item| item.position.map(|p| (item, p))).collect();
+    if placed.is_empty() {
+        return Stability {
+            score: 1.0,
+            supported_fraction: 1.0,
+            center_of_gravity: 0.0,
+            weight_order: 1.0,
+        };
+    }
+
+    let supported = placed
+        .iter()
+        .filter(|(item, position)| position.z <= CONTACT_EPSILON || overhang(box_data, item, *position) <= CONTACT_EPSILON)
+        .count();
+    let supported_fraction = supported as f64 / placed.len() as f64;
+
+    // Weighted by item weight, falling back to volume for weightless items
+    let by_weight = box_data.items_weight() > 0.0;
+    let mass = |item: &Item| if by_weight { item.weight } else { item.volume() };
+    let total_mass: f64 = placed.iter().map(|(item, _)| mass(item)).sum();
+    let center_of_gravity = if total_mass > 0.0 && box_data.height > 0.0 {
+        let moment: f64 = placed.iter().map(|(item, position)| mass(item) * (position.z + item.height / 2.0)).sum();
+        (moment / total_mass / box_data.height).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    let mut resting = 0;
+    let mut in_order = 0;
+    for (upper, Position { x: ux, y: uy, z: uz }) in &placed {
+        let below: Vec<_> = placed
+            .iter()
+            .filter(|(lower, Position { x: lx, y: ly, z: lz })| {
+                (lz + lower.height - uz).abs() <= CONTACT_EPSILON && footprints_overlap(upper, (*ux, *uy), lower, (*lx, *ly))
+            })
+            .collect();
+        if below.is_empty() {
+            continue;
+        }
+        resting += 1;
+        if below.iter().all(|(lower, _)| upper.weight <= lower.weight + CONTACT_EPSILON) {
+            in_order += 1;
+        }
+    }
+    let weight_order = if resting > 0 { in_order as f64 / resting as f64 } else { 1.0 };
+
+    Stability {
+        score: (supported_fraction + (1.0 - center_of_gravity) + weight_order) / 3.0,
+        supported_fraction,
+        center_of_gravity,
+        weight_order,
+    }
+}
@@ -0,0 +1,88 @@
+// Timing and work counters for a packing run, collected when PackingOptions.collect_stats is set.
+// Counters live in a thread-local that is empty unless a run asked for them, so the placement
+// hot path only pays for a thread-local lookup.
+use serde::{Deserialize, Serialize};
+use crate::auto::Heuristic;
+use crate::platform::Instant;
+use std::cell::RefCell;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DestinationTiming {
+    pub destination: String,
+    // Summed over every packing pass, the time-budgeted search included
+    pub elapsed_ms: f64,
+    // What Strategy::Auto chose for it, None under any other strategy
+    #[serde(default)]
+    pub heuristic: Option<Heuristic>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PackStats {
+    pub elapsed_ms: f64,
+    pub destinations: Vec<DestinationTiming>,
+    // Positions checked for an item
+    pub candidate_positions: u64,
+    // Orientations tried for an item in a box
+    pub rotation_trials: u64,
+    pub boxes_opened: u64,
+    // Positions rejected by each check
+    pub rejected_bounds: u64,
+    pub rejected_collision: u64,
+    // Overhang, stacking height, and liquid or squashed-item rules
+    pub rejected_support: u64,
+    pub rejected_weight: u64,
+    // Declared value cap
+    #[serde(default)]
+    pub rejected_value: u64,
+    // Hazard classes that may not be packed together, and the hazmat quantity cap
+    #[serde(default)]
+    pub rejected_hazmat: u64,
+    // Boxes not searched for an item because an identical one found no place in them as they were
+    #[serde(default)]
+    pub class_skips: u64,
+    // Boxes not searched for an item because they were closed as too full for any item left
+    #[serde(default)]
+    pub closed_skips: u64,
+    // Distinct destinations of the request, spellings of one counted once
+    #[serde(default)]
+    pub destination_count: usize,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<PackStats>> = const { RefCell::new(None) };
+}
+
+// Start collecting on this thread, discarding anything left from an earlier run
+pub(crate) fn begin() {
+    CURRENT.with(|current| *current.borrow_mut() = Some(PackStats::default()));
+}
+
+// Stop collecting and return what was counted
+pub(crate) fn finish(started: Instant) -> Option<PackStats> {
+    let mut stats = CURRENT.with(|current| current.borrow_mut().take())?;
+    stats.elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+    Some(stats)
+}
+
+// Update the counters if a run on this thread is collecting them
+pub(crate) fn record(update: impl FnOnce(&mut PackStats)) {
+    CURRENT.with(|current| {
+        if let Some(stats) = current.borrow_mut().as_mut() {
+            update(stats);
+        }
+    });
+}
+
+pub(crate) fn record_destination(destination: &str, heuristic: Option<Heuristic>, started: Instant) {
+    record(|stats| {
+        let elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+        match stats.destinations.iter_mut().find(|timing| timing.destination == destination) {
+            Some(timing) => timing.elapsed_ms += elapsed_ms,
+            None => stats.destinations.push(DestinationTiming {
+                destination: destination.to_string(),
+                elapsed_ms,
+                heuristic,
+            }),
+        }
+    });
+}
@@ -0,0 +1,454 @@
+// Unit conversion at the command boundary; the packer itself always works in cm and kg
+use crate::{diagnostics, DestinationConstraints, Item, PackedBox, PackingSolution, Position};
+use serde::{Deserialize, Serialize};
+
+const CM_PER_INCH: f64 = 2.54;
+const KG_PER_POUND: f64 = 0.453_592_37;
+const KG_PER_GRAM: f64 = 0.001;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LengthUnit {
+    #[default]
+    Cm,
+    In,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WeightUnit {
+    #[default]
+    Kg,
+    G,
+    Lb,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Units {
+    pub length: LengthUnit,
+    pub weight: WeightUnit,
+}
+
+impl LengthUnit {
+    // Factor converting a value in this unit to centimetres
+    pub(crate) fn cm_factor(self) -> f64 {
+        match self {
+            LengthUnit::Cm => 1.0,
+            LengthUnit::In => CM_PER_INCH,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LengthUnit::Cm => "cm",
+            LengthUnit::In => "in",
+        }
+    }
+}
+
+impl WeightUnit {
+    // Factor converting a value in this unit to kilograms
+    pub(crate) fn kg_factor(self) -> f64 {
+        match self {
+            WeightUnit::Kg => 1.0,
+            WeightUnit::G => KG_PER_GRAM,
+            WeightUnit::Lb => KG_PER_POUND,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            WeightUnit::Kg => "kg",
+            WeightUnit::G => "g",
+            WeightUnit::Lb => "lb",
+        }
+    }
+
+    // Decimal places used for serialized weights unless the caller overrides it
+    pub fn default_precision(self) -> u32 {
+        match self {
+            WeightUnit::G => 0,
+            WeightUnit::Kg | WeightUnit::Lb => 3,
+        }
+    }
+}
+
+impl Units {
+    pub fn is_metric(&self) -> bool {
+        self.length == LengthUnit::Cm && self.weight == WeightUnit::Kg
+    }
+}
+
+// What a limit named in a constraint gap or a limit warning measures
+#[derive(Clone, Copy, PartialEq)]
+enum Measure {
+    Length,
+    Volume,
+    Weight,
+    Value,
+}
+
+fn measure_of(constraint: &str) -> Measure {
+    match constraint {
+        "max_box_volume" => Measure::Volume,
+        "max_box_weight" => Measure::Weight,
+        "max_box_value" => Measure::Value,
+        _ => Measure::Length,
+    }
+}
+
+fn scale_triple(triple: (f64, f64, f64), factor: f64) -> (f64, f64, f64) {
+    (triple.0 * factor, triple.1 * factor, triple.2 * factor)
+}
+
+// Multiply every length and weight of an item by the given factors
+fn scale_item(item: &mut Item, length_factor: f64, weight_factor: f64) {
+    item.length *= length_factor;
+    item.width *= length_factor;
+    item.height *= length_factor;
+    item.weight *= weight_factor;
+    item.position = item.position.map(|p| p.map(|v| v * length_factor));
+    item.original_dimensions = item
+        .original_dimensions
+        .map(|d| d.map(|v| v * length_factor));
+    item.compressible_to_height = item.compressible_to_height.map(|h| h * length_factor);
+    item.nominal_height = item.nominal_height.map(|h| h * length_factor);
+}
+
+// The limits of an exceptional box. The value cap is an amount of money, and the fields named
+// with their unit stay in it, like the box's own headspace_cm.
+fn scale_constraints(limits: &mut DestinationConstraints, length_factor: f64, weight_factor: f64) {
+    limits.max_box_dimension *= length_factor;
+    limits.max_box_weight *= weight_factor;
+    limits.alternative_dimensions = limits.alternative_dimensions.map(|d| d.map(|v| v * length_factor));
+    limits.max_length_plus_girth = limits.max_length_plus_girth.map(|v| v * length_factor);
+    limits.max_dimension_sum = limits.max_dimension_sum.map(|v| v * length_factor);
+    limits.max_box_volume = limits.max_box_volume.map(|v| v * length_factor.powi(3));
+}
+
+fn scale_box(packed_box: &mut PackedBox, length_factor: f64, weight_factor: f64) {
+    packed_box.length *= length_factor;
+    packed_box.width *= length_factor;
+    packed_box.height *= length_factor;
+    packed_box.weight *= weight_factor;
+    packed_box.dunnage_kg *= weight_factor;
+    for count in &mut packed_box.contents_summary {
+        count.total_weight *= weight_factor;
+    }
+    if let Some(grid) = &mut packed_box.grid {
+        grid.cell = scale_triple(grid.cell, length_factor);
+    }
+    if let Some(reserved) = &mut packed_box.reservation {
+        reserved.length *= length_factor;
+        reserved.width *= length_factor;
+        reserved.height *= length_factor;
+        reserved.weight *= weight_factor;
+    }
+    if let Some(limits) = &mut packed_box.override_limits {
+        scale_constraints(limits, length_factor, weight_factor);
+    }
+    for item in &mut packed_box.items {
+        scale_item(item, length_factor, weight_factor);
+    }
+    packed_box.index.reset();
+    packed_box.spaces.reset();
+}
+
+// The rejection diagnostics and limit warnings, their messages rebuilt in `units`
+fn scale_limits(solution: &mut PackingSolution, length_factor: f64, weight_factor: f64, units: Units) {
+    let factor = |constraint: &str| match measure_of(constraint) {
+        Measure::Length => length_factor,
+        Measure::Volume => length_factor.powi(3),
+        Measure::Weight => weight_factor,
+        Measure::Value => 1.0,
+    };
+    for gap in solution.unpacked_details.iter_mut().flat_map(|detail| &mut detail.gaps) {
+        let factor = factor(&gap.constraint);
+        gap.needed *= factor;
+        gap.limit *= factor;
+        gap.message = diagnostics::gap_message(&gap.constraint, gap.needed, gap.limit, units);
+    }
+    for warning in &mut solution.limit_warnings {
+        let factor = factor(&warning.constraint);
+        warning.value *= factor;
+        warning.limit *= factor;
+        warning.headroom *= factor;
+    }
+}
+
+fn scale_volumes(solution: &mut PackingSolution, volume_factor: f64) {
+    solution.total_volume *= volume_factor;
+    solution.wasted_volume *= volume_factor;
+    for summary in &mut solution.destination_summaries {
+        summary.item_volume *= volume_factor;
+        summary.box_volume *= volume_factor;
+        summary.wasted_volume *= volume_factor;
+    }
+}
+
+// Convert request items from the caller's units to cm/kg
+pub fn items_to_metric(items: &mut [Item], units: Units) {
+    if units.is_metric() {
+        return;
+    }
+    let length_factor = units.length.cm_factor();
+    let weight_factor = units.weight.kg_factor();
+    for item in items {
+        scale_item(item, length_factor, weight_factor);
+    }
+}
+
+// Convert a metric solution into the caller's units just before it is returned
+pub fn solution_from_metric(solution: &mut PackingSolution, units: Units) {
+    if units.is_metric() {
+        return;
+    }
+    let length_factor = 1.0 / units.length.cm_factor();
+    let weight_factor = 1.0 / units.weight.kg_factor();
+    for packed_box in &mut solution.boxes {
+        scale_box(packed_box, length_factor, weight_factor);
+    }
+    for item in &mut solution.unpacked_items {
+        scale_item(item, length_factor, weight_factor);
+    }
+    scale_limits(solution, length_factor, weight_factor, units);
+    scale_volumes(solution, length_factor.powi(3));
+}
+
+// Convert a solution handed back by the caller, e.g. edited in the UI, into cm/kg
+pub fn solution_to_metric(solution: &mut PackingSolution, units: Units) {
+    if units.is_metric() {
+        return;
+    }
+    let length_factor = units.length.cm_factor();
+    let weight_factor = units.weight.kg_factor();
+    for packed_box in &mut solution.boxes {
+        scale_box(packed_box, length_factor, weight_factor);
+    }
+    for item in &mut solution.unpacked_items {
+        scale_item(item, length_factor, weight_factor);
+    }
+    scale_limits(solution, length_factor, weight_factor, Units::default());
+    scale_volumes(solution, length_factor.powi(3));
+}
+
+// A cm³ total in the caller's units
+pub(crate) fn volume_from_metric(value: f64, units: Units) -> f64 {
+    value / units.length.cm_factor().powi(3)
+}
+
+// A kg total in the caller's units
+pub(crate) fn weight_from_metric(value: f64, units: Units) -> f64 {
+    value / units.weight.kg_factor()
+}
+
+fn round_to(value: f64, decimals: u32) -> f64 {
+    let factor = 10f64.powi(decimals as i32);
+    (value * factor).round() / factor
+}
+
+fn round_item_weight(item: &mut Item, decimals: u32) {
+    item.weight = round_to(item.weight, decimals);
+}
+
+// Round the gaps and limit warnings of the measures `round` picks
+fn round_limits(solution: &mut PackingSolution, decimals: u32, round: impl Fn(Measure) -> bool) {
+    for gap in solution.unpacked_details.iter_mut().flat_map(|detail| &mut detail.gaps) {
+        if round(measure_of(&gap.constraint)) {
+            gap.needed = round_to(gap.needed, decimals);
+            gap.limit = round_to(gap.limit, decimals);
+        }
+    }
+    for warning in solution.limit_warnings.iter_mut().filter(|warning| round(measure_of(&warning.constraint))) {
+        warning.value = round_to(warning.value, decimals);
+        warning.limit = round_to(warning.limit, decimals);
+        warning.headroom = round_to(warning.headroom, decimals);
+    }
+}
+
+// Round every serialized weight; only ever called on the finished solution, never before
+// the constraint checks, so a box right at the limit can't be mis-accepted
+pub fn round_weights(solution: &mut PackingSolution, decimals: u32) {
+    for packed_box in &mut solution.boxes {
+        packed_box.weight = round_to(packed_box.weight, decimals);
+        packed_box.dunnage_kg = round_to(packed_box.dunnage_kg, decimals);
+        for item in &mut packed_box.items {
+            round_item_weight(item, decimals);
+        }
+        // Net and gross from the rounded weights, the tare what makes up the difference
+        packed_box.gross_weight = packed_box.weight;
+        packed_box.net_weight = round_to(packed_box.items_weight(), decimals);
+        packed_box.tare_weight = round_to(packed_box.gross_weight - packed_box.net_weight, decimals);
+        for count in &mut packed_box.contents_summary {
+            count.total_weight = round_to(count.total_weight, decimals);
+        }
+    }
+    for item in &mut solution.unpacked_items {
+        round_item_weight(item, decimals);
+    }
+    round_limits(solution, decimals, |measure| measure == Measure::Weight);
+}
+
+fn round_triple(triple: (f64, f64, f64), decimals: u32) -> (f64, f64, f64) {
+    (round_to(triple.0, decimals), round_to(triple.1, decimals), round_to(triple.2, decimals))
+}
+
+fn round_item_dimensions(item: &mut Item, decimals: u32) {
+    item.length = round_to(item.length, decimals);
+    item.width = round_to(item.width, decimals);
+    item.height = round_to(item.height, decimals);
+    item.position = item.position.map(|p| p.map(|v| round_to(v, decimals)));
+    item.original_dimensions = item.original_dimensions.map(|d| d.map(|v| round_to(v, decimals)));
+    item.compressible_to_height = item.compressible_to_height.map(|h| round_to(h, decimals));
+    item.nominal_height = item.nominal_height.map(|h| round_to(h, decimals));
+}
+
+// Round every serialized length, position and volume total, on the finished solution only
+// like the weights. A box is never left shorter than the rounded items in it reach, so checking
+// its dimensions against its items can't turn up a mismatch that is only rounding.
+pub fn round_dimensions(solution: &mut PackingSolution, decimals: u32) {
+    for packed_box in &mut solution.boxes {
+        for item in &mut packed_box.items {
+            round_item_dimensions(item, decimals);
+        }
+        let (mut length, mut width, mut height) = round_triple((packed_box.length, packed_box.width, packed_box.height), decimals);
+        for item in &packed_box.items {
+            if let Some(Position { x, y, z }) = item.position {
+                length = length.max(round_to(x + item.length, decimals));
+                width = width.max(round_to(y + item.width, decimals));
+                height = height.max(round_to(z + item.height, decimals));
+            }
+        }
+        (packed_box.length, packed_box.width, packed_box.height) = (length, width, height);
+        if let Some(grid) = &mut packed_box.grid {
+            grid.cell = round_triple(grid.cell, decimals);
+        }
+    }
+    for item in &mut solution.unpacked_items {
+        round_item_dimensions(item, decimals);
+    }
+    round_limits(solution, decimals, |measure| matches!(measure, Measure::Length | Measure::Volume));
+    solution.total_volume = round_to(solution.total_volume, decimals);
+    solution.wasted_volume = round_to(solution.wasted_volume, decimals);
+    for summary in &mut solution.destination_summaries {
+        summary.item_volume = round_to(summary.item_volume, decimals);
+        summary.box_volume = round_to(summary.box_volume, decimals);
+        summary.wasted_volume = round_to(summary.wasted_volume, decimals);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::item;
+    use crate::{pack_with_options, PackingOptions};
+
+    // Net plus tare is the gross weight to the serialized decimals, whatever the units, the
+    // precision and weights binary floating point can't hold, such as 0.1 and 0.2
+    #[test]
+    fn net_and_tare_add_up_to_gross() {
+        let weight_sets: [&[f64]; 5] = [&[0.1, 0.2], &[0.3], &[1.0 / 3.0, 2.0 / 3.0, 0.7], &[0.001, 0.125, 7.77], &[0.0, 0.05]];
+        for weight in [WeightUnit::Kg, WeightUnit::G, WeightUnit::Lb] {
+            for length in [LengthUnit::Cm, LengthUnit::In] {
+                for precision in 0..=4 {
+                    for weights in weight_sets {
+                        let items = weights
+                            .iter()
+                            .enumerate()
+                            .map(|(index, &weight)| item(&format!("I{index}"), "USA", (4.0, 3.0, 2.0 + index as f64), weight))
+                            .collect();
+                        let options = PackingOptions {
+                            units: Units { length, weight },
+                            weight_precision: Some(precision),
+                            ..Default::default()
+                        };
+                        let solution = pack_with_options(items, &options).unwrap();
+                        for packed_box in &solution.boxes {
+                            let case = format!("{weight:?} {length:?} {precision} {weights:?}: {packed_box:?}");
+                            assert_eq!(packed_box.gross_weight, packed_box.weight, "{case}");
+                            assert_eq!(packed_box.net_weight, round_to(packed_box.items_weight(), precision), "{case}");
+                            assert!(packed_box.tare_weight >= 0.0, "{case}");
+                            assert_eq!(round_to(packed_box.net_weight + packed_box.tare_weight, precision), packed_box.gross_weight, "{case}");
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // A 30 in, 60 lb item too long and heavy for its lane: the gaps and their messages come back in
+    // inches and pounds, rounded like the rest of the solution, matching the same item in cm and kg
+    #[test]
+    fn gaps_are_in_the_callers_units() {
+        let limits = crate::DestinationConstraints {
+            max_box_dimension: 63.0,
+            max_box_weight: 20.0,
+            ..Default::default()
+        };
+        let options = PackingOptions {
+            custom_destinations: std::collections::HashMap::from([("Test".to_string(), limits)]),
+            units: Units { length: LengthUnit::In, weight: WeightUnit::Lb },
+            ..Default::default()
+        };
+        let imperial = pack_with_options(vec![item("long", "Test", (30.0, 10.0, 8.0), 60.0)], &options).unwrap();
+        let metric_item = item("long", "Test", (30.0 * CM_PER_INCH, 10.0 * CM_PER_INCH, 8.0 * CM_PER_INCH), 60.0 * KG_PER_POUND);
+        let metric = pack_with_options(vec![metric_item], &PackingOptions { units: Units::default(), ..options.clone() }).unwrap();
+        let (gaps, metric_gaps) = (&imperial.unpacked_details[0].gaps, &metric.unpacked_details[0].gaps);
+        assert_eq!(gaps.len(), 2, "{gaps:?}");
+        for (gap, metric_gap) in gaps.iter().zip(metric_gaps) {
+            let (factor, decimals, label) = match gap.constraint.as_str() {
+                "max_box_weight" => (KG_PER_POUND, 3, " lb "),
+                _ => (CM_PER_INCH, 2, " in "),
+            };
+            // The metric gaps are rounded themselves
+            let close = |value: f64, metric: f64| (value - metric / factor).abs() <= 10f64.powi(-(decimals as i32));
+            assert!(close(gap.needed, metric_gap.needed) && close(gap.limit, metric_gap.limit), "{gap:?} {metric_gap:?}");
+            assert_eq!((gap.needed, gap.limit), (round_to(gap.needed, decimals), round_to(gap.limit, decimals)));
+            assert!(gap.message.contains(label), "{gap:?}");
+        }
+        let dimension = gaps.iter().find(|gap| gap.constraint == "max_box_dimension").unwrap();
+        assert_eq!(dimension.limit, 24.8);
+        assert!(dimension.message.ends_with("in (limit 24.8)"), "{dimension:?}");
+
+        // Within 10% of both limits once it fits, warned about in inches and pounds
+        let near = PackingOptions { safety_margin_pct: Some(10.0), ..options };
+        let packed = pack_with_options(vec![item("near", "Test", (23.5, 10.0, 8.0), 42.0)], &near).unwrap();
+        let limits: Vec<(&str, f64)> = packed.limit_warnings.iter().map(|warning| (warning.constraint.as_str(), warning.limit)).collect();
+        assert_eq!(limits, [("max_box_weight", 44.092), ("max_box_dimension", 24.8)]);
+    }
+
+    // The room held back for reserved items and an exceptional box's limits convert with the rest
+    // of the box, and back again
+    #[test]
+    fn reservation_and_override_limits_convert() {
+        let mut solution = pack_with_options(vec![item("a", "USA", (10.0, 10.0, 10.0), 1.0)], &PackingOptions::default()).unwrap();
+        let reserved = crate::Reservation { length: 25.4, width: 12.7, height: 2.54, weight: KG_PER_POUND, value: 5.0 };
+        let limits = crate::DestinationConstraints {
+            max_box_dimension: 127.0,
+            max_box_weight: 45.359237,
+            max_box_value: Some(300.0),
+            max_box_volume: Some(CM_PER_INCH.powi(3) * 1000.0),
+            headspace_cm: Some(2.0),
+            ..Default::default()
+        };
+        solution.boxes[0].reservation = Some(reserved);
+        solution.boxes[0].override_limits = Some(limits.clone());
+        let imperial = Units { length: LengthUnit::In, weight: WeightUnit::Lb };
+        solution_from_metric(&mut solution, imperial);
+
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        let converted = solution.boxes[0].reservation.unwrap();
+        assert!(close(converted.length, 10.0) && close(converted.width, 5.0) && close(converted.height, 1.0), "{converted:?}");
+        assert!(close(converted.weight, 1.0) && converted.value == 5.0, "{converted:?}");
+        let converted = solution.boxes[0].override_limits.clone().unwrap();
+        assert!(close(converted.max_box_dimension, 50.0) && close(converted.max_box_weight, 100.0), "{converted:?}");
+        assert!(close(converted.max_box_volume.unwrap(), 1000.0), "{converted:?}");
+        assert_eq!((converted.max_box_value, converted.headspace_cm), (Some(300.0), Some(2.0)));
+
+        solution_to_metric(&mut solution, imperial);
+        let back = solution.boxes[0].override_limits.clone().unwrap();
+        assert!(close(back.max_box_dimension, limits.max_box_dimension) && close(back.max_box_weight, limits.max_box_weight));
+        assert!(close(solution.boxes[0].reservation.unwrap().length, reserved.length));
+    }
+}
@@ -0,0 +1,173 @@
+// Audit of a finished solution, checked from the boxes alone: every item placed inside its box,
+// no two items overlapping, and every box within its destination's limits; with check_unpacking
+// also that the items come out in reverse order by lifting each straight up
+use crate::{
+    box_constraints, box_fits_constraints, footprints_overlap, BoundsMode, Item, PackedBox, PackingOptions, PackingSolution, Position,
+    CONTACT_EPSILON,
+};
+use serde::Serialize;
+
+// Slack for float noise in positions that were computed by adding up item sizes
+const EPSILON: f64 = 1e-6;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Violation {
+    // Zero-based index into solution.boxes
+    pub box_index: usize,
+    pub item_ids: Vec<String>,
+    pub message: String,
+}
+
+fn describe(item: &Item) -> String {
+    let Position { x, y, z } = item.position.unwrap_or_default();
+    format!("{} at ({}, {}, {}) sized {}×{}×{}", item.id, x, y, z, item.length, item.width, item.height)
+}
+
+fn overlap(a: &Item, b: &Item) -> bool {
+    let (Some(pa), Some(pb)) = (a.position, b.position) else {
+        return false;
+    };
+    pa.x < pb.x + b.length - EPSILON &&
+    pb.x < pa.x + a.length - EPSILON &&
+    pa.y < pb.y + b.width - EPSILON &&
+    pb.y < pa.y + a.width - EPSILON &&
+    pa.z < pb.z + b.height - EPSILON &&
+    pb.z < pa.z + a.height - EPSILON
+}
+
+// Taking the items out from the last one, each item that has one still in the box over it, with
+// the first such item: (trapped, above) as indices into the box's items
+pub(crate) fn trapped_items(box_data: &PackedBox) -> Vec<(usize, usize)> {
+    let items = &box_data.items;
+    (0..items.len())
+        .rev()
+        .filter_map(|index| {
+            let item = &items[index];
+            let Position { x, y, z } = item.position?;
+            let above = items[..index].iter().position(|other| {
+                other.position.is_some_and(|Position { x: ox, y: oy, z: oz }| {
+                    oz >= z + item.height - EPSILON && footprints_overlap(item, (x, y), other, (ox, oy))
+                })
+            })?;
+            Some((index, above))
+        })
+        .collect()
+}
+
+fn check_box(index: usize, box_data: &PackedBox, options: &PackingOptions, violations: &mut Vec<Violation>) {
+    let label = format!("Box {} ({})", index + 1, box_data.destination);
+    let mut report = |items: &[&Item], message: String| {
+        violations.push(Violation {
+            box_index: index,
+            item_ids: items.iter().map(|item| item.id.clone()).collect(),
+            message: format!("{}: {}", label, message),
+        });
+    };
+
+    // An inf or NaN measure compares as neither bigger nor smaller and would pass every check below
+    let measures = [box_data.volume(), box_data.outer_surface_sqm(), box_data.weight];
+    if !measures.iter().all(|measure| measure.is_finite()) {
+        report(&[], "size or weight is not a finite number".to_string());
+        return;
+    }
+
+    // An item shipping loose is its box, sized as it is and holding nothing else
+    if box_data.ship_loose && box_data.items.len() > 1 {
+        report(&box_data.items.iter().collect::<Vec<_>>(), format!("a loose parcel holds {} items", box_data.items.len()));
+    }
+
+    for item in &box_data.items {
+        if item.ship_loose && !box_data.ship_loose {
+            report(&[item], format!("item {} ships loose but was packed in a carton", item.id));
+        }
+        if !item.ship_loose && box_data.ship_loose {
+            report(&[item], format!("item {} is not shipping loose but makes up a loose parcel", item.id));
+        }
+        // A weight-only box has no geometry to check, only its limits below; its items must stay
+        // without a position and the other boxes' have one, so the two are never mixed
+        if item.dimensions_unreliable && !box_data.weight_only {
+            report(&[item], format!("item {} has unreliable dimensions but was placed by them", item.id));
+        }
+        let Some(Position { x, y, z }) = item.position else {
+            if !box_data.weight_only {
+                report(&[item], format!("item {} has no position", item.id));
+            }
+            continue;
+        };
+        if box_data.weight_only {
+            report(&[item], format!("item {} has a position in a box packed by weight alone", item.id));
+            continue;
+        }
+        let start = [x, y, z];
+        let end = [x + item.length, y + item.width, z + item.height];
+        let size = [box_data.length, box_data.width, box_data.height];
+        let within = (0..3).all(|axis| start[axis] >= -CONTACT_EPSILON && end[axis] <= size[axis] + CONTACT_EPSILON);
+        let opening = box_data.opening_axis.index();
+        if !within {
+            report(
+                &[item],
+                format!(
+                    "item {} reaches outside the {}×{}×{} box",
+                    describe(item),
+                    box_data.length,
+                    box_data.width,
+                    box_data.height
+                ),
+            );
+        } else if end[opening] > size[opening] - box_data.clearance()[opening] + CONTACT_EPSILON {
+            report(
+                &[item],
+                format!(
+                    "item {} crosses the opening plane, {} inside the box's end along {:?}",
+                    describe(item),
+                    box_data.clearance()[opening],
+                    box_data.opening_axis
+                ),
+            );
+        } else if BoundsMode::fixed(box_data).extent(box_data, item, Position::new(x, y, z)).is_none() {
+            // The box is checked as built, so it does not grow
+            report(&[item], format!("item {} reaches into the headspace", describe(item)));
+        }
+    }
+
+    for (i, a) in box_data.items.iter().enumerate() {
+        for b in &box_data.items[i + 1..] {
+            if overlap(a, b) {
+                report(&[a, b], format!("items {} and {} overlap", describe(a), describe(b)));
+            }
+        }
+    }
+
+    if options.check_unpacking {
+        for (trapped, above) in trapped_items(box_data) {
+            let (trapped, above) = (&box_data.items[trapped], &box_data.items[above]);
+            report(
+                &[trapped, above],
+                format!("item {} can't be lifted out, {} is still over it", trapped.id, above.id),
+            );
+        }
+    }
+
+    for message in options.hazmat_rules.violations(box_data) {
+        report(&[], message);
+    }
+
+    if !box_fits_constraints(box_data, &box_constraints(box_data, options)) {
+        report(
+            &[],
+            format!(
+                "{}×{}×{} box of {} kg is outside the destination limits",
+                box_data.length, box_data.width, box_data.height, box_data.weight
+            ),
+        );
+    }
+}
+
+// Every violation in the solution; empty when it is sound. Expects the solution in cm and kg.
+pub fn validate_solution(solution: &PackingSolution, options: &PackingOptions) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    for (index, box_data) in solution.boxes.iter().enumerate() {
+        check_box(index, box_data, options, &mut violations);
+    }
+    violations
+}
@@ -0,0 +1,195 @@
+// Render-ready description of a packing solution for the 3D view
+use crate::{Item, PackedBox, PackingSolution, Position};
+use serde::{Deserialize, Serialize};
+
+// Axis-aligned bounds plus the values a camera needs to frame them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bounds {
+    pub min: (f64, f64, f64),
+    pub max: (f64, f64, f64),
+    pub center: (f64, f64, f64),
+    // Radius of the bounding sphere, for placing the camera far enough away
+    pub radius: f64,
+}
+
+impl Bounds {
+    fn from_extent(length: f64, width: f64, height: f64) -> Self {
+        Bounds {
+            min: (0.0, 0.0, 0.0),
+            max: (length, width, height),
+            center: (length / 2.0, width / 2.0, height / 2.0),
+            radius: (length * length + width * width + height * height).sqrt() / 2.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VisualItem {
+    pub id: String,
+    // Min-corner position in cm
+    pub position: (f64, f64, f64),
+    // Min-corner position as a fraction of the box dimensions (0..1)
+    pub normalized_position: (f64, f64, f64),
+    // Dimensions as placed, after rotation
+    pub dimensions: (f64, f64, f64),
+    // Placed dimensions as a fraction of the box dimensions (0..1)
+    pub normalized_dimensions: (f64, f64, f64),
+    pub original_dimensions: (f64, f64, f64),
+    pub rotation: u8,
+    pub category: Option<String>,
+    // CSS hex color, one per category, otherwise stable for the same item id across runs
+    pub color: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryColor {
+    pub category: String,
+    pub color: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VisualBox {
+    pub box_index: usize,
+    pub destination: String,
+    pub dimensions: (f64, f64, f64),
+    pub bounds: Bounds,
+    pub items: Vec<VisualItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VisualizationPayload {
+    pub boxes: Vec<VisualBox>,
+    // Bounds enclosing the largest box, so switching boxes keeps a consistent camera
+    pub bounds: Bounds,
+    // Legend for the categories in the solution, sorted by name
+    pub categories: Vec<CategoryColor>,
+}
+
+// 64-bit FNV-1a, chosen because it is stable across platforms and releases
+fn fnv1a(key: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in key.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+// Map a key to a pleasant, well-saturated color
+pub fn color_for_key(key: &str) -> String {
+    let hash = fnv1a(key);
+    let hue = (hash % 360) as f64;
+    let saturation = 0.55 + ((hash >> 16) % 20) as f64 / 100.0;
+    let lightness = 0.50 + ((hash >> 32) % 15) as f64 / 100.0;
+
+    // HSL to RGB
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let sector = hue / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    let channel = |value: f64| ((value + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+    format!("#{:02x}{:02x}{:02x}", channel(r), channel(g), channel(b))
+}
+
+// Category keys are prefixed so a category never shares a color with an item whose id happens
+// to be the same text
+fn category_color(category: &str) -> String {
+    color_for_key(&format!("category:{}", category))
+}
+
+fn item_color(item: &Item) -> String {
+    match &item.category {
+        Some(category) => category_color(category),
+        None => color_for_key(&item.id),
+    }
+}
+
+// Divide, mapping a zero-sized box axis to 0 instead of producing NaN
+fn fraction(value: f64, total: f64) -> f64 {
+    if total > 0.0 {
+        value / total
+    } else {
+        0.0
+    }
+}
+
+fn visual_box(box_index: usize, packed_box: &PackedBox) -> VisualBox {
+    let (length, width, height) = (packed_box.length, packed_box.width, packed_box.height);
+    let items = packed_box
+        .items
+        .iter()
+        .filter_map(|item| {
+            let Position { x, y, z } = item.position?;
+            Some(VisualItem {
+                id: item.id.clone(),
+                position: (x, y, z),
+                normalized_position: (fraction(x, length), fraction(y, width), fraction(z, height)),
+                dimensions: (item.length, item.width, item.height),
+                normalized_dimensions: (
+                    fraction(item.length, length),
+                    fraction(item.width, width),
+                    fraction(item.height, height),
+                ),
+                original_dimensions: item.unrotated_dimensions().into(),
+                rotation: item.rotation,
+                category: item.category.clone(),
+                color: item_color(item),
+            })
+        })
+        .collect();
+
+    VisualBox {
+        box_index,
+        destination: packed_box.destination.clone(),
+        dimensions: (length, width, height),
+        bounds: Bounds::from_extent(length, width, height),
+        items,
+    }
+}
+
+pub fn build_payload(solution: &PackingSolution) -> VisualizationPayload {
+    let boxes: Vec<VisualBox> = solution
+        .boxes
+        .iter()
+        .enumerate()
+        .map(|(i, packed_box)| visual_box(i, packed_box))
+        .collect();
+
+    let (length, width, height) = solution.boxes.iter().fold((0.0, 0.0, 0.0), |acc, b| {
+        (
+            f64::max(acc.0, b.length),
+            f64::max(acc.1, b.width),
+            f64::max(acc.2, b.height),
+        )
+    });
+
+    let mut names: Vec<&str> = solution
+        .boxes
+        .iter()
+        .flat_map(|b| b.items.iter())
+        .filter_map(|item| item.category.as_deref())
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+    let categories = names
+        .into_iter()
+        .map(|category| CategoryColor {
+            category: category.to_string(),
+            color: category_color(category),
+        })
+        .collect();
+
+    VisualizationPayload {
+        boxes,
+        bounds: Bounds::from_extent(length, width, height),
+        categories,
+    }
+}
@@ -0,0 +1,111 @@
+// Wall building: fill a vertical wall across the box's width and height, then start the next behind it
+use crate::layers::Shelves;
+use crate::{box_fits_constraints, fits_constraints, overhang_ok, stack_limits_ok, stats, DestinationConstraints, Item, PackedBox, PackingOptions, Position};
+
+// Items shallower than this share of the wall depth waste too much of it and wait for a later wall
+const MIN_DEPTH_RATIO: f64 = 0.7;
+
+// Orientation lying as deep as possible without exceeding `max_depth`, if the destination accepts one
+fn deepest_orientation(item: &Item, max_depth: f64, constraints: &DestinationConstraints) -> Option<Item> {
+    item.allowed_rotations().into_iter()
+        .map(|rot| item.with_rotation(rot))
+        .filter(|rotated| rotated.length <= max_depth && fits_constraints(rotated, constraints))
+        .max_by(|a, b| a.length.total_cmp(&b.length).then(b.width.total_cmp(&a.width)))
+}
+
+// The box with the item added, if it stays within the limits
+fn try_add(
+    box_data: &PackedBox,
+    item: Item,
+    position: Position,
+    constraints: &DestinationConstraints,
+    options: &PackingOptions,
+) -> Option<PackedBox> {
+    if !overhang_ok(box_data, &item, position, options) || !stack_limits_ok(box_data, &item, position) {
+        return None;
+    }
+    let mut trial = box_data.clone();
+    trial.add_item(item, position);
+    box_fits_constraints(&trial, constraints).then_some(trial)
+}
+
+// Fill one wall at depth offset `x`; returns its depth, or None if not even the first item fits
+fn build_wall(
+    box_data: &mut PackedBox,
+    pending: &mut Vec<Item>,
+    x: f64,
+    constraints: &DestinationConstraints,
+    options: &PackingOptions,
+) -> Option<f64> {
+    let (max_length, max_width, max_height) = constraints.axis_limits();
+
+    // The first pending item sets the wall depth
+    let first = deepest_orientation(&pending[0], max_length - x, constraints)?;
+    let depth = first.length;
+    let mut shelves = Shelves::new((max_width, max_height));
+    let (y, z) = shelves.place(first.width, first.height)?;
+    *box_data = try_add(box_data, first, Position::new(x, y, z), constraints, options)?;
+    pending.remove(0);
+
+    let mut index = 0;
+    while index < pending.len() {
+        let placed = deepest_orientation(&pending[index], depth, constraints)
+            .filter(|oriented| oriented.length >= MIN_DEPTH_RATIO * depth)
+            .and_then(|oriented| {
+                let mut trial_shelves = shelves.clone();
+                let (y, z) = trial_shelves.place(oriented.width, oriented.height)?;
+                let grown = try_add(box_data, oriented, Position::new(x, y, z), constraints, options)?;
+                Some((grown, trial_shelves))
+            });
+
+        match placed {
+            Some((grown, trial_shelves)) => {
+                *box_data = grown;
+                shelves = trial_shelves;
+                pending.remove(index);
+            }
+            None => index += 1,
+        }
+    }
+
+    Some(depth)
+}
+
+// Pack items in walls, pushing the boxes onto `boxes`, and return the items no wall could take
+pub fn pack_walls(
+    items: Vec<Item>,
+    constraints: &DestinationConstraints,
+    options: &PackingOptions,
+    boxes: &mut Vec<PackedBox>,
+) -> Vec<Item> {
+    // Catalog cartons have fixed sizes; walls are laid out against the destination limits, past a
+    // footprint locked to the first item
+    if !options.box_catalog.is_empty() || options.lock_footprint_after_first_item {
+        return items;
+    }
+
+    // Liquids must stand on the floor, which a wall stacked up in shelves doesn't guarantee
+    let (mut leftovers, mut pending): (Vec<Item>, Vec<Item>) = items.into_iter().partition(|item| item.is_liquid);
+
+    while !pending.is_empty() {
+        let mut box_data = PackedBox::cut(&pending[0].destination, pending[0].service.as_deref(), options);
+
+        let mut x = 0.0;
+        while !pending.is_empty() {
+            match build_wall(&mut box_data, &mut pending, x, constraints, options) {
+                Some(depth) => x += depth,
+                None => break,
+            }
+        }
+
+        if box_data.items.is_empty() {
+            // Not even a fresh box takes it
+            leftovers.push(pending.remove(0));
+        } else {
+            boxes.push(box_data);
+            stats::record(|stats| stats.boxes_opened += 1);
+        }
+    }
+
+    leftovers
+}
@@ -0,0 +1,29 @@
+// JavaScript bindings for the web preview, built with the wasm feature for wasm32-unknown-unknown.
+// JSON in and out, the same shapes the desktop app's pack_items command takes and returns, so both
+// produce the same solution for the same request.
+use crate::{try_pack_with_options, Item, PackError, PackingOptions};
+use wasm_bindgen::prelude::*;
+
+fn invalid_json(what: &str, error: serde_json::Error) -> JsValue {
+    let error = PackError::InvalidOption {
+        field: what.to_string(),
+        reason: format!("is not valid JSON: {}", error),
+    };
+    JsValue::from_str(&serde_json::to_string(&error).unwrap_or_default())
+}
+
+// Pack the items (a JSON array of Item) with the options (a JSON PackingOptions object; empty for
+// the defaults) and return the PackingSolution as JSON. A request that can't be packed throws the
+// PackError as JSON, `{ "kind": ... }`.
+#[wasm_bindgen]
+pub fn pack_items(items_json: &str, options_json: &str) -> Result<String, JsValue> {
+    let items: Vec<Item> = serde_json::from_str(items_json).map_err(|error| invalid_json("items", error))?;
+    let options: PackingOptions = if options_json.trim().is_empty() {
+        PackingOptions::default()
+    } else {
+        serde_json::from_str(options_json).map_err(|error| invalid_json("options", error))?
+    };
+    let solution = try_pack_with_options(items, &options)
+        .map_err(|error| JsValue::from_str(&serde_json::to_string(&error).unwrap_or_default()))?;
+    serde_json::to_string(&solution).map_err(|error| JsValue::from_str(&error.to_string()))
+}
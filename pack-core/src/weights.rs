@@ -0,0 +1,59 @@
+// Packing by weight alone, for items whose entered sizes can't be trusted (Item.dimensions_unreliable):
+// first fit decreasing on weight against the destination's weight limit, into boxes of their own
+// that hold nothing else. The items get no position and the boxes no size, as there is no geometry
+// to go by; the value cap, the hazmat rules and the reserved items are still held to.
+use crate::{
+    dunnage_weight, reservation, stats, within_value_cap, DestinationConstraints, Item, PackedBox, PackingOptions, UnpackedReason,
+};
+
+// Pack the items into new boxes pushed onto `boxes`, returning the ones no box can take and why
+pub(crate) fn pack_by_weight(
+    mut items: Vec<Item>,
+    constraints: &DestinationConstraints,
+    margined: &DestinationConstraints,
+    options: &PackingOptions,
+    boxes: &mut Vec<PackedBox>,
+) -> Vec<(Item, UnpackedReason)> {
+    // Heaviest first; the sort is stable, so equal weights keep their packing order
+    items.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+    let reserved = reservation(options).unwrap_or_default();
+    // Only a per box allowance is known without a box volume
+    let dunnage = dunnage_weight(options.dunnage.as_ref(), (0.0, 0.0, 0.0), 0.0);
+    let first = boxes.len();
+    let mut rejected = Vec::new();
+
+    for item in items {
+        if options.hazmat_rules.refuses(&item, &item.destination) {
+            rejected.push((item, UnpackedReason::HazmatRestricted));
+            continue;
+        }
+        if options.insurance_cap_per_box.is_some_and(|cap| item.declared_value() > cap) {
+            rejected.push((item, UnpackedReason::OverInsuranceCap));
+            continue;
+        }
+        // Like a box's first item, one on its own may go up to the limit itself
+        let alone = item.weight + reserved.weight + dunnage;
+        if alone > constraints.max_box_weight || !within_value_cap(item.declared_value() + reserved.value, constraints) {
+            rejected.push((item, UnpackedReason::ExceedsLimits));
+            continue;
+        }
+
+        let fits = |box_data: &PackedBox| {
+            box_data.weight + item.weight <= margined.max_box_weight &&
+            within_value_cap(box_data.value + box_data.reserved_value() + item.declared_value(), constraints) &&
+            options.hazmat_rules.allows(box_data, &item)
+        };
+        match boxes[first..].iter().position(fits) {
+            Some(index) => boxes[first + index].add_unplaced(item),
+            None => {
+                let mut box_data = PackedBox::cut(&item.destination, item.service.as_deref(), options);
+                box_data.weight_only = true;
+                box_data.add_unplaced(item);
+                boxes.push(box_data);
+                stats::record(|stats| stats.boxes_opened += 1);
+            }
+        }
+    }
+
+    rejected
+}
@@ -1,406 +1,389 @@
-// Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
+// Tauri layer of the app: the packing engine (pack-core) behind the commands the frontend invokes,
+// plus the state they share. Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 use serde::{Deserialize, Serialize};
-use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+use tauri::Manager;
 
-// Constants for outer box parameters
-const CARDBOARD_THICKNESS_CM: f64 = 0.6;
-const CARDBOARD_WEIGHT_KG_PER_SQM: f64 = 0.54;
+pub use pack_core::*;
 
-// Destination constraints
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DestinationConstraints {
-    pub max_box_dimension: f64,     // Maximum dimension for any side of the box
-    pub max_box_weight: f64,        // Maximum weight of a filled box
-    pub alternative_dimensions: Option<(f64, f64, f64)>, // For special cases like Japan (length, width, height)
-}
+// Persisted settings, used as defaults for requests without options
+pub struct SettingsState(pub Mutex<settings::AppSettings>);
 
-// Item dimensions
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Item {
-    pub id: String,
-    pub destination: String,
-    pub length: f64,
-    pub width: f64,
-    pub height: f64,
-    pub weight: f64,
-    // For tracking placement in solution
-    pub position: Option<(f64, f64, f64)>,
-    pub box_index: Option<usize>,
+fn settings_path(app: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(settings::SETTINGS_FILE))
+        .map_err(|e| format!("App data directory unavailable: {}", e))
 }
 
-impl Item {
-    pub fn volume(&self) -> f64 {
-        self.length * self.width * self.height
-    }
-
-    // Create a rotated copy of the item (swapping dimensions)
-    pub fn with_rotation(&self, rotation: usize) -> Self {
-        // Six possible orientations: (l,w,h), (l,h,w), (w,l,h), (w,h,l), (h,l,w), (h,w,l)
-        let (length, width, height) = match rotation {
-            0 => (self.length, self.width, self.height), // Original
-            1 => (self.length, self.height, self.width), // Rotate around x-axis
-            2 => (self.width, self.length, self.height), // Rotate around y-axis
-            3 => (self.width, self.height, self.length), // Rotate around x and y
-            4 => (self.height, self.length, self.width), // Rotate around x and z
-            5 => (self.height, self.width, self.length), // Rotate around z-axis
-            _ => (self.length, self.width, self.height), // Default to original
-        };
-
-        Item {
-            id: self.id.clone(),
-            destination: self.destination.clone(),
-            length,
-            width,
-            height,
-            weight: self.weight,
-            position: None,
-            box_index: None,
-        }
+// The loaded destination config, handed to every request, and how loading it went for the UI
+pub struct DestinationConfigState(pub RwLock<config::DestinationConfig>);
+
+// Load destinations.json from the app config directory, logging why it couldn't be
+fn load_destination_config(app: &tauri::AppHandle) -> config::DestinationConfig {
+    let loaded = match app.path().app_config_dir() {
+        Ok(dir) => config::load_destination_config(&dir.join(config::DESTINATION_CONFIG_FILE)),
+        Err(error) => config::DestinationConfig {
+            status: config::DestinationConfigStatus {
+                error: Some(format!("App config directory unavailable: {}", error)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    };
+    if let Some(error) = &loaded.status.error {
+        eprintln!("{}", error);
     }
+    loaded
 }
 
-// Packed box with items
+// Constraint table in effect together with where it came from
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PackedBox {
-    pub items: Vec<Item>,
-    pub length: f64,
-    pub width: f64,
-    pub height: f64,
-    pub weight: f64,
-    pub destination: String,
+pub struct ActiveConstraints {
+    pub destinations: HashMap<String, DestinationConstraints>,
+    pub config: config::DestinationConfigStatus,
 }
 
-impl PackedBox {
-    pub fn new(destination: &str) -> Self {
-        PackedBox {
-            items: Vec::new(),
-            length: 0.0,
-            width: 0.0,
-            height: 0.0,
-            weight: 0.0,
-            destination: destination.to_string(),
+// Define commands in a separate module to avoid name conflicts
+pub mod commands {
+    use super::*;
+    use tauri::Emitter;
+
+    // Options of the request, else the saved settings, with the loaded destination config
+    fn options_or_saved(options: Option<PackingOptions>, settings: &SettingsState, config: &DestinationConfigState) -> PackingOptions {
+        let mut options = options.unwrap_or_else(|| {
+            settings
+                .0
+                .lock()
+                .map(|saved| saved.options.clone())
+                .unwrap_or_default()
+        });
+        if let Ok(config) = config.0.read() {
+            options.configured_destinations = config.table.clone();
         }
+        options
     }
 
-    pub fn volume(&self) -> f64 {
-        self.length * self.width * self.height
+    #[tauri::command]
+    pub fn pack_items(
+        items: Vec<Item>,
+        options: Option<PackingOptions>,
+        settings: tauri::State<'_, SettingsState>,
+        config: tauri::State<'_, DestinationConfigState>,
+    ) -> Result<PackingSolution, PackError> {
+        let options = options_or_saved(options, &settings, &config);
+        try_pack_with_options(items, &options)
     }
 
-    pub fn add_item(&mut self, mut item: Item, position: (f64, f64, f64)) -> bool {
-        // Calculate new dimensions after adding the item
-        let new_length = (position.0 + item.length).max(self.length);
-        let new_width = (position.1 + item.width).max(self.width);
-        let new_height = (position.2 + item.height).max(self.height);
-
-        // Update item with position and box information
-        item.position = Some(position);
-        item.box_index = Some(self.items.len());
-
-        // Store item weight before pushing to items vector
-        let item_weight = item.weight;
-        self.items.push(item);
-
-        // Update box dimensions and weight
-        self.length = new_length;
-        self.width = new_width;
-        self.height = new_height;
-        self.weight += item_weight;
-
-        // Calculate box weight including cardboard
-        self.update_box_weight();
-
-        true
+    // Pack with every strategy so the operator can pick the best result
+    #[tauri::command]
+    pub fn compare_strategies(
+        items: Vec<Item>,
+        options: Option<PackingOptions>,
+        settings: tauri::State<'_, SettingsState>,
+        config: tauri::State<'_, DestinationConfigState>,
+    ) -> Result<Vec<StrategyComparison>, PackError> {
+        let options = options_or_saved(options, &settings, &config);
+        crate::compare_strategies(items, &options)
     }
 
-    fn update_box_weight(&mut self) {
-        // Calculate box surface area in square meters
-        let length_m = (self.length + 2.0 * CARDBOARD_THICKNESS_CM) / 100.0;
-        let width_m = (self.width + 2.0 * CARDBOARD_THICKNESS_CM) / 100.0;
-        let height_m = (self.height + 2.0 * CARDBOARD_THICKNESS_CM) / 100.0;
-
-        // Calculate box surface area (2 * (lw + lh + wh))
-        let surface_area = 2.0 * (length_m * width_m + length_m * height_m + width_m * height_m);
-
-        // Calculate box weight
-        let box_weight = surface_area * CARDBOARD_WEIGHT_KG_PER_SQM;
-
-        // Total weight = items weight + box weight
-        let items_weight: f64 = self.items.iter().map(|item| item.weight).sum();
-        self.weight = items_weight + box_weight;
+    // Strategy and objective runs nothing else beats on box count, volume and cost, for the tradeoff plot
+    #[tauri::command]
+    pub fn utilization_frontier(
+        items: Vec<Item>,
+        options: Option<PackingOptions>,
+        settings: tauri::State<'_, SettingsState>,
+        config: tauri::State<'_, DestinationConfigState>,
+    ) -> Result<Vec<frontier::FrontierPoint>, PackError> {
+        let options = options_or_saved(options, &settings, &config);
+        frontier::utilization_frontier(items, &options)
     }
 
-    // Calculate the smallest face area
-    pub fn smallest_face_area(&self) -> f64 {
-        let face1 = self.length * self.width;
-        let face2 = self.length * self.height;
-        let face3 = self.width * self.height;
+    // Pack at every candidate quality to see what the slower levels buy for these items
+    #[tauri::command]
+    pub fn tune_quality(
+        items: Vec<Item>,
+        options: Option<PackingOptions>,
+        settings: tauri::State<'_, SettingsState>,
+        config: tauri::State<'_, DestinationConfigState>,
+    ) -> Result<Vec<QualityReport>, PackError> {
+        let options = options_or_saved(options, &settings, &config);
+        crate::tune_quality(items, &options)
+    }
 
-        face1.min(face2).min(face3)
+    // Independent shipments in one call, results under the caller's batch names
+    #[tauri::command]
+    pub fn pack_batches(
+        batches: Vec<(String, Vec<Item>)>,
+        options: Option<PackingOptions>,
+        settings: tauri::State<'_, SettingsState>,
+        config: tauri::State<'_, DestinationConfigState>,
+    ) -> Vec<(String, Result<PackingSolution, PackError>)> {
+        let options = options_or_saved(options, &settings, &config);
+        crate::pack_batches(batches, &options)
     }
 
-    // Calculate total surface area
-    pub fn surface_area(&self) -> f64 {
-        2.0 * (
-            self.length * self.width +
-            self.length * self.height +
-            self.width * self.height
-        )
+    // Drop an item into a built box by hand, checked against the box's own interior
+    #[tauri::command]
+    pub fn place_item(
+        solution: PackingSolution,
+        box_index: usize,
+        item: Item,
+        position: Position,
+        options: Option<PackingOptions>,
+        settings: tauri::State<'_, SettingsState>,
+        config: tauri::State<'_, DestinationConfigState>,
+    ) -> Result<PackingSolution, PackError> {
+        let options = options_or_saved(options, &settings, &config);
+        crate::place_item(solution, box_index, item, position, &options)
     }
-}
 
-// Packing solution
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PackingSolution {
-    pub boxes: Vec<PackedBox>,
-    pub total_volume: f64,
-    pub unpacked_items: Vec<Item>,
-}
+    // Items outside their box, overlaps and boxes over their limits; empty when the solution is sound
+    #[tauri::command]
+    pub fn validate_solution(
+        solution: PackingSolution,
+        options: Option<PackingOptions>,
+        settings: tauri::State<'_, SettingsState>,
+        config: tauri::State<'_, DestinationConfigState>,
+    ) -> Vec<verify::Violation> {
+        let options = options_or_saved(options, &settings, &config);
+        crate::validate_solution(solution, &options)
+    }
 
-// Get destination constraints
-fn get_destination_constraints(destination: &str) -> DestinationConstraints {
-    match destination {
-        "Australia" | "USA" => DestinationConstraints {
-            max_box_dimension: 63.0,
-            max_box_weight: 22.0,
-            alternative_dimensions: None,
-        },
-        "UK" => DestinationConstraints {
-            max_box_dimension: 63.0,
-            max_box_weight: 15.0,
-            alternative_dimensions: None,
-        },
-        "Germany" => DestinationConstraints {
-            max_box_dimension: 63.0,
-            max_box_weight: 22.5,
-            alternative_dimensions: None,
-        },
-        "Japan" => DestinationConstraints {
-            max_box_dimension: 60.0,
-            max_box_weight: 40.0,
-            alternative_dimensions: Some((60.0, 50.0, 50.0)),
-        },
-        _ => DestinationConstraints {
-            max_box_dimension: 63.0,
-            max_box_weight: 22.0,
-            alternative_dimensions: None,
-        },
+    // Baseline and hypothetical-limit packings of the same items, for the comparison table
+    #[tauri::command]
+    pub fn what_if(
+        items: Vec<Item>,
+        scenarios: Vec<scenarios::ScenarioOverride>,
+        options: Option<PackingOptions>,
+        settings: tauri::State<'_, SettingsState>,
+        config: tauri::State<'_, DestinationConfigState>,
+    ) -> Result<Vec<scenarios::ScenarioResult>, PackError> {
+        let options = options_or_saved(options, &settings, &config);
+        scenarios::what_if(items, scenarios, &options)
     }
-}
 
-// Check if an item fits within destination constraints
-fn fits_constraints(item: &Item, constraints: &DestinationConstraints) -> bool {
-    if let Some((max_length, max_width, max_height)) = constraints.alternative_dimensions {
-        // Special case for destinations with specific dimension constraints (like Japan)
-        item.length <= max_length &&
-        item.width <= max_width &&
-        item.height <= max_height &&
-        item.weight <= constraints.max_box_weight
-    } else {
-        // Standard case
-        item.length <= constraints.max_box_dimension &&
-        item.width <= constraints.max_box_dimension &&
-        item.height <= constraints.max_box_dimension &&
-        item.weight <= constraints.max_box_weight
+    // Pack a large import in the background; progress arrives as jobs::PROGRESS_EVENT events
+    #[tauri::command]
+    pub fn start_packing_job(
+        app: tauri::AppHandle,
+        items: Vec<Item>,
+        options: Option<PackingOptions>,
+        settings: tauri::State<'_, SettingsState>,
+        config: tauri::State<'_, DestinationConfigState>,
+        jobs: tauri::State<'_, jobs::JobsState>,
+    ) -> Result<String, PackError> {
+        let options = options_or_saved(options, &settings, &config);
+        jobs.start(items, options, move |progress| {
+            let _ = app.emit(jobs::PROGRESS_EVENT, progress.clone());
+        })
     }
-}
 
-// Check if item can be placed at the position without collision
-fn can_place_item(box_data: &PackedBox, item: &Item, position: (f64, f64, f64)) -> bool {
-    let (x, y, z) = position;
+    // Summary and one page of boxes of a packing job; None for an unknown job
+    #[tauri::command]
+    pub fn get_job_result(
+        job_id: String,
+        page: usize,
+        page_size: Option<usize>,
+        jobs: tauri::State<'_, jobs::JobsState>,
+    ) -> Option<jobs::JobPage> {
+        jobs.page(&job_id, page, page_size)
+    }
 
-    // Check if the item fits within box constraints
-    let constraints = get_destination_constraints(&box_data.destination);
+    // Drop a job's result, stopping it if it is still running
+    #[tauri::command]
+    pub fn discard_packing_job(job_id: String, jobs: tauri::State<'_, jobs::JobsState>) -> bool {
+        jobs.discard(&job_id)
+    }
 
-    // Check if item extends beyond the maximum allowed dimensions
-    if let Some((max_length, max_width, max_height)) = constraints.alternative_dimensions {
-        if x + item.length > max_length ||
-           y + item.width > max_width ||
-           z + item.height > max_height {
-            return false;
-        }
-    } else if x + item.length > constraints.max_box_dimension ||
-              y + item.width > constraints.max_box_dimension ||
-              z + item.height > constraints.max_box_dimension {
-        return false;
-    }
-
-    // Check for collisions with existing items
-    for existing_item in &box_data.items {
-        if let Some((ex, ey, ez)) = existing_item.position {
-            // Check if the new item overlaps with any existing item
-            if !(x + item.length <= ex ||
-                 ex + existing_item.length <= x ||
-                 y + item.width <= ey ||
-                 ey + existing_item.width <= y ||
-                 z + item.height <= ez ||
-                 ez + existing_item.height <= z) {
-                return false;
-            }
-        }
+    // Queue a whole packing request behind the others; poll it with job_status
+    #[tauri::command]
+    pub fn enqueue_pack_job(
+        items: Vec<Item>,
+        options: Option<PackingOptions>,
+        settings: tauri::State<'_, SettingsState>,
+        config: tauri::State<'_, DestinationConfigState>,
+        queue: tauri::State<'_, queue::QueueState>,
+    ) -> Result<String, PackError> {
+        let options = options_or_saved(options, &settings, &config);
+        queue.enqueue(items, options)
     }
 
-    // Check if total weight would exceed maximum
-    box_data.weight + item.weight <= constraints.max_box_weight
-}
+    // None for an unknown job, or one whose result was fetched or cleared
+    #[tauri::command]
+    pub fn job_status(job_id: String, queue: tauri::State<'_, queue::QueueState>) -> Option<queue::JobStatus> {
+        queue.status(&job_id)
+    }
 
-// Find the best position to place an item in a box
-fn find_best_position(box_data: &PackedBox, item: &Item) -> Option<(f64, f64, f64)> {
-    // For an empty box, place at origin
-    if box_data.items.is_empty() {
-        return Some((0.0, 0.0, 0.0));
+    // The solution of a done job; the job is forgotten once fetched
+    #[tauri::command]
+    pub fn job_result(job_id: String, queue: tauri::State<'_, queue::QueueState>) -> Option<PackingSolution> {
+        queue.take_result(&job_id)
     }
 
-    // Get all extreme points (candidates for placement)
-    let mut candidates = Vec::with_capacity(box_data.items.len() * 3 + 1);
+    #[tauri::command]
+    pub fn cancel_job(job_id: String, queue: tauri::State<'_, queue::QueueState>) -> bool {
+        queue.cancel(&job_id)
+    }
 
-    // Add (0,0,0) as a candidate
-    candidates.push((0.0, 0.0, 0.0));
+    // Drop a queued job's status or result, cancelling it if it has not ended
+    #[tauri::command]
+    pub fn clear_job(job_id: String, queue: tauri::State<'_, queue::QueueState>) -> bool {
+        queue.clear(&job_id)
+    }
 
-    // Add extreme points based on existing items
-    for existing_item in &box_data.items {
-        if let Some((ex, ey, ez)) = existing_item.position {
-            // Point to the right of the item
-            candidates.push((ex + existing_item.length, ey, ez));
+    #[tauri::command]
+    pub fn set_job_concurrency(concurrency: usize, queue: tauri::State<'_, queue::QueueState>) {
+        queue.set_concurrency(concurrency);
+    }
 
-            // Point to the front of the item
-            candidates.push((ex, ey + existing_item.width, ez));
+    // Reproducible random items for demos and stress tests
+    #[tauri::command]
+    pub fn generate_test_items(spec: generator::GeneratorSpec) -> Vec<Item> {
+        generator::generate_items(&spec)
+    }
 
-            // Point on top of the item
-            candidates.push((ex, ey, ez + existing_item.height));
+    #[tauri::command]
+    pub fn save_settings(
+        app: tauri::AppHandle,
+        state: tauri::State<'_, SettingsState>,
+        settings: settings::AppSettings,
+    ) -> Result<(), String> {
+        settings::save_settings_file(&settings_path(&app)?, &settings)?;
+        if let Ok(mut current) = state.0.lock() {
+            *current = settings;
         }
+        Ok(())
     }
 
-    // Sort candidates by the sum of coordinates (prefer closer to origin)
-    candidates.sort_by(|a, b| {
-        let sum_a = a.0 + a.1 + a.2;
-        let sum_b = b.0 + b.1 + b.2;
-        sum_a.partial_cmp(&sum_b).unwrap_or(Ordering::Equal)
-    });
-
-    // Try each candidate position
-    candidates.into_iter().find(|&pos| can_place_item(box_data, item, pos))
-}
-
-// Find the best position and rotation to place an item in a box
-fn find_best_position_with_rotation(box_data: &PackedBox, item: &Item) -> Option<((f64, f64, f64), Item)> {
-    let mut best_placement: Option<((f64, f64, f64), Item)> = None;
-    let mut smallest_resulting_surface_area = f64::MAX;
-
-    // Try all six possible rotations of the item
-    for rotation in 0..6 {
-        let rotated_item = item.with_rotation(rotation);
-
-        // Skip if this rotation violates constraints
-        let constraints = get_destination_constraints(&box_data.destination);
-        if !fits_constraints(&rotated_item, &constraints) {
-            continue;
+    #[tauri::command]
+    pub fn load_settings(app: tauri::AppHandle, state: tauri::State<'_, SettingsState>) -> settings::AppSettings {
+        let settings = settings_path(&app)
+            .map(|path| settings::load_settings_file(&path))
+            .unwrap_or_default();
+        if let Ok(mut current) = state.0.lock() {
+            *current = settings.clone();
         }
+        settings
+    }
 
-        // Find the best position for this rotation
-        if let Some(position) = find_best_position(box_data, &rotated_item) {
-            // Create a temporary box copy to test this placement
-            let mut test_box = box_data.clone();
-            test_box.add_item(rotated_item.clone(), position);
-
-            // Calculate the resulting surface area
-            let surface_area = test_box.surface_area();
+    // Re-read destinations.json so edits take effect without restarting
+    #[tauri::command]
+    pub fn reload_destination_config(
+        app: tauri::AppHandle,
+        state: tauri::State<'_, DestinationConfigState>,
+    ) -> config::DestinationConfigStatus {
+        let loaded = load_destination_config(&app);
+        let status = loaded.status.clone();
+        if let Ok(mut current) = state.0.write() {
+            *current = loaded;
+        }
+        status
+    }
 
-            // Update best placement if this results in smaller surface area
-            if surface_area < smallest_resulting_surface_area {
-                smallest_resulting_surface_area = surface_area;
-                best_placement = Some((position, rotated_item));
-            }
+    #[tauri::command]
+    pub fn get_active_constraints(state: tauri::State<'_, DestinationConfigState>) -> ActiveConstraints {
+        let loaded = state.0.read().map(|loaded| loaded.clone()).unwrap_or_default();
+        ActiveConstraints {
+            destinations: active_destination_constraints(&loaded.table),
+            config: loaded.status,
         }
     }
 
-    best_placement
-}
+    // Render-ready boxes and items for the 3D view
+    #[tauri::command]
+    pub fn get_visualization(solution: PackingSolution) -> visualization::VisualizationPayload {
+        visualization::build_payload(&solution)
+    }
 
-// Main packing algorithm implementation
-fn pack_items_impl(items: Vec<Item>) -> PackingSolution {
-    // Group items by destination
-    let mut items_by_destination: HashMap<String, Vec<Item>> = HashMap::new();
+    // Top-down SVG diagram for each layer of one box in the solution
+    #[tauri::command]
+    pub fn export_layer_svgs(solution: PackingSolution, box_index: usize) -> Vec<String> {
+        solution
+            .boxes
+            .get(box_index)
+            .map(|packed_box| export::svg::render_layers(packed_box, box_index + 1))
+            .unwrap_or_default()
+    }
 
-    for item in items {
-        items_by_destination
-            .entry(item.destination.clone())
-            .or_insert_with(Vec::new)
-            .push(item);
+    // CN22 / commercial invoice rows for every box, numbers and values in the locale's format
+    #[tauri::command]
+    pub fn export_customs_csv(
+        solution: PackingSolution,
+        metadata_keys: Option<Vec<String>>,
+        locale: Option<String>,
+        currency: Option<String>,
+    ) -> String {
+        let format = export::format::NumberFormat::new(locale.as_deref(), currency.as_deref());
+        export::customs::customs_csv(&solution, &metadata_keys.unwrap_or_default(), &format)
     }
 
-    let mut solution = PackingSolution {
-        boxes: Vec::new(),
-        total_volume: 0.0,
-        unpacked_items: Vec::new(),
-    };
+    // What to pick for each box, with the grid layout where there is one
+    #[tauri::command]
+    pub fn export_pick_list(solution: PackingSolution) -> String {
+        export::pick_list::pick_list_csv(&solution)
+    }
 
-    // Process each destination separately
-    for (destination, mut destination_items) in items_by_destination {
-        // Sort items by volume (decreasing)
-        destination_items.sort_by(|a, b| {
-            b.volume().partial_cmp(&a.volume()).unwrap_or(Ordering::Equal)
-        });
+    // Numbered packing steps for one box as printable text, in English or Chinese
+    #[tauri::command]
+    pub fn export_instructions(
+        solution: PackingSolution,
+        box_index: usize,
+        language: Option<Language>,
+        locale: Option<String>,
+        currency: Option<String>,
+    ) -> String {
+        let format = export::format::NumberFormat::new(locale.as_deref(), currency.as_deref());
+        export::instructions::instructions(&solution, box_index, language.unwrap_or_default(), &format)
+    }
 
-        let mut boxes_for_destination: Vec<PackedBox> = Vec::new();
-        let mut unpacked = Vec::new();
-
-        // Process each item
-        for item in destination_items {
-            let constraints = get_destination_constraints(&destination);
-
-            // Check if the item itself is too large for constraints (in any orientation)
-            if !(0..6).any(|rot| fits_constraints(&item.with_rotation(rot), &constraints)) {
-                unpacked.push(item);
-                continue;
-            }
-
-            let mut placed = false;
-
-            // Try to place in existing boxes
-            for box_data in &mut boxes_for_destination {
-                if let Some((position, rotated_item)) = find_best_position_with_rotation(box_data, &item) {
-                    box_data.add_item(rotated_item, position);
-                    placed = true;
-                    break;
-                }
-            }
-
-            // If not placed, create a new box
-            if !placed {
-                let mut new_box = PackedBox::new(&destination);
-
-                // For a new box, try all rotations and pick the one that fits constraints
-                let (position, rotated_item) = (0..6)
-                    .map(|rot| (rot, item.with_rotation(rot)))
-                    .filter(|(_, rotated)| fits_constraints(rotated, &constraints))
-                    .next()
-                    .map(|(_, rotated)| ((0.0, 0.0, 0.0), rotated))
-                    .unwrap_or(((0.0, 0.0, 0.0), item.clone()));
-
-                new_box.add_item(rotated_item, position);
-                boxes_for_destination.push(new_box);
-            }
-        }
+    // Printable HTML packing slip for one box, ready for the print dialog
+    #[tauri::command]
+    pub fn export_packing_slip_html(
+        solution: PackingSolution,
+        box_index: usize,
+        locale: Option<String>,
+        currency: Option<String>,
+    ) -> String {
+        let format = export::format::NumberFormat::new(locale.as_deref(), currency.as_deref());
+        export::packing_slip::packing_slip_html(&solution, box_index, &format)
+    }
 
-        // Add to solution
-        solution.boxes.extend(boxes_for_destination);
-        solution.unpacked_items.extend(unpacked);
+    // Corrugate, cardboard, tape and void fill used by the boxes, per destination and overall
+    #[tauri::command]
+    pub fn material_report(solution: PackingSolution) -> materials::MaterialReport {
+        materials::material_report(&solution)
     }
 
-    // Calculate total volume
-    solution.total_volume = solution.boxes.iter().map(PackedBox::volume).sum();
+    // Stack the solution's boxes onto pallets, per destination
+    #[tauri::command]
+    pub fn palletize(solution: PackingSolution, pallet: pallet::PalletSpec) -> pallet::PalletPlan {
+        pallet::palletize(&solution, &pallet)
+    }
 
-    solution
-}
+    // Floor-load the solution's boxes into one container, to see whether the batch fits
+    #[tauri::command]
+    pub fn load_plan(solution: PackingSolution, container: container::ContainerSpec) -> container::LoadPlan {
+        container::load_plan(&solution, &container)
+    }
 
-// Define commands in a separate module to avoid name conflicts
-pub mod commands {
-    use super::*;
+    // Weighted score of a solution, lower is better; the same one the time-budgeted search minimizes
+    #[tauri::command]
+    pub fn score_solution(solution: PackingSolution, weights: score::ScoreWeights) -> Result<score::ScoreBreakdown, PackError> {
+        score::try_score_solution(&solution, &weights)
+    }
 
+    // One row per packed item with its box and placement, numbers and values in the locale's format
     #[tauri::command]
-    pub fn pack_items(items: Vec<Item>) -> PackingSolution {
-        pack_items_impl(items)
+    pub fn export_manifest_csv(
+        solution: PackingSolution,
+        metadata_keys: Option<Vec<String>>,
+        locale: Option<String>,
+        currency: Option<String>,
+    ) -> String {
+        let format = export::format::NumberFormat::new(locale.as_deref(), currency.as_deref());
+        export::manifest::manifest_csv(&solution, &metadata_keys.unwrap_or_default(), &format)
     }
 }
 
@@ -408,8 +391,51 @@ pub mod commands {
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .setup(|app| {
+            let loaded = load_destination_config(app.handle());
+            app.manage(DestinationConfigState(RwLock::new(loaded)));
+            let settings = settings_path(app.handle())
+                .map(|path| settings::load_settings_file(&path))
+                .unwrap_or_default();
+            app.manage(SettingsState(Mutex::new(settings)));
+            app.manage(jobs::JobsState::default());
+            app.manage(queue::QueueState::default());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
-            commands::pack_items
+            commands::pack_items,
+            commands::compare_strategies,
+            commands::utilization_frontier,
+            commands::tune_quality,
+            commands::what_if,
+            commands::pack_batches,
+            commands::place_item,
+            commands::validate_solution,
+            commands::start_packing_job,
+            commands::get_job_result,
+            commands::discard_packing_job,
+            commands::enqueue_pack_job,
+            commands::job_status,
+            commands::job_result,
+            commands::cancel_job,
+            commands::clear_job,
+            commands::set_job_concurrency,
+            commands::generate_test_items,
+            commands::save_settings,
+            commands::load_settings,
+            commands::reload_destination_config,
+            commands::get_active_constraints,
+            commands::get_visualization,
+            commands::export_layer_svgs,
+            commands::export_customs_csv,
+            commands::export_manifest_csv,
+            commands::material_report,
+            commands::palletize,
+            commands::load_plan,
+            commands::score_solution,
+            commands::export_pick_list,
+            commands::export_instructions,
+            commands::export_packing_slip_html
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");